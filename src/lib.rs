@@ -0,0 +1,113 @@
+//! Library half of `task-warrior-mcp`: helpers for building Taskwarrior command lines
+//! that are useful outside the MCP server binary.
+
+/// Composable Taskwarrior filter.
+///
+/// ```
+/// use task_warrior_mcp::FilterBuilder;
+///
+/// let filter = FilterBuilder::new().tag("urgent").priority("H").build();
+/// assert_eq!(filter, "+urgent priority:H");
+/// ```
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct FilterBuilder {
+    tokens: Vec<String>,
+}
+
+impl FilterBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// `project:<name>` — matches the project and its dot-notation subprojects.
+    pub fn project(self, name: &str) -> Self {
+        self.push(format!("project:{name}"))
+    }
+
+    /// `+<tag>`. A leading `+` in `tag` is ignored.
+    pub fn tag(self, tag: &str) -> Self {
+        self.push(format!("+{}", tag.trim_start_matches('+')))
+    }
+
+    /// `-<tag>`: excludes tasks carrying the tag. A leading `-` in `tag` is ignored.
+    pub fn not_tag(self, tag: &str) -> Self {
+        self.push(format!("-{}", tag.trim_start_matches('-')))
+    }
+
+    /// `priority:<p>` — `H`, `M`, `L`, or `""` for tasks without a priority.
+    pub fn priority(self, priority: &str) -> Self {
+        self.push(format!("priority:{priority}"))
+    }
+
+    /// `due.before:<date>`, using any Taskwarrior date expression.
+    pub fn due_before(self, date: &str) -> Self {
+        self.push(format!("due.before:{date}"))
+    }
+
+    /// `due.after:<date>`, using any Taskwarrior date expression.
+    pub fn due_after(self, date: &str) -> Self {
+        self.push(format!("due.after:{date}"))
+    }
+
+    /// `status:<s>` — pending, completed, deleted, waiting, or recurring.
+    pub fn status(self, status: &str) -> Self {
+        self.push(format!("status:{status}"))
+    }
+
+    /// Append a free-form filter string, split on whitespace.
+    pub fn raw(mut self, filter: &str) -> Self {
+        self.tokens
+            .extend(filter.split_whitespace().map(str::to_string));
+        self
+    }
+
+    /// The filter as one string, e.g. `"+urgent priority:H"`.
+    pub fn build(&self) -> String {
+        self.tokens.join(" ")
+    }
+
+    /// The filter as separate `task` arguments, ready to pass to a command.
+    pub fn into_args(self) -> Vec<String> {
+        self.tokens
+    }
+
+    fn push(mut self, token: String) -> Self {
+        self.tokens.push(token);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_filter_builder_tag_and_priority() {
+        assert_eq!(
+            FilterBuilder::new().tag("urgent").priority("H").build(),
+            "+urgent priority:H"
+        );
+    }
+
+    #[test]
+    fn test_filter_builder_composes_in_order() {
+        let filter = FilterBuilder::new()
+            .project("Work.Backend")
+            .status("pending")
+            .not_tag("-someday")
+            .due_after("today")
+            .due_before("eow")
+            .raw("  +READY   limit:5 ");
+        assert_eq!(
+            filter.build(),
+            "project:Work.Backend status:pending -someday due.after:today due.before:eow \
+             +READY limit:5"
+        );
+        assert_eq!(filter.into_args().len(), 7);
+    }
+
+    #[test]
+    fn test_filter_builder_empty() {
+        assert_eq!(FilterBuilder::new().build(), "");
+    }
+}
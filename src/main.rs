@@ -1,23 +1,32 @@
 use anyhow::Result;
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, Utc};
 use rmcp::{
-    handler::server::{router::tool::ToolRouter, wrapper::Parameters},
+    handler::server::{router::tool::ToolRouter, tool::ToolCallContext, wrapper::Parameters},
     model::*,
-    schemars, tool, tool_handler, tool_router,
+    schemars,
+    service::RequestContext,
+    tool, tool_router,
     transport::stdio,
-    ErrorData as McpError, ServerHandler, ServiceExt,
+    ErrorData as McpError, RoleServer, ServerHandler, ServiceExt,
 };
+use std::collections::HashMap;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use task_warrior_mcp::FilterBuilder;
 use tokio::process::Command;
+use tracing::Instrument;
 use tracing_subscriber::EnvFilter;
 
 // ── Parameter types ──────────────────────────────────────────────────────────
 
-#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize, schemars::JsonSchema)]
 struct AddTaskRequest {
     /// Task description
     description: String,
     /// Project this task belongs to (REQUIRED). Use dot-notation for subprojects, e.g. "Work.Backend".
     /// Every task must be filed under a project — untagged tasks pollute the global list.
+    /// Pass "auto" to file it under the name of the active Taskwarrior context.
     project: String,
     /// Due date/time: "today", "tomorrow", "eow", "eom", "friday", "2025-06-15", "2025-06-15T14:30"
     due: Option<String>,
@@ -31,7 +40,7 @@ struct AddTaskRequest {
     scheduled: Option<String>,
 }
 
-#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
 struct ListTasksRequest {
     /// Project to scope this query to (REQUIRED). Use dot-notation, e.g. "Work" or "Work.Backend".
     /// All list operations are project-scoped by default to avoid dumping thousands of unrelated
@@ -58,6 +67,22 @@ struct ListTasksRequest {
     /// Override project scoping and query ALL projects. Only use when the request is explicitly
     /// cross-project (e.g. "show me everything overdue across all projects").
     all_projects: Option<bool>,
+    /// Only tasks due after this date, e.g. "today", "yesterday", "2025-06-01"
+    due_after: Option<String>,
+    /// Only tasks due before this date, e.g. "eow", "today+7d", "2025-06-30"
+    due_before: Option<String>,
+    /// Only tasks created after this date, e.g. "today", "2025-06-01T09:00"
+    created_after: Option<String>,
+    /// Only tasks created before this date, e.g. "sow", "2025-06-01T09:00"
+    created_before: Option<String>,
+    /// Only tasks changed after this date — for incremental sync ("what changed since I last
+    /// checked?"), e.g. "now-10min", "2025-06-01T09:00"
+    modified_after: Option<String>,
+    /// Page number, starting at 1. Setting `page` or `page_size` switches the response to
+    /// JSON: {"tasks": [...], "total": N, "page": P, "pages": Q}, urgency-sorted.
+    page: Option<u32>,
+    /// Tasks per page (default 25)
+    page_size: Option<u32>,
 }
 
 #[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
@@ -91,673 +116,11756 @@ struct ModifyTaskRequest {
 struct AnnotateTaskRequest {
     /// Task ID (numeric) or UUID
     id: String,
-    /// Note text to attach; timestamped automatically by Taskwarrior
+    /// Single-line note text to attach; timestamped automatically by Taskwarrior.
+    /// Line breaks (real or "\n" escapes) are collapsed to spaces — use `note_lines` to keep them.
+    #[serde(default)]
     note: String,
+    /// Multi-paragraph note: lines are joined with real newlines and stored as ONE annotation.
+    /// When set, `note` (if non-empty) becomes the first line.
+    note_lines: Option<Vec<String>>,
 }
 
-// ── Server ────────────────────────────────────────────────────────────────────
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct ExportCsvRequest {
+    /// Project to scope the export to (REQUIRED). Set all_projects=true to export globally.
+    project: String,
+    /// Additional filter tokens to narrow results, e.g. "+READY priority:H"
+    filter: Option<String>,
+    /// Task attributes to emit as columns, in order. Defaults to
+    /// ["id", "uuid", "description", "project", "status", "priority", "due", "scheduled", "wait", "tags"].
+    /// Any exported attribute (including UDAs) may be named; absent values become empty cells.
+    columns: Option<Vec<String>>,
+    /// Override project scoping and export ALL projects.
+    all_projects: Option<bool>,
+}
 
-#[derive(Clone)]
-struct TaskWarriorServer {
-    tool_router: ToolRouter<TaskWarriorServer>,
-    /// Overrides the taskwarrior data directory. Used in tests for isolation.
-    data_dir: Option<PathBuf>,
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct ExportMarkdownRequest {
+    /// Project to export as a checklist (REQUIRED).
+    project: String,
+    /// Additional filter tokens to narrow results. Completed tasks are already included as
+    /// checked-off items; pass "status:pending" to list only open ones.
+    filter: Option<String>,
+    /// Append "(due: …, priority: …)" after each description when those attributes are set.
+    /// Defaults to false.
+    include_metadata: Option<bool>,
 }
 
-impl TaskWarriorServer {
-    async fn run(&self, args: &[&str]) -> Result<String, McpError> {
-        let mut cmd = Command::new("task");
-        cmd.arg("rc.confirmation=no");
-        if let Some(dir) = &self.data_dir {
-            cmd.arg(format!("rc.data.location={}", dir.display()));
-        }
-        cmd.args(args);
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct ImportMarkdownRequest {
+    /// Markdown checklist. Each `- [ ] description` line becomes a task; an optional trailing
+    /// "(due: tomorrow, priority: H)" group sets due/priority/scheduled/wait.
+    /// Checked (`- [x]`) items and non-checklist lines are skipped.
+    markdown: String,
+    /// Project every imported task is filed under (REQUIRED).
+    project: String,
+    /// Priority for items that don't specify one inline: H, M, or L
+    default_priority: Option<String>,
+}
 
-        let output = cmd
-            .output()
-            .await
-            .map_err(|e| McpError::internal_error(format!("Failed to run task: {e}"), None))?;
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct ExportIcalRequest {
+    /// Project to scope the export to (REQUIRED). Set all_projects=true to export globally.
+    project: String,
+    /// Additional filter tokens to narrow results, e.g. "due.before:eom"
+    filter: Option<String>,
+    /// Override project scoping and export ALL projects.
+    all_projects: Option<bool>,
+}
 
-        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct ImportJsonArrayRequest {
+    /// JSON array of task objects in Taskwarrior export format, e.g.
+    /// '[{"description": "Write docs", "project": "Work", "priority": "H"}]'.
+    /// Every element needs a non-empty "description"; others are skipped.
+    json_array: String,
+    /// Force every imported task into this project, replacing any "project" in the input.
+    project_override: Option<String>,
+}
 
-        if !output.status.success() && stdout.is_empty() {
-            return Err(McpError::internal_error(
-                if !stderr.is_empty() {
-                    stderr
-                } else {
-                    format!("task exited with status {}", output.status)
-                },
-                None,
-            ));
-        }
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct CompletedTasksRequest {
+    /// Project to scope this query to (REQUIRED). Set all_projects=true to query globally.
+    project: String,
+    /// Only tasks completed after this date, e.g. "today", "sow", "2025-06-01", "today-7d"
+    since: Option<String>,
+    /// Only tasks completed before this date, e.g. "tomorrow", "2025-06-30"
+    until_date: Option<String>,
+    /// Override project scoping and query ALL projects.
+    all_projects: Option<bool>,
+}
 
-        Ok(if !stdout.is_empty() { stdout } else { stderr })
-    }
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct RecurringTasksRequest {
+    /// Project to scope this query to (REQUIRED). Set all_projects=true to query globally.
+    project: String,
+    /// Override project scoping and query ALL projects.
+    all_projects: Option<bool>,
 }
 
-#[cfg(test)]
-impl TaskWarriorServer {
-    fn with_data_dir(dir: &std::path::Path) -> Self {
-        Self {
-            tool_router: Self::tool_router(),
-            data_dir: Some(dir.to_path_buf()),
-        }
-    }
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct EditAnnotationRequest {
+    /// Task ID (numeric) or UUID
+    id: String,
+    /// Text identifying the annotation(s) to replace; matches any annotation containing it
+    old_pattern: String,
+    /// Replacement annotation text
+    new_text: String,
 }
 
-#[tool_router]
-impl TaskWarriorServer {
-    fn new() -> Self {
-        Self {
-            tool_router: Self::tool_router(),
-            data_dir: None,
-        }
-    }
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct SuggestDueDateRequest {
+    /// Task description to scan for date cues, e.g. "finish report by friday"
+    description: String,
+    /// Task priority (H/M/L); used as a fallback hint when the description has no date cues
+    priority: Option<String>,
+}
 
-    #[tool(description = "\
-        Add a new task. `project` is REQUIRED — every task must belong to a project. \
-        Supports due dates (today/tomorrow/eow/eom/friday/ISO datetime), tags, \
-        dot-notation subprojects (e.g. Work.Backend), priorities (H/M/L), \
-        wait dates (hide until actionable), and scheduled dates (when you plan to start).")]
-    async fn add_task(
-        &self,
-        Parameters(req): Parameters<AddTaskRequest>,
-    ) -> Result<CallToolResult, McpError> {
-        let mut args = vec!["add".to_string(), req.description];
-        args.push(format!("project:{}", req.project));
-        if let Some(v) = req.due {
-            args.push(format!("due:{v}"));
-        }
-        if let Some(v) = req.priority {
-            args.push(format!("priority:{v}"));
-        }
-        if let Some(v) = req.wait {
-            args.push(format!("wait:{v}"));
-        }
-        if let Some(v) = req.scheduled {
-            args.push(format!("scheduled:{v}"));
-        }
-        if let Some(tags) = req.tags {
-            for t in tags {
-                args.push(format!("+{t}"));
-            }
-        }
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct NextActionsRequest {
+    /// Maximum number of tasks to return (default 10)
+    limit: Option<u32>,
+    /// Projects to leave out, e.g. ["Someday", "Personal"]. Subprojects are excluded too.
+    exclude_projects: Option<Vec<String>>,
+}
 
-        let refs: Vec<&str> = args.iter().map(String::as_str).collect();
-        Ok(CallToolResult::success(vec![Content::text(
-            self.run(&refs).await?,
-        )]))
-    }
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct GetFocusRequest {
+    /// Project to focus on (REQUIRED)
+    project: String,
+    /// Maximum number of tasks to return (default 5)
+    limit: Option<u32>,
+}
 
-    #[tool(description = "\
-        List tasks sorted by urgency. `project` is REQUIRED. \
-        ALWAYS use the most specific `filter` and `report` for your intent — \
-        do NOT call with no filter unless you genuinely need every pending task. \
-        Quick reference: actionable work → filter='+READY' or report='next'; \
-        overdue → filter='+OVERDUE'; due today → filter='+TODAY'; \
-        blocked → filter='+BLOCKED' or report='blocked'; \
-        high priority → filter='priority:H'; waiting/snoozed → report='waiting'. \
-        Only set `all_projects=true` for explicit cross-project requests.")]
-    async fn list_tasks(
-        &self,
-        Parameters(req): Parameters<ListTasksRequest>,
-    ) -> Result<CallToolResult, McpError> {
-        let mut args: Vec<String> = Vec::new();
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct SetWaitRequest {
+    /// Task ID (numeric) or UUID
+    id: String,
+    /// Hide the task until this date: "monday", "tomorrow", "eow", "2025-07-01", "today+3d"
+    wait_until: String,
+}
 
-        if !req.all_projects.unwrap_or(false) {
-            args.push(format!("project:{}", req.project));
-        }
-        if let Some(f) = req.filter {
-            args.extend(f.split_whitespace().map(str::to_string));
-        }
-        args.push(req.report.unwrap_or_else(|| "next".to_string()));
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct CountByTagRequest {
+    /// Limit the tally to one project (and its subprojects). Omit to count across all projects.
+    project: Option<String>,
+}
 
-        let refs: Vec<&str> = args.iter().map(String::as_str).collect();
-        let out = self
-            .run(&refs)
-            .await
-            .unwrap_or_else(|_| "No tasks found.".to_string());
-        Ok(CallToolResult::success(vec![Content::text(
-            if out.is_empty() {
-                "No tasks found.".to_string()
-            } else {
-                out
-            },
-        )]))
-    }
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct CountByPriorityRequest {
+    /// Project to scope the tally to (REQUIRED). Set all_projects=true to count globally.
+    project: String,
+    /// Override project scoping and count ALL projects.
+    all_projects: Option<bool>,
+}
 
-    #[tool(description = "\
-        Search tasks by regex pattern across descriptions and annotations. \
-        `project` is REQUIRED and automatically scopes the search. \
-        Only set `all_projects=true` for explicit cross-project searches.")]
-    async fn search_tasks(
-        &self,
-        Parameters(req): Parameters<SearchTasksRequest>,
-    ) -> Result<CallToolResult, McpError> {
-        let mut args: Vec<String> = Vec::new();
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct WeeklyReviewRequest {
+    /// Project to review (REQUIRED)
+    project: String,
+}
 
-        if !req.all_projects.unwrap_or(false) {
-            args.push(format!("project:{}", req.project));
-        }
-        if let Some(f) = req.filter {
-            args.extend(f.split_whitespace().map(str::to_string));
-        }
-        args.push(format!("/{}/", req.pattern));
-        args.push("list".to_string());
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct SprintSummaryRequest {
+    /// Project the sprint belongs to (REQUIRED)
+    project: String,
+    /// Tag marking tasks in this sprint, without the + prefix (e.g. "sprint-1")
+    sprint_tag: String,
+}
 
-        let refs: Vec<&str> = args.iter().map(String::as_str).collect();
-        let out = self
-            .run(&refs)
-            .await
-            .unwrap_or_else(|_| "No matching tasks.".to_string());
-        Ok(CallToolResult::success(vec![Content::text(
-            if out.is_empty() {
-                "No matching tasks.".to_string()
-            } else {
-                out
-            },
-        )]))
-    }
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct RecentlyCompletedRequest {
+    /// Look-back window in hours (default 24)
+    hours: Option<u32>,
+}
 
-    #[tool(description = "\
-        Get full details of a task by ID or UUID: all attributes, annotations, \
-        urgency score, dependencies, and timestamps.")]
-    async fn get_task(
-        &self,
-        Parameters(req): Parameters<TaskIdRequest>,
-    ) -> Result<CallToolResult, McpError> {
-        let out = self.run(&[&req.id, "information"]).await?;
-        Ok(CallToolResult::success(vec![Content::text(out)]))
-    }
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct RecentlyAddedRequest {
+    /// Look-back window in hours (default 24)
+    hours: Option<u32>,
+}
 
-    #[tool(description = "\
-        Modify a task's attributes. Pass modifications as a space-separated string: \
-        'due:friday priority:H +newtag -oldtag project:Work'. \
-        Clear a field by omitting its value: 'due: priority:'.")]
-    async fn modify_task(
-        &self,
-        Parameters(req): Parameters<ModifyTaskRequest>,
-    ) -> Result<CallToolResult, McpError> {
-        let mut args = vec![req.id, "modify".to_string()];
-        args.extend(req.modifications.split_whitespace().map(str::to_string));
-        let refs: Vec<&str> = args.iter().map(String::as_str).collect();
-        Ok(CallToolResult::success(vec![Content::text(
-            self.run(&refs).await?,
-        )]))
-    }
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct TaskChangelogRequest {
+    /// Task ID (numeric) or UUID
+    id: String,
+}
 
-    #[tool(description = "Mark a task as completed.")]
-    async fn complete_task(
-        &self,
-        Parameters(req): Parameters<TaskIdRequest>,
-    ) -> Result<CallToolResult, McpError> {
-        Ok(CallToolResult::success(vec![Content::text(
-            self.run(&[&req.id, "done"]).await?,
-        )]))
-    }
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct DueRangeSearchRequest {
+    /// Project to scope this query to (REQUIRED). Set all_projects=true to query globally.
+    project: String,
+    /// Start of the window (exclusive), e.g. "today", "sow", "2025-06-01"
+    due_after: String,
+    /// End of the window (exclusive), e.g. "eow", "today+14d", "2025-06-30"
+    due_before: String,
+    /// Additional filter tokens to narrow results, e.g. "+READY priority:H"
+    filter: Option<String>,
+    /// Override project scoping and query ALL projects.
+    all_projects: Option<bool>,
+}
 
-    #[tool(description = "Permanently delete a task.")]
-    async fn delete_task(
-        &self,
-        Parameters(req): Parameters<TaskIdRequest>,
-    ) -> Result<CallToolResult, McpError> {
-        Ok(CallToolResult::success(vec![Content::text(
-            self.run(&[&req.id, "delete"]).await?,
-        )]))
-    }
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct RecursiveDepsRequest {
+    /// Task ID (numeric) or UUID
+    id: String,
+    /// How many levels of dependencies to follow (default 10)
+    max_depth: Option<u8>,
+}
 
-    #[tool(description = "\
-        Attach a timestamped annotation (note) to a task. \
-        Use for progress updates, links, or context that shouldn't be lost.")]
-    async fn annotate_task(
-        &self,
-        Parameters(req): Parameters<AnnotateTaskRequest>,
-    ) -> Result<CallToolResult, McpError> {
-        Ok(CallToolResult::success(vec![Content::text(
-            self.run(&[&req.id, "annotate", &req.note]).await?,
-        )]))
-    }
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct SearchReplaceRequest {
+    /// Project to scope the rename to (REQUIRED). Set all_projects=true to rename globally.
+    project: String,
+    /// Literal text to find in pending task descriptions (case-sensitive, not a regex)
+    pattern: String,
+    /// Text to substitute for every occurrence of `pattern`
+    replacement: String,
+    /// Override project scoping and rewrite tasks in ALL projects.
+    all_projects: Option<bool>,
+    /// Preview the new descriptions without modifying anything
+    dry_run: Option<bool>,
 }
 
-#[tool_handler]
-impl ServerHandler for TaskWarriorServer {
-    fn get_info(&self) -> ServerInfo {
-        ServerInfo {
-            protocol_version: ProtocolVersion::V_2024_11_05,
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
-            server_info: Implementation {
-                name: env!("CARGO_PKG_NAME").to_string(),
-                version: env!("CARGO_PKG_VERSION").to_string(),
-                title: None,
-                description: None,
-                icons: None,
-                website_url: None,
-            },
-            instructions: Some(
-                "Taskwarrior MCP server. PROJECT SCOPING IS MANDATORY: \
-                add_task requires `project`, list_tasks and search_tasks require `project` and \
-                automatically prepend it as a filter — this prevents thousands of unrelated tasks \
-                from flooding context. Only pass all_projects=true when the user explicitly asks \
-                for a cross-project view. \
-                FILTER DISCIPLINE: never call list_tasks without a filter or a non-default report \
-                unless every pending task is genuinely needed. Match filter/report to intent: \
-                actionable → filter='+READY'; overdue → filter='+OVERDUE'; today → filter='+TODAY'; \
-                blocked → filter='+BLOCKED'; snoozed → report='waiting'; history → report='completed'. \
-                Tools: add_task · list_tasks · search_tasks · get_task · modify_task · complete_task · delete_task · annotate_task. \
-                Date syntax: today · tomorrow · eow · eom · friday · 2025-06-15 · 2025-06-15T14:30. \
-                Virtual filter tags: +OVERDUE · +DUE · +READY · +BLOCKED · +BLOCKING · +ACTIVE · +WAITING · +TODAY."
-                .to_string(),
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct RenameProjectRequest {
+    /// Current project name, matched exactly (subprojects are left alone)
+    old_project: String,
+    /// New project name, e.g. "Work.Platform"
+    new_project: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct ArchiveProjectRequest {
+    /// Project to pause, including its subprojects
+    project: String,
+    /// Hide the tasks until this date (default "2099-12-31", i.e. indefinitely)
+    wait_until: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct UnarchiveProjectRequest {
+    /// Project to resume, including its subprojects
+    project: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct TagDetailsRequest {
+    /// Tag to look up, without the + prefix (e.g. "urgent")
+    tag: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct NormalizeTagsRequest {
+    /// Project whose pending and waiting tasks should have their tags lowercased (REQUIRED)
+    project: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct RegisterTemplateRequest {
+    /// Template name, e.g. "bug" or "weekly-report". Registering an existing name replaces it.
+    name: String,
+    /// Default task fields: the same shape as add_task
+    template: AddTaskRequest,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct UseTemplateRequest {
+    /// Name of a template registered with register_template
+    template_name: String,
+    /// Replace the template's description
+    description_override: Option<String>,
+    /// Replace the template's due date, e.g. "friday"
+    due_override: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct DeleteTemplateRequest {
+    /// Name of the template to remove
+    name: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct StreamListRequest {
+    /// Project to scope this query to (REQUIRED). Set all_projects=true to query globally.
+    project: String,
+    /// Additional filter tokens to narrow results, e.g. "+READY priority:H"
+    filter: Option<String>,
+    /// Tasks per content chunk (default 25)
+    chunk_size: Option<u32>,
+    /// Override project scoping and query ALL projects.
+    all_projects: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct SuggestProjectsRequest {
+    /// Start of the project name, case-insensitive (e.g. "wo" → "Work", "Work.Backend")
+    prefix: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct SuggestTagsRequest {
+    /// Start of the tag, without the + prefix, case-insensitive (e.g. "ur" → "urgent")
+    prefix: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct EffortReportRequest {
+    /// Project to report on (REQUIRED). Set all_projects=true to break down every project.
+    project: String,
+    /// Override project scoping and report on ALL projects.
+    all_projects: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct SplitTaskRequest {
+    /// Task ID (numeric) or UUID of the task to break down
+    id: String,
+    /// One description per subtask, e.g. ["Draft outline", "Write section 1", "Review"]
+    subtask_descriptions: Vec<String>,
+    /// Mark the original task done once the subtasks exist (default false)
+    complete_original: Option<bool>,
+    /// Make every subtask depend on the original task (default false)
+    add_dependency: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct MergeTasksRequest {
+    /// Task ID (numeric) or UUID of the task to keep
+    primary_id: String,
+    /// Task ID (numeric) or UUID of the duplicate; its annotations move to the primary and it is deleted
+    secondary_id: String,
+    /// New description for the merged task; omit to keep the primary's description
+    combined_description: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct TaskChainRequest {
+    /// Project every step is created in
+    project: String,
+    /// Step descriptions in execution order; each step depends on the one before it
+    descriptions: Vec<String>,
+    /// Priority applied to every step: H, M, or L
+    priority: Option<String>,
+    /// Tags applied to every step, without the + prefix
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct InboxProcessRequest {
+    /// Project used as the capture inbox, usually "Inbox"
+    project_for_inbox: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct CaptureTaskRequest {
+    /// Task description — capture it as-is, refine later
+    description: String,
+    /// Project to file into (default "Inbox")
+    project: Option<String>,
+    /// Tags to apply, without the + prefix
+    tags: Option<Vec<String>>,
+    /// Due date, if one is already known
+    due: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct SomedayMaybeRequest {
+    /// Extra Taskwarrior filter, e.g. "+travel"
+    filter: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct AddSomedayTaskRequest {
+    /// The idea to park for later
+    description: String,
+    /// Tags to apply, without the + prefix
+    tags: Option<Vec<String>>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct SavePresetRequest {
+    /// Preset name, e.g. "urgent-backend". Saving an existing name replaces it.
+    name: String,
+    /// Taskwarrior filter, e.g. "+READY priority:H"
+    filter: String,
+    /// Project the preset is scoped to; omit to run it across all projects
+    project: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct RunPresetRequest {
+    /// Name of a preset saved with save_filter_preset
+    name: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct QuickAddRequest {
+    /// Taskwarrior shorthand: description words plus inline modifiers, e.g.
+    /// "Fix the login bug project:Work priority:H +bug due:friday". project:<name> is REQUIRED;
+    /// depends:<id_or_uuid> may be repeated.
+    text: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct FuzzySearchRequest {
+    /// Approximate text to look for; typos are fine, e.g. "implment feture"
+    query: String,
+    /// Project to scope this search to (REQUIRED). Set all_projects=true to search globally.
+    project: String,
+    /// Minimum similarity from 0.0 to 1.0 (default 0.6); lower finds looser matches
+    threshold: Option<f32>,
+    /// Override project scoping and search ALL projects.
+    all_projects: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct DueTodayRequest {
+    /// Project to scope to; omit to include every project
+    project: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct DueThisWeekRequest {
+    /// Project to scope to; omit to include every project
+    project: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct UrgencyReportRequest {
+    /// Project to scope this report to (REQUIRED). Set all_projects=true to rank globally.
+    project: String,
+    /// Number of tasks to rank (default 10)
+    limit: Option<u32>,
+    /// Override project scoping and rank tasks from ALL projects.
+    all_projects: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct TaskReviewRequest {
+    /// Project to review (REQUIRED)
+    project: String,
+    /// A task is stale when it hasn't been modified for this many days (default 14)
+    stale_days: Option<u32>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct DescribeTaskRequest {
+    /// Task ID (numeric) or UUID
+    id: String,
+    /// Also list pending tasks in the same project with similar descriptions (default false)
+    include_similar: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct BackupRequest {
+    /// Archive file to write, or an existing directory to create a timestamped
+    /// `taskwarrior-backup-<date>-<time>.tar.gz` in
+    output_path: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct RestoreRequest {
+    /// Archive created by task_backup
+    backup_path: String,
+    /// Must be true: restoring overwrites the current task data
+    confirm: bool,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct DescribeFilterRequest {
+    /// Taskwarrior filter to explain, e.g. "project:Work +urgent priority:H -WAITING"
+    filter: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct PrependTextRequest {
+    /// Task ID (numeric) or UUID
+    id: String,
+    /// Text to put in front of the description, e.g. "URGENT:"
+    text: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct AppendTextRequest {
+    /// Task ID (numeric) or UUID
+    id: String,
+    /// Text to add to the end of the description
+    text: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct CompleteWithNoteRequest {
+    /// Task ID (numeric) or UUID
+    id: String,
+    /// Completion note, e.g. "Shipped in v1.4" — attached as an annotation before completing
+    note: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct ImportCsvRequest {
+    /// CSV text whose first row is a header, e.g. "description,project,due\nBuy milk,Home,tomorrow"
+    csv: String,
+    /// Header of the project column (default "project")
+    project_column: Option<String>,
+    /// Header of the description column (default "description")
+    description_column: Option<String>,
+    /// Header of the due date column (default "due")
+    due_column: Option<String>,
+    /// Header of the priority column (default "priority")
+    priority_column: Option<String>,
+    /// Project for rows without a project cell (or when there is no project column)
+    default_project: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct CompletionRateRequest {
+    /// Project to measure (REQUIRED)
+    project: String,
+    /// Window length in days (default 7)
+    days: Option<u32>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct CloseProjectRequest {
+    /// Project to close, including its subprojects (REQUIRED)
+    project: String,
+    /// Annotation added to every task before it is completed, e.g. "Closed: project shipped"
+    note: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct AgeReportRequest {
+    /// Project to report on (REQUIRED)
+    project: String,
+    /// Only include tasks open for at least this many days
+    older_than_days: Option<u32>,
+}
+
+/// Options accepted by every tool. Read centrally in `call_tool` rather than embedded in
+/// each request type; `list_tools` merges these fields into every tool's input schema.
+#[derive(Debug, Default, serde::Deserialize, schemars::JsonSchema)]
+struct CommonRequestFields {
+    /// Wrap the result as JSON: {"tool", "status": "ok"|"error", "data", "timestamp"}.
+    /// For programmatic clients that want one uniform result shape.
+    json_envelope: Option<bool>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct NextWeekRequest {
+    /// Project to scope to; omit to include every project
+    project: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct DeprioritizeProjectRequest {
+    /// Project to push into the background, including its subprojects (REQUIRED)
+    project: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct EscalateOverdueRequest {
+    /// Project to scope to; omit to escalate overdue tasks in every project
+    project: Option<String>,
+    /// Priority to give overdue tasks: H (default), M, or L
+    new_priority: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct GetToolInfoRequest {
+    /// Tool name, e.g. "add_task"
+    name: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct DailyHabitRequest {
+    /// One description per habit, e.g. ["Meditate", "Stretch", "Read 20 pages"]
+    descriptions: Vec<String>,
+    /// Project every habit is created in
+    project: String,
+    /// Tags applied to every habit, without the + prefix
+    tags: Option<Vec<String>>,
+    /// When the first occurrence is due: a time of day today ("07:30") or any Taskwarrior
+    /// date ("tomorrow", "2025-06-15T07:30"). Defaults to "today".
+    due_time: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct GithubLinkRequest {
+    /// Task ID (numeric) or UUID
+    id: String,
+    /// Repository as "owner/name", e.g. "rust-lang/rust"
+    repo: String,
+    /// Issue (or pull request) number
+    issue_number: u32,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct GetTasksWithUrlRequest {
+    /// Project to scan, including its subprojects
+    project: String,
+    /// Only URLs on this domain or its subdomains, e.g. "github.com"
+    domain: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct AutoTagRequest {
+    /// Project whose tasks (including every subproject) get the tag, e.g. "Work"
+    project_prefix: String,
+    /// Tag to add, without the + prefix
+    tag: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct EstimateCompletionRequest {
+    /// Project to forecast (REQUIRED)
+    project: String,
+    /// Days of completion history used to measure velocity (default 14)
+    velocity_window_days: Option<u32>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct RecurringStatusRequest {
+    /// ID or UUID of a recurring task — the template or any of its instances
+    id: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct UnwaitAllRequest {
+    /// Project whose waiting tasks are released, including its subprojects (REQUIRED)
+    project: String,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct DependencyGraphRequest {
+    /// Project whose dependency graph to draw (REQUIRED)
+    project: String,
+    /// Draw only the graph below this task (ID or UUID); default: every task in the project
+    /// that has dependencies but that nothing depends on
+    root_id: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct NotesToDocumentRequest {
+    /// Task ID (numeric) or UUID
+    id: String,
+    /// "markdown" (default) or "plain"
+    format: Option<String>,
+}
+
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct ReorderRequest {
+    /// Task (ID or UUID) that should rank above the other
+    higher_id: String,
+    /// Task (ID or UUID) that should rank below the other
+    lower_id: String,
+}
+
+// ── Data directory ───────────────────────────────────────────────────────────
+
+/// Resolve the Taskwarrior data directory. Candidates in priority order:
+/// `$TASKWARRIOR_DATA_DIR`, `$XDG_DATA_HOME/task` (3.x), `~/.local/share/task` (3.x),
+/// `~/.task` (2.x). An explicit `TASKWARRIOR_DATA_DIR` always wins, even before it exists;
+/// the others are only picked if they exist on disk. Falls back to `~/.task` when nothing
+/// exists yet, and to `None` when `$HOME` is unset too.
+fn detect_data_dir() -> Option<PathBuf> {
+    detect_data_dir_from(|key| std::env::var(key).ok())
+}
+
+/// [`detect_data_dir`] with an injectable environment lookup, so tests don't have to
+/// mutate the process environment.
+fn detect_data_dir_from(env: impl Fn(&str) -> Option<String>) -> Option<PathBuf> {
+    let non_empty = |key: &str| env(key).filter(|v| !v.is_empty());
+
+    if let Some(dir) = non_empty("TASKWARRIOR_DATA_DIR") {
+        return Some(PathBuf::from(dir));
+    }
+
+    let home = non_empty("HOME").map(PathBuf::from);
+    let candidates = [
+        non_empty("XDG_DATA_HOME").map(|d| PathBuf::from(d).join("task")),
+        home.as_ref().map(|h| h.join(".local/share/task")),
+        home.as_ref().map(|h| h.join(".task")),
+    ];
+
+    candidates
+        .into_iter()
+        .flatten()
+        .find(|dir| dir.is_dir())
+        .or_else(|| home.map(|h| h.join(".task")))
+}
+
+// ── Validation ───────────────────────────────────────────────────────────────
+
+/// Reject date values that can't be a single Taskwarrior date token. Taskwarrior does the
+/// real parsing; this only stops empty values, embedded whitespace, and values that would
+/// be read as a tag or option (leading `+`/`-`).
+fn validate_date(field: &str, value: &str) -> Result<(), McpError> {
+    let ok = !value.is_empty()
+        && !value.starts_with(['+', '-'])
+        && value
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "-+:._".contains(c));
+    if ok {
+        Ok(())
+    } else {
+        Err(McpError::invalid_params(
+            format!(
+                "Invalid {field} date {value:?}: expected a Taskwarrior date such as \
+                 \"tomorrow\", \"eow\", \"2025-06-15\", or \"today+3d\""
+            ),
+            None,
+        ))
+    }
+}
+
+/// Reject project names Taskwarrior would misparse or silently mangle: empty names,
+/// whitespace, filter syntax (`:`, quotes, parentheses), and empty dot-notation segments.
+fn validate_project_name(field: &str, name: &str) -> Result<(), McpError> {
+    let ok = !name.is_empty()
+        && !name.starts_with(['+', '-'])
+        && name.split('.').all(|segment| !segment.is_empty())
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || "-_.".contains(c));
+    if ok {
+        Ok(())
+    } else {
+        Err(McpError::invalid_params(
+            format!(
+                "Invalid {field} {name:?}: use letters, digits, '-' and '_', with '.' \
+                 between subproject levels, e.g. \"Work.Backend\""
             ),
+            None,
+        ))
+    }
+}
+
+// ── Contexts ─────────────────────────────────────────────────────────────────
+
+/// `add_task` project that stands for the active context's name.
+const AUTO_PROJECT: &str = "auto";
+
+// ── Quick add ────────────────────────────────────────────────────────────────
+
+/// Split Taskwarrior shorthand (`Buy milk project:Home +errand due:tomorrow`) into
+/// `task add` arguments. Requires a `project:` token, matching add_task. Repeated
+/// `depends:` tokens are coalesced into one `depends:1,2,3`, since Taskwarrior keeps only
+/// the last of them.
+fn quick_add_args(text: &str) -> Result<Vec<String>, McpError> {
+    let mut depends: Vec<&str> = Vec::new();
+    let mut args: Vec<String> = Vec::new();
+    for token in text.split_whitespace() {
+        match token.strip_prefix("depends:") {
+            Some(ids) => depends.extend(ids.split(',').filter(|id| !id.is_empty())),
+            None => args.push(token.to_string()),
+        }
+    }
+    let mut has_project = false;
+    for arg in &args {
+        if let Some(project) = arg.strip_prefix("project:") {
+            validate_project_name("project", project)?;
+            has_project = true;
+        } else if let Some(due) = arg.strip_prefix("due:") {
+            validate_date("due", due)?;
+        }
+    }
+    if !has_project {
+        return Err(McpError::invalid_params(
+            "Missing project: include a project:<name> token, e.g. \"Buy milk project:Home\"",
+            None,
+        ));
+    }
+    let is_modifier = |arg: &String| arg.starts_with('+') || arg.contains(':');
+    if args.iter().all(is_modifier) {
+        return Err(McpError::invalid_params(
+            "Missing description: text only contains modifiers",
+            None,
+        ));
+    }
+    if !depends.is_empty() {
+        args.push(format!("depends:{}", depends.join(",")));
+    }
+    Ok(args)
+}
+
+// ── Filter descriptions ──────────────────────────────────────────────────────
+
+/// Virtual tags: (tag, phrase for +TAG, phrase for -TAG).
+const VIRTUAL_TAGS: &[(&str, &str, &str)] = &[
+    ("ACTIVE", "are started", "are not started"),
+    ("ANNOTATED", "have annotations", "have no annotations"),
+    ("BLOCKED", "are blocked by other tasks", "are not blocked"),
+    ("BLOCKING", "block other tasks", "block no other tasks"),
+    ("COMPLETED", "are completed", "are not completed"),
+    ("DELETED", "are deleted", "are not deleted"),
+    (
+        "DUE",
+        "are due within the next week",
+        "are not due within the next week",
+    ),
+    ("MONTH", "are due this month", "are not due this month"),
+    ("OVERDUE", "are overdue", "are not overdue"),
+    ("PENDING", "are pending", "are not pending"),
+    ("READY", "are ready to work on", "are not ready to work on"),
+    ("RECURRING", "recur", "do not recur"),
+    ("SCHEDULED", "are scheduled", "are not scheduled"),
+    ("TAGGED", "have tags", "have no tags"),
+    ("TODAY", "are due today", "are not due today"),
+    ("TOMORROW", "are due tomorrow", "are not due tomorrow"),
+    ("UNBLOCKED", "are not blocked", "are blocked by other tasks"),
+    ("WAITING", "are waiting", "are not waiting"),
+    ("WEEK", "are due this week", "are not due this week"),
+    ("YEAR", "are due this year", "are not due this year"),
+    ("YESTERDAY", "were due yesterday", "were not due yesterday"),
+];
+
+/// "a", "a and b", "a, b, and c".
+fn join_clauses(clauses: &[String]) -> String {
+    match clauses {
+        [] => String::new(),
+        [only] => only.clone(),
+        [first, second] => format!("{first} and {second}"),
+        [rest @ .., last] => format!("{}, and {last}", rest.join(", ")),
+    }
+}
+
+/// Phrase for an `attribute[.modifier]:value` token.
+fn describe_attribute(attribute: &str, value: &str) -> String {
+    let (name, modifier) = attribute.split_once('.').unwrap_or((attribute, "is"));
+    let dated = [
+        "due",
+        "scheduled",
+        "wait",
+        "until",
+        "entry",
+        "end",
+        "modified",
+        "start",
+    ]
+    .contains(&name);
+    match (name, modifier, value) {
+        ("status", _, value) => format!("have status {value}"),
+        ("priority", "is" | "equals", "") => "have no priority".to_string(),
+        ("priority", "is" | "equals", value) => format!("have priority {value}"),
+        (_, "none", _) | (_, "is" | "equals", "") => format!("have no {name}"),
+        (_, "any", _) => format!("have any {name}"),
+        ("due", "is" | "equals", value) => format!("are due {value}"),
+        ("due", "before" | "below", value) => format!("are due before {value}"),
+        ("due", "after" | "above", value) => format!("are due after {value}"),
+        (_, "before" | "below", value) if dated => format!("have {name} before {value}"),
+        (_, "after" | "above", value) if dated => format!("have {name} after {value}"),
+        (_, "before" | "below", value) => format!("have {name} below {value}"),
+        (_, "after" | "above", value) => format!("have {name} above {value}"),
+        (_, "not" | "isnt", value) => format!("do not have {name} {value}"),
+        (_, "has" | "contains", value) => format!("have {name} containing '{value}'"),
+        (_, "hasnt", value) => format!("have {name} not containing '{value}'"),
+        (_, "startswith" | "left", value) => format!("have {name} starting with '{value}'"),
+        (_, "endswith" | "right", value) => format!("have {name} ending with '{value}'"),
+        (_, _, value) => format!("have {name} {value}"),
+    }
+}
+
+/// Explain a Taskwarrior filter in plain English, e.g. `project:Work +urgent priority:H
+/// -WAITING` → "This filter matches tasks in project 'Work' that are tagged 'urgent',
+/// have priority H, and are not waiting."
+fn describe_filter(filter: &str) -> String {
+    let mut scope = Vec::new();
+    let mut clauses = Vec::new();
+    let mut uses_or = false;
+    for token in filter.split_whitespace() {
+        let token = token.trim_matches(|c| c == '(' || c == ')');
+        if let Some(pattern) = token
+            .strip_prefix('/')
+            .and_then(|rest| rest.strip_suffix('/'))
+        {
+            clauses.push(format!("mention '{pattern}'"));
+        } else if let Some((sign, tag)) = token
+            .strip_prefix('+')
+            .map(|tag| (true, tag))
+            .or_else(|| token.strip_prefix('-').map(|tag| (false, tag)))
+            .filter(|(_, tag)| !tag.is_empty())
+        {
+            let clause = match VIRTUAL_TAGS.iter().find(|(name, _, _)| *name == tag) {
+                Some((_, yes, no)) => if sign { yes } else { no }.to_string(),
+                None if sign => format!("are tagged '{tag}'"),
+                None => format!("are not tagged '{tag}'"),
+            };
+            clauses.push(clause);
+        } else if let Some((attribute, value)) = token.split_once(':') {
+            match attribute {
+                "project" | "project.is" if value.is_empty() => {
+                    scope.push("without a project".to_string())
+                }
+                "project" => scope.push(format!("in project '{value}' or its subprojects")),
+                "project.is" => scope.push(format!("in project '{value}'")),
+                "project.not" => scope.push(format!("outside project '{value}'")),
+                _ => clauses.push(describe_attribute(attribute, value)),
+            }
+        } else {
+            match token.to_ascii_lowercase().as_str() {
+                "" | "and" => {}
+                "or" | "xor" => uses_or = true,
+                _ => clauses.push(format!("contain '{token}' in the description")),
+            }
         }
     }
-}
 
-// ── Entry point ───────────────────────────────────────────────────────────────
+    let mut out = "This filter matches ".to_string();
+    out.push_str(if scope.is_empty() && clauses.is_empty() {
+        "all tasks"
+    } else {
+        "tasks"
+    });
+    if !scope.is_empty() {
+        out.push_str(&format!(" {}", join_clauses(&scope)));
+    }
+    if !clauses.is_empty() {
+        out.push_str(&format!(" that {}", join_clauses(&clauses)));
+    }
+    out.push('.');
+    if uses_or {
+        out.push_str(
+            " Note: the filter uses 'or', so some of these conditions are alternatives \
+             rather than all being required.",
+        );
+    }
+    out
+}
+
+// ── Annotation helpers ───────────────────────────────────────────────────────
+
+/// Collapse a single-line note: literal `\n` escapes and real line breaks become spaces.
+fn single_line_note(note: &str) -> String {
+    note.replace("\\n", " ")
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Build the annotation text passed to `task annotate` as a single argument.
+/// `note_lines` are joined with real newlines, led by `note` when it is non-empty.
+fn annotation_text(note: &str, note_lines: Option<&[String]>) -> String {
+    let first = single_line_note(note);
+    match note_lines {
+        Some(lines) => std::iter::once(first.as_str())
+            .filter(|l| !l.is_empty())
+            .chain(lines.iter().map(|l| l.trim_end()))
+            .collect::<Vec<_>>()
+            .join("\n")
+            .trim()
+            .to_string(),
+        None => first,
+    }
+}
+
+// ── Due date suggestions ─────────────────────────────────────────────────────
+
+/// Keyword → (Taskwarrior date token, confidence). Phrases are matched on word boundaries.
+const DUE_DATE_CUES: &[(&str, &str, f32)] = &[
+    ("today", "today", 0.95),
+    ("tonight", "today", 0.9),
+    ("end of day", "eod", 0.9),
+    ("eod", "eod", 0.9),
+    ("asap", "today", 0.7),
+    ("immediately", "today", 0.7),
+    ("urgent", "today", 0.6),
+    ("tomorrow", "tomorrow", 0.95),
+    ("monday", "monday", 0.9),
+    ("tuesday", "tuesday", 0.9),
+    ("wednesday", "wednesday", 0.9),
+    ("thursday", "thursday", 0.9),
+    ("friday", "friday", 0.9),
+    ("saturday", "saturday", 0.9),
+    ("sunday", "sunday", 0.9),
+    ("weekend", "saturday", 0.7),
+    ("this week", "eow", 0.8),
+    ("end of week", "eow", 0.85),
+    ("eow", "eow", 0.9),
+    ("next week", "eow+7d", 0.75),
+    ("this month", "eom", 0.75),
+    ("end of month", "eom", 0.85),
+    ("eom", "eom", 0.9),
+    ("next month", "som", 0.7),
+    ("end of quarter", "eoq", 0.8),
+    ("this year", "eoy", 0.6),
+    ("end of year", "eoy", 0.8),
+    ("soon", "eow", 0.4),
+    ("someday", "someday", 0.6),
+    ("eventually", "someday", 0.5),
+    ("later", "later", 0.4),
+];
+
+/// Suggest Taskwarrior due-date tokens for a description, highest confidence first.
+/// Explicit ISO dates and "in N days/weeks" phrases beat keywords; with no cues at all,
+/// the priority (if any) yields a low-confidence default.
+fn suggest_due_dates(description: &str, priority: Option<&str>) -> Vec<(String, f32, String)> {
+    let lower = description.to_lowercase();
+    let words: Vec<&str> = lower
+        .split(|c: char| !c.is_alphanumeric() && c != '-')
+        .filter(|w| !w.is_empty())
+        .collect();
+    let padded = format!(" {} ", words.join(" "));
+
+    fn add(found: &mut Vec<(String, f32, String)>, token: String, confidence: f32, reason: String) {
+        match found.iter_mut().find(|(t, _, _)| *t == token) {
+            Some(existing) if existing.1 >= confidence => {}
+            Some(existing) => *existing = (token, confidence, reason),
+            None => found.push((token, confidence, reason)),
+        }
+    }
+
+    let mut found: Vec<(String, f32, String)> = Vec::new();
+
+    for word in &words {
+        if NaiveDate::parse_from_str(word, "%Y-%m-%d").is_ok() {
+            add(
+                &mut found,
+                word.to_string(),
+                1.0,
+                format!("explicit date {word:?}"),
+            );
+        }
+    }
+    for pair in words.windows(3) {
+        if let ["in", n, unit] = pair {
+            let Ok(n) = n.parse::<u32>() else { continue };
+            let suffix = match unit.trim_end_matches('s') {
+                "day" => "d",
+                "week" => "w",
+                _ => continue,
+            };
+            add(
+                &mut found,
+                format!("today+{n}{suffix}"),
+                0.85,
+                format!("matched \"in {n} {unit}\""),
+            );
+        }
+    }
+    for (cue, token, confidence) in DUE_DATE_CUES {
+        if padded.contains(&format!(" {cue} ")) {
+            add(
+                &mut found,
+                token.to_string(),
+                *confidence,
+                format!("matched {cue:?}"),
+            );
+        }
+    }
+
+    if found.is_empty() {
+        let fallback = match priority.map(str::to_uppercase).as_deref() {
+            Some("H") => Some(("tomorrow", 0.3)),
+            Some("M") => Some(("eow", 0.3)),
+            Some("L") => Some(("eom", 0.2)),
+            _ => None,
+        };
+        if let Some((token, confidence)) = fallback {
+            add(
+                &mut found,
+                token.to_string(),
+                confidence,
+                "no date cues; default for priority".to_string(),
+            );
+        }
+    }
+
+    found.sort_by(|a, b| b.1.total_cmp(&a.1));
+    found
+}
+
+// ── URLs ─────────────────────────────────────────────────────────────────────
+
+/// `http(s)://` URLs in free text, each running to the next whitespace. Trailing
+/// sentence punctuation and closing brackets are not part of the URL.
+fn extract_urls(text: &str) -> Vec<&str> {
+    text.split_whitespace()
+        .filter_map(|word| {
+            let start = word.find("https://").or_else(|| word.find("http://"))?;
+            let url = word[start..]
+                .trim_end_matches(['.', ',', ';', ':', '!', '?', ')', ']', '>', '"', '\'']);
+            (!url.ends_with("://")).then_some(url)
+        })
+        .collect()
+}
+
+/// Whether `url`'s host is `domain` or one of its subdomains (case-insensitive).
+fn url_in_domain(url: &str, domain: &str) -> bool {
+    let host = url
+        .split_once("://")
+        .map_or(url, |(_, rest)| rest)
+        .split(['/', '?', '#'])
+        .next()
+        .unwrap_or_default();
+    // Drop userinfo and port.
+    let host = host.rsplit('@').next().unwrap_or(host);
+    let host = host.split(':').next().unwrap_or(host).to_lowercase();
+    let domain = domain.trim().trim_start_matches("www.").to_lowercase();
+    let host = host.trim_start_matches("www.");
+    host == domain || host.ends_with(&format!(".{domain}"))
+}
+
+// ── Fuzzy matching ───────────────────────────────────────────────────────────
+
+/// Edit distance between two strings, counted in chars.
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = prev[j] + usize::from(ca != *cb);
+            cur[j + 1] = substitution.min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// Normalized similarity in `0.0..=1.0`: 1 − distance / longer length.
+fn similarity(a: &str, b: &str) -> f32 {
+    let longest = a.chars().count().max(b.chars().count());
+    if longest == 0 {
+        return 1.0;
+    }
+    1.0 - levenshtein(a, b) as f32 / longest as f32
+}
+
+/// Case-insensitive match score of `query` against `text`: the best similarity between
+/// the query and the whole text or any run of as many words as the query has, so a short
+/// query can still match inside a long description.
+fn fuzzy_score(query: &str, text: &str) -> f32 {
+    let query = query
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    let words: Vec<String> = text.split_whitespace().map(str::to_lowercase).collect();
+    let width = query.split(' ').count().min(words.len()).max(1);
+    words
+        .windows(width)
+        .map(|window| similarity(&query, &window.join(" ")))
+        .chain([similarity(&query, &words.join(" "))])
+        .fold(0.0, f32::max)
+}
+
+// ── Export helpers ───────────────────────────────────────────────────────────
+
+const DEFAULT_CSV_COLUMNS: &[&str] = &[
+    "id",
+    "uuid",
+    "description",
+    "project",
+    "status",
+    "priority",
+    "due",
+    "scheduled",
+    "wait",
+    "tags",
+];
+
+/// Text of a tool result's first content block (empty for non-text results).
+fn text_content(result: &CallToolResult) -> String {
+    result
+        .content
+        .first()
+        .and_then(|c| c.as_text())
+        .map(|t| t.text.clone())
+        .unwrap_or_default()
+}
+
+/// UUID in `add_task` output: "Created task 5 (uuid: abc-123)." → "abc-123".
+fn parse_created_uuid(output: &str) -> Option<&str> {
+    output
+        .split_once("(uuid: ")
+        .and_then(|(_, rest)| rest.split_once(')'))
+        .map(|(uuid, _)| uuid)
+}
+
+/// Whether `s` is a full UUID (8-4-4-4-12 hex digits), as opposed to an ID or filter.
+fn is_uuid(s: &str) -> bool {
+    s.len() == 36
+        && s.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+/// Split a raw filter string into individual `task` arguments.
+fn filter_tokens(filter: &str) -> Vec<String> {
+    filter.split_whitespace().map(str::to_string).collect()
+}
+
+/// Build the leading filter tokens shared by project-scoped tools:
+/// `project:<p>` (unless `all_projects`) followed by the whitespace-split `filter`.
+fn scoped_filter(project: &str, filter: Option<&str>, all_projects: Option<bool>) -> Vec<String> {
+    let mut builder = FilterBuilder::new();
+    if !all_projects.unwrap_or(false) {
+        builder = builder.project(project);
+    }
+    builder.raw(filter.unwrap_or_default()).into_args()
+}
+
+/// Render a single exported attribute as plain text. Arrays (tags, depends) are
+/// space-joined; anything structured (annotations) falls back to its JSON form.
+fn field_text(value: Option<&serde_json::Value>) -> String {
+    match value {
+        None | Some(serde_json::Value::Null) => String::new(),
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(serde_json::Value::Array(items)) if items.iter().all(|v| v.is_string()) => items
+            .iter()
+            .filter_map(serde_json::Value::as_str)
+            .collect::<Vec<_>>()
+            .join(" "),
+        Some(other) => other.to_string(),
+    }
+}
+
+/// UUIDs a task depends on. Taskwarrior 2.6 exports `depends` as an array; older
+/// releases use a comma-separated string.
+fn depends_of(task: &serde_json::Value) -> Vec<String> {
+    match task.get("depends") {
+        Some(serde_json::Value::Array(items)) => items
+            .iter()
+            .filter_map(serde_json::Value::as_str)
+            .map(str::to_string)
+            .collect(),
+        Some(serde_json::Value::String(s)) => s
+            .split(',')
+            .map(str::trim)
+            .filter(|u| !u.is_empty())
+            .map(str::to_string)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Quote a CSV cell per RFC 4180 when it contains a delimiter, quote, or line break.
+fn csv_cell(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
+
+/// Parse RFC 4180 CSV into rows of cells: quoted cells may contain commas, doubled
+/// quotes, and line breaks. Blank lines are skipped.
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, in_quotes) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                cell.push('"');
+                chars.next();
+            }
+            ('"', true) => in_quotes = false,
+            ('"', false) if cell.is_empty() => in_quotes = true,
+            (',', false) => row.push(std::mem::take(&mut cell)),
+            ('\r', false) => {}
+            ('\n', false) => {
+                row.push(std::mem::take(&mut cell));
+                if row.iter().any(|c| !c.is_empty()) {
+                    rows.push(std::mem::take(&mut row));
+                }
+                row.clear();
+            }
+            (c, _) => cell.push(c),
+        }
+    }
+    row.push(cell);
+    if row.iter().any(|c| !c.is_empty()) {
+        rows.push(row);
+    }
+    rows
+}
+
+/// Parse Taskwarrior's compact UTC timestamp (`20250615T143000Z`).
+fn parse_tw_date(text: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(text, "%Y%m%dT%H%M%SZ")
+        .ok()
+        .map(|dt| dt.and_utc())
+}
+
+/// Render a Taskwarrior timestamp in local time: `2025-06-15` for midnight dates,
+/// `2025-06-15 14:30` otherwise. Unparseable input is returned unchanged.
+fn display_date(text: &str) -> String {
+    match parse_tw_date(text) {
+        Some(dt) => {
+            let local = dt.with_timezone(&Local);
+            if local.time() == NaiveTime::MIN {
+                local.format("%Y-%m-%d").to_string()
+            } else {
+                local.format("%Y-%m-%d %H:%M").to_string()
+            }
+        }
+        None => text.to_string(),
+    }
+}
+
+/// Render one exported task as a Markdown checklist item. Deleted tasks are skipped.
+fn task_to_markdown(task: &serde_json::Value, include_metadata: bool) -> Option<String> {
+    let box_mark = match task.get("status").and_then(|v| v.as_str()) {
+        Some("deleted") => return None,
+        Some("completed") => "x",
+        _ => " ",
+    };
+    let description = field_text(task.get("description"));
+    let mut line = format!("- [{box_mark}] {description}");
+
+    if include_metadata {
+        let mut meta = Vec::new();
+        if let Some(due) = task.get("due").and_then(|v| v.as_str()) {
+            meta.push(format!("due: {}", display_date(due)));
+        }
+        if let Some(priority) = task.get("priority").and_then(|v| v.as_str()) {
+            meta.push(format!("priority: {priority}"));
+        }
+        if !meta.is_empty() {
+            line.push_str(&format!(" ({})", meta.join(", ")));
+        }
+    }
+    Some(line)
+}
+
+/// A task's annotations as `(entry, note)` pairs, oldest first.
+fn sorted_annotations(task: &serde_json::Value) -> Vec<(&str, &str)> {
+    let mut annotations: Vec<(&str, &str)> = task
+        .get("annotations")
+        .and_then(|a| a.as_array())
+        .into_iter()
+        .flatten()
+        .filter_map(|a| {
+            Some((
+                a.get("entry").and_then(|e| e.as_str()).unwrap_or_default(),
+                a.get("description")?.as_str()?,
+            ))
+        })
+        .collect();
+    annotations.sort_by_key(|(entry, _)| *entry);
+    annotations
+}
+
+/// A task's annotations as a dated journal, in Markdown (`# Task:`, `## Annotations`, one
+/// `### <timestamp>` per note) or as plain text.
+fn notes_document(task: &serde_json::Value, markdown: bool) -> String {
+    let description = field_text(task.get("description"));
+    let annotations = sorted_annotations(task);
+    let stamp = |entry: &str| match parse_tw_date(entry) {
+        Some(dt) => dt
+            .with_timezone(&Local)
+            .format("%Y-%m-%d %H:%M")
+            .to_string(),
+        None => entry.to_string(),
+    };
+    let mut out = if markdown {
+        format!("# Task: {description}\n\n## Annotations")
+    } else {
+        format!("Task: {description}\n\nAnnotations")
+    };
+    if annotations.is_empty() {
+        out.push_str("\n\n(none)");
+    }
+    for (entry, note) in annotations {
+        if markdown {
+            out.push_str(&format!("\n\n### {}\n\n{note}", stamp(entry)));
+        } else {
+            out.push_str(&format!("\n\n{}\n{note}", stamp(entry)));
+        }
+    }
+    out
+}
+
+/// Render one exported task as labelled lines (`Description:`, `Project:`, …, only the
+/// fields that are set) followed by its annotations as a dated timeline.
+fn describe_task(task: &serde_json::Value) -> String {
+    let project = field_text(task.get("project"));
+    let mut lines = vec![
+        format!("Description: {}", field_text(task.get("description"))),
+        format!(
+            "Project: {}",
+            if project.is_empty() {
+                "(none)"
+            } else {
+                &project
+            }
+        ),
+    ];
+    let text_fields = [
+        ("Status", "status"),
+        ("Priority", "priority"),
+        ("Tags", "tags"),
+        ("Recurrence", "recur"),
+    ];
+    for (label, key) in text_fields {
+        let value = field_text(task.get(key));
+        if !value.is_empty() {
+            lines.push(format!("{label}: {value}"));
+        }
+    }
+    let date_fields = [
+        ("Due", "due"),
+        ("Scheduled", "scheduled"),
+        ("Waiting until", "wait"),
+        ("Until", "until"),
+        ("Created", "entry"),
+        ("Modified", "modified"),
+        ("Started", "start"),
+        ("Ended", "end"),
+    ];
+    for (label, key) in date_fields {
+        if let Some(date) = task.get(key).and_then(|v| v.as_str()) {
+            lines.push(format!("{label}: {}", display_date(date)));
+        }
+    }
+    let depends = depends_of(task);
+    if !depends.is_empty() {
+        lines.push(format!("Depends on: {}", depends.join(", ")));
+    }
+    if let Some(urgency) = numeric_field(task, "urgency") {
+        lines.push(format!("Urgency: {urgency:.2}"));
+    }
+    lines.push(format!("UUID: {}", field_text(task.get("uuid"))));
+
+    let annotations = sorted_annotations(task);
+    if !annotations.is_empty() {
+        lines.push("Annotations:".to_string());
+        for (entry, note) in annotations {
+            lines.push(format!(
+                "  {} — {}",
+                display_date(entry),
+                note.replace('\n', "\n    ")
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Consistency checks over a full export, as (check, offending tasks) pairs: dependencies
+/// on missing or deleted tasks, recurring templates without a due date, priorities other
+/// than H/M/L, and open tasks whose wait date has already passed.
+fn data_issues(
+    tasks: &[serde_json::Value],
+    now: DateTime<Utc>,
+) -> Vec<(&'static str, Vec<String>)> {
+    let live: std::collections::HashSet<String> = tasks
+        .iter()
+        .filter(|t| field_text(t.get("status")) != "deleted")
+        .map(|t| field_text(t.get("uuid")))
+        .collect();
+    let mut broken = Vec::new();
+    let mut recurring_without_due = Vec::new();
+    let mut bad_priority = Vec::new();
+    let mut stale_wait = Vec::new();
+    for task in tasks {
+        let status = field_text(task.get("status"));
+        if status == "deleted" {
+            continue;
+        }
+        let label = format!(
+            "{} ({})",
+            field_text(task.get("description")),
+            field_text(task.get("uuid"))
+        );
+        let missing: Vec<String> = depends_of(task)
+            .into_iter()
+            .filter(|dep| !live.contains(dep))
+            .collect();
+        if !missing.is_empty() {
+            broken.push(format!("{label} → {}", missing.join(", ")));
+        }
+        if status == "recurring" && task.get("due").is_none() {
+            recurring_without_due.push(label.clone());
+        }
+        let priority = field_text(task.get("priority"));
+        if !priority.is_empty() && !["H", "M", "L"].contains(&priority.as_str()) {
+            bad_priority.push(format!("{label}: {priority:?}"));
+        }
+        let waited = task
+            .get("wait")
+            .and_then(|w| w.as_str())
+            .and_then(parse_tw_date)
+            .is_some_and(|wait| wait < now);
+        // Taskwarrior ≥2.6 keeps waiting tasks `pending`, so don't rely on the status.
+        if matches!(status.as_str(), "pending" | "waiting") && waited {
+            stale_wait.push(label);
+        }
+    }
+    vec![
+        ("Dependencies on missing or deleted tasks", broken),
+        ("Recurring tasks without a due date", recurring_without_due),
+        ("Invalid priority values", bad_priority),
+        ("Open tasks whose wait date has passed", stale_wait),
+    ]
+}
+
+/// An open `- [ ]` item parsed from a Markdown checklist.
+#[derive(Debug, Default, PartialEq)]
+struct ChecklistItem {
+    description: String,
+    due: Option<String>,
+    priority: Option<String>,
+    scheduled: Option<String>,
+    wait: Option<String>,
+}
+
+/// Parse `- [ ] description (due: tomorrow, priority: H)`. Returns `None` for anything
+/// that isn't an unchecked checklist item. A trailing parenthesised group is only treated
+/// as metadata when every entry is a recognised `key: value` pair, so "Call Bob (again)"
+/// keeps its parentheses.
+fn parse_checklist_item(line: &str) -> Option<ChecklistItem> {
+    let rest = line.trim_start();
+    let rest = rest
+        .strip_prefix("- [ ]")
+        .or_else(|| rest.strip_prefix("* [ ]"))?;
+    let rest = rest.trim();
+    if rest.is_empty() {
+        return None;
+    }
+
+    let mut item = ChecklistItem {
+        description: rest.to_string(),
+        ..Default::default()
+    };
+    let Some((head, group)) = rest.strip_suffix(')').and_then(|r| r.rsplit_once(" (")) else {
+        return Some(item);
+    };
+
+    let mut meta = ChecklistItem {
+        description: head.trim().to_string(),
+        ..Default::default()
+    };
+    for entry in group.split(',') {
+        let Some((key, value)) = entry.split_once(':') else {
+            return Some(item);
+        };
+        let value = value.trim();
+        // Exported dates look like "2025-06-15 14:30"; Taskwarrior wants "2025-06-15T14:30".
+        let value = if value.len() > 10 && value.as_bytes()[10] == b' ' {
+            value.replacen(' ', "T", 1)
+        } else {
+            value.to_string()
+        };
+        let slot = match key.trim() {
+            "due" => &mut meta.due,
+            "priority" => &mut meta.priority,
+            "scheduled" => &mut meta.scheduled,
+            "wait" => &mut meta.wait,
+            _ => return Some(item),
+        };
+        if value.is_empty() {
+            return Some(item);
+        }
+        *slot = Some(value);
+    }
+    if !meta.description.is_empty() {
+        item = meta;
+    }
+    Some(item)
+}
+
+/// Escape a TEXT value per RFC 5545 §3.3.11.
+fn ical_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Fold a content line at 75 octets (RFC 5545 §3.1), never splitting a UTF-8 sequence.
+fn ical_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for ch in line.chars() {
+        if width + ch.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(ch);
+        width += ch.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+/// Render exported tasks as a VCALENDAR of VTODO components.
+fn tasks_to_ical(tasks: &[serde_json::Value]) -> String {
+    let mut out = String::new();
+    ical_line(&mut out, "BEGIN:VCALENDAR");
+    ical_line(&mut out, "VERSION:2.0");
+    ical_line(
+        &mut out,
+        concat!("PRODID:-//", env!("CARGO_PKG_NAME"), "//EN"),
+    );
+    let now = Utc::now().format("%Y%m%dT%H%M%SZ").to_string();
+
+    for task in tasks {
+        let get = |key: &str| task.get(key).and_then(|v| v.as_str());
+        ical_line(&mut out, "BEGIN:VTODO");
+        if let Some(uuid) = get("uuid") {
+            ical_line(&mut out, &format!("UID:{uuid}"));
+        }
+        ical_line(
+            &mut out,
+            &format!("DTSTAMP:{}", get("modified").unwrap_or(&now)),
+        );
+        ical_line(
+            &mut out,
+            &format!("SUMMARY:{}", ical_text(get("description").unwrap_or(""))),
+        );
+        if let Some(due) = get("due") {
+            ical_line(&mut out, &format!("DUE:{due}"));
+        }
+        let priority = match get("priority") {
+            Some("H") => Some(1),
+            Some("M") => Some(5),
+            Some("L") => Some(9),
+            _ => None,
+        };
+        if let Some(p) = priority {
+            ical_line(&mut out, &format!("PRIORITY:{p}"));
+        }
+        let status = match get("status") {
+            Some("completed") => "COMPLETED",
+            Some("deleted") => "CANCELLED",
+            _ => "NEEDS-ACTION",
+        };
+        ical_line(&mut out, &format!("STATUS:{status}"));
+        if let Some(end) = get("end").filter(|_| status == "COMPLETED") {
+            ical_line(&mut out, &format!("COMPLETED:{end}"));
+        }
+        ical_line(&mut out, "END:VTODO");
+    }
+
+    ical_line(&mut out, "END:VCALENDAR");
+    out
+}
+
+/// Validate a JSON array of tasks for `task import`. Returns the importable objects
+/// (with `project_override` applied) and a reason for each skipped element.
+fn prepare_import(
+    json_array: &str,
+    project_override: Option<&str>,
+) -> Result<(Vec<serde_json::Value>, Vec<String>), McpError> {
+    let root: serde_json::Value = serde_json::from_str(json_array)
+        .map_err(|e| McpError::invalid_params(format!("Invalid JSON: {e}"), None))?;
+    let serde_json::Value::Array(items) = root else {
+        return Err(McpError::invalid_params(
+            "json_array must be a JSON array of task objects",
+            None,
+        ));
+    };
+
+    let mut tasks = Vec::new();
+    let mut skipped = Vec::new();
+    for (i, mut item) in items.into_iter().enumerate() {
+        let Some(obj) = item.as_object_mut() else {
+            skipped.push(format!("element {i}: not a JSON object"));
+            continue;
+        };
+        let has_description = obj
+            .get("description")
+            .and_then(|d| d.as_str())
+            .is_some_and(|d| !d.trim().is_empty());
+        if !has_description {
+            skipped.push(format!("element {i}: missing \"description\""));
+            continue;
+        }
+        if let Some(project) = project_override {
+            obj.insert("project".to_string(), project.into());
+        }
+        tasks.push(item);
+    }
+    Ok((tasks, skipped))
+}
+
+/// Read a numeric attribute (typically a UDA). Numeric UDAs export as JSON numbers, but
+/// orphaned ones (no `uda.<name>.type` in the taskrc) come through as strings.
+fn numeric_field(task: &serde_json::Value, key: &str) -> Option<f64> {
+    match task.get(key)? {
+        serde_json::Value::Number(n) => n.as_f64(),
+        serde_json::Value::String(s) => s.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+/// Order exported tasks the way the `next` report does: most urgent first.
+fn sort_by_urgency(tasks: &mut [serde_json::Value]) {
+    let urgency = |t: &serde_json::Value| numeric_field(t, "urgency").unwrap_or(0.0);
+    tasks.sort_by(|a, b| urgency(b).total_cmp(&urgency(a)));
+}
+
+/// Tally tasks by the keys `keys_of` yields for each one, sorted by count (desc) then key.
+fn count_by<'a>(
+    tasks: &'a [serde_json::Value],
+    keys_of: impl Fn(&'a serde_json::Value) -> Vec<String>,
+) -> Vec<(String, usize)> {
+    let mut counts: std::collections::BTreeMap<String, usize> = Default::default();
+    for task in tasks {
+        for key in keys_of(task) {
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+    let mut rows: Vec<(String, usize)> = counts.into_iter().collect();
+    // BTreeMap already yields keys in order and the sort is stable.
+    rows.sort_by_key(|(_, n)| std::cmp::Reverse(*n));
+    rows
+}
+
+/// Render `(name, count)` rows as a `Name | Count` text table.
+fn count_table(header: &str, rows: &[(String, usize)]) -> String {
+    let width = rows
+        .iter()
+        .map(|(name, _)| name.chars().count())
+        .chain([header.len()])
+        .max()
+        .unwrap_or(0);
+    let mut out = format!("{header:<width$} | Count\n{}-|------", "-".repeat(width));
+    for (name, count) in rows {
+        out.push_str(&format!("\n{name:<width$} | {count}"));
+    }
+    out
+}
+
+fn tasks_to_csv(tasks: &[serde_json::Value], columns: &[String]) -> String {
+    let mut out = String::new();
+    let mut push_row = |cells: Vec<String>| {
+        out.push_str(&cells.join(","));
+        out.push_str("\r\n");
+    };
+    push_row(columns.iter().map(|c| csv_cell(c)).collect());
+    for task in tasks {
+        push_row(
+            columns
+                .iter()
+                .map(|c| csv_cell(&field_text(task.get(c))))
+                .collect(),
+        );
+    }
+    out
+}
+
+// ── Urgency ──────────────────────────────────────────────────────────────────
+
+/// Urgency breakdown rows from `task info`, as `(factor, value, coefficient,
+/// contribution)`. Rows look like `    priority      1 *    6 =      6`.
+fn parse_urgency_factors(info: &str) -> Vec<(String, f64, f64, f64)> {
+    info.lines()
+        .filter_map(|line| {
+            let (left, rest) = line.split_once(" * ")?;
+            let (coefficient, contribution) = rest.split_once(" = ")?;
+            let (factor, value) = left.trim().rsplit_once(char::is_whitespace)?;
+            Some((
+                factor.trim().to_string(),
+                value.parse().ok()?,
+                coefficient.trim().parse().ok()?,
+                contribution.trim().parse().ok()?,
+            ))
+        })
+        .collect()
+}
+
+// ── Forecasts ────────────────────────────────────────────────────────────────
+
+/// Day a backlog of `pending` tasks runs out at the velocity of `completed` tasks per
+/// `window_days`, counting partial days as whole ones. `None` when nothing was completed.
+fn estimate_finish(
+    pending: usize,
+    completed: usize,
+    window_days: u32,
+    today: NaiveDate,
+) -> Option<NaiveDate> {
+    if pending == 0 {
+        return Some(today);
+    }
+    if completed == 0 {
+        return None;
+    }
+    let days = (pending as f64 * f64::from(window_days) / completed as f64).ceil();
+    today.checked_add_days(chrono::Days::new(days as u64))
+}
+
+// ── Dependency graphs ────────────────────────────────────────────────────────
+
+/// Deepest level `dependency_visualization` draws below a root.
+const MAX_GRAPH_DEPTH: usize = 5;
+
+/// Render the dependency graph of `tasks` as box-drawing trees where `A ─▶ B` means A
+/// depends on B. The roots are `root` when given, otherwise every task that has
+/// dependencies but that no other task depends on. Subtrees already drawn are referenced
+/// instead of repeated, and branches stop at [`MAX_GRAPH_DEPTH`].
+fn render_dependency_graph(tasks: &[serde_json::Value], root: Option<&str>) -> String {
+    let by_uuid: HashMap<String, &serde_json::Value> = tasks
+        .iter()
+        .map(|task| (field_text(task.get("uuid")), task))
+        .collect();
+    let roots: Vec<String> = match root {
+        Some(uuid) => vec![uuid.to_string()],
+        None => {
+            let depended: std::collections::HashSet<String> =
+                tasks.iter().flat_map(depends_of).collect();
+            tasks
+                .iter()
+                .filter(|task| !depends_of(task).is_empty())
+                .map(|task| field_text(task.get("uuid")))
+                .filter(|uuid| !depended.contains(uuid))
+                .collect()
+        }
+    };
+
+    fn label(by_uuid: &HashMap<String, &serde_json::Value>, uuid: &str) -> String {
+        let short = uuid.get(..8).unwrap_or(uuid);
+        match by_uuid.get(uuid) {
+            Some(task) => {
+                let done = field_text(task.get("status")) == "completed";
+                let mark = if done { " ✓" } else { "" };
+                format!("{} [{short}]{mark}", field_text(task.get("description")))
+            }
+            None => format!("(task {short}, outside this project)"),
+        }
+    }
+
+    fn walk(
+        lines: &mut Vec<String>,
+        by_uuid: &HashMap<String, &serde_json::Value>,
+        uuid: &str,
+        prefix: &str,
+        depth: usize,
+        drawn: &mut std::collections::HashSet<String>,
+        path: &mut Vec<String>,
+    ) {
+        let deps = by_uuid.get(uuid).map(|t| depends_of(t)).unwrap_or_default();
+        for (i, dep) in deps.iter().enumerate() {
+            let last = i + 1 == deps.len();
+            let branch = if last { "└─▶ " } else { "├─▶ " };
+            let line = format!("{prefix}{branch}{}", label(by_uuid, dep));
+            if path.contains(dep) {
+                lines.push(format!("{line} (cycle)"));
+                continue;
+            }
+            if !drawn.insert(dep.clone()) {
+                lines.push(format!("{line} (see above)"));
+                continue;
+            }
+            lines.push(line);
+            let prefix = format!("{prefix}{}", if last { "    " } else { "│   " });
+            if depth + 1 >= MAX_GRAPH_DEPTH {
+                if by_uuid
+                    .get(dep.as_str())
+                    .is_some_and(|t| !depends_of(t).is_empty())
+                {
+                    lines.push(format!("{prefix}└─▶ …"));
+                }
+                continue;
+            }
+            path.push(dep.clone());
+            walk(lines, by_uuid, dep, &prefix, depth + 1, drawn, path);
+            path.pop();
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut drawn = std::collections::HashSet::new();
+    for root in &roots {
+        lines.push(label(&by_uuid, root));
+        drawn.insert(root.clone());
+        let mut path = vec![root.clone()];
+        walk(&mut lines, &by_uuid, root, "", 0, &mut drawn, &mut path);
+    }
+    lines.join("\n")
+}
+
+// ── Recurrence ───────────────────────────────────────────────────────────────
+
+/// Advance `from` by `periods` recurrence periods of `recur`: named periods (daily,
+/// weekly, biweekly, monthly, quarterly, semiannual, yearly, …) or `<n><unit>` with unit
+/// d, w, mo, q, or y. Month steps clamp to the end of shorter months. `None` for periods
+/// this doesn't model, such as `weekdays`.
+fn advance_recurrence(from: DateTime<Utc>, recur: &str, periods: u32) -> Option<DateTime<Utc>> {
+    let recur = recur.trim().to_lowercase();
+    let (days, months) = match recur.as_str() {
+        "daily" | "day" => (1, 0),
+        "weekly" | "week" => (7, 0),
+        "biweekly" | "fortnight" => (14, 0),
+        "monthly" | "month" => (0, 1),
+        "bimonthly" => (0, 2),
+        "quarterly" => (0, 3),
+        "semiannual" => (0, 6),
+        "annual" | "yearly" | "year" => (0, 12),
+        "biannual" | "biyearly" => (0, 24),
+        _ => {
+            let split = recur.find(|c: char| !c.is_ascii_digit())?;
+            let n: u32 = if split == 0 {
+                1
+            } else {
+                recur[..split].parse().ok()?
+            };
+            match &recur[split..] {
+                "d" | "day" | "days" => (n, 0),
+                "w" | "wk" | "wks" | "week" | "weeks" => (7 * n, 0),
+                "mo" | "mos" | "month" | "months" => (0, n),
+                "q" | "qtr" | "qtrs" | "quarter" | "quarters" => (0, 3 * n),
+                "y" | "yr" | "yrs" | "year" | "years" => (0, 12 * n),
+                _ => return None,
+            }
+        }
+    };
+    from.checked_add_days(chrono::Days::new(u64::from(days * periods)))?
+        .checked_add_months(chrono::Months::new(months * periods))
+}
+
+// ── Undo log ─────────────────────────────────────────────────────────────────
+
+/// Attributes stored as epoch seconds in the undo log.
+const EPOCH_FIELDS: &[&str] = &[
+    "entry",
+    "modified",
+    "due",
+    "wait",
+    "scheduled",
+    "until",
+    "start",
+    "end",
+];
+
+/// Parse one FF4 task line as written to `undo.data`:
+/// `[description:"Write docs" project:"Work" uuid:"…"]`. Values are JSON-escaped with
+/// `"`, `[` and `]` additionally spelled `&dquot;`, `&open;` and `&close;`.
+fn parse_ff4(line: &str) -> std::collections::BTreeMap<String, String> {
+    let mut attrs = std::collections::BTreeMap::new();
+    let body = line.trim().trim_start_matches('[').trim_end_matches(']');
+    let mut chars = body.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let key: String = std::iter::from_fn(|| chars.next_if(|&c| c != ':')).collect();
+        if key.is_empty() || chars.next() != Some(':') || chars.next() != Some('"') {
+            break;
+        }
+        let mut value = String::new();
+        while let Some(c) = chars.next() {
+            match c {
+                '"' => break,
+                '\\' => match chars.next() {
+                    Some('n') => value.push('\n'),
+                    Some('t') => value.push('\t'),
+                    Some(other) => value.push(other),
+                    None => {}
+                },
+                _ => value.push(c),
+            }
+        }
+        let value = value
+            .replace("&dquot;", "\"")
+            .replace("&open;", "[")
+            .replace("&close;", "]");
+        attrs.insert(key, value);
+    }
+    attrs
+}
+
+/// Render an undo-log attribute for humans: epoch fields become local timestamps.
+fn undo_value(key: &str, value: &str) -> String {
+    if EPOCH_FIELDS.contains(&key) {
+        if let Some(dt) = value
+            .parse()
+            .ok()
+            .and_then(|s| DateTime::from_timestamp(s, 0))
+        {
+            return dt
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string();
+        }
+    }
+    value.to_string()
+}
+
+/// Summarise every `undo.data` transaction touching `uuid`, oldest first, one line per
+/// changed attribute: `"2025-06-01 12:00 — priority changed from M to H"`.
+fn task_changelog(undo: &str, uuid: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    for block in undo.split("---") {
+        let (mut time, mut old, mut new) = (None, None, None);
+        for line in block.lines() {
+            if let Some(rest) = line.strip_prefix("time ") {
+                time = rest.trim().parse::<i64>().ok();
+            } else if let Some(rest) = line.strip_prefix("old ") {
+                old = Some(parse_ff4(rest));
+            } else if let Some(rest) = line.strip_prefix("new ") {
+                new = Some(parse_ff4(rest));
+            }
+        }
+        let Some(new) = new.filter(|t| t.get("uuid").map(String::as_str) == Some(uuid)) else {
+            continue;
+        };
+        let when = time
+            .and_then(|s| DateTime::from_timestamp(s, 0))
+            .map(|dt| {
+                dt.with_timezone(&Local)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|| "unknown time".to_string());
+
+        let Some(old) = old else {
+            let desc = new.get("description").map(String::as_str).unwrap_or("");
+            lines.push(format!("{when} — created: {desc}"));
+            continue;
+        };
+        let keys: std::collections::BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+        for key in keys.into_iter().filter(|k| *k != "modified") {
+            let change = match (old.get(key), new.get(key)) {
+                (Some(a), Some(b)) if a == b => continue,
+                (Some(_), Some(b)) if key.starts_with("annotation_") => {
+                    format!("annotation edited: {b}")
+                }
+                (Some(a), Some(b)) => format!(
+                    "{key} changed from {} to {}",
+                    undo_value(key, a),
+                    undo_value(key, b)
+                ),
+                (None, Some(b)) if key.starts_with("annotation_") => format!("annotated: {b}"),
+                (None, Some(b)) => format!("{key} set to {}", undo_value(key, b)),
+                (Some(a), None) if key.starts_with("annotation_") => {
+                    format!("annotation removed: {a}")
+                }
+                (Some(a), None) => format!("{key} cleared (was {})", undo_value(key, a)),
+                (None, None) => continue,
+            };
+            lines.push(format!("{when} — {change}"));
+        }
+    }
+    lines
+}
+
+// ── Backups ──────────────────────────────────────────────────────────────────
+
+/// A ustar header block for a regular file.
+fn tar_header(name: &str, size: u64, mtime: u64) -> [u8; 512] {
+    let mut header = [0u8; 512];
+    let mut put = |offset: usize, value: &[u8]| {
+        header[offset..offset + value.len()].copy_from_slice(value);
+    };
+    put(0, &name.as_bytes()[..name.len().min(100)]);
+    put(100, b"0000644\0");
+    put(108, b"0000000\0");
+    put(116, b"0000000\0");
+    put(124, format!("{size:011o}\0").as_bytes());
+    put(136, format!("{mtime:011o}\0").as_bytes());
+    put(148, b"        ");
+    put(156, b"0");
+    put(257, b"ustar\0");
+    put(263, b"00");
+    let checksum: u32 = header.iter().map(|&b| u32::from(b)).sum();
+    header[148..156].copy_from_slice(format!("{checksum:06o}\0 ").as_bytes());
+    header
+}
+
+fn io_error(what: &str, path: &std::path::Path, e: std::io::Error) -> McpError {
+    McpError::internal_error(format!("Failed to {what} {}: {e}", path.display()), None)
+}
+
+/// Gzip-compressed tar archive of `(name, contents)` entries.
+fn tar_gz(files: &[(String, Vec<u8>)], mtime: u64) -> std::io::Result<Vec<u8>> {
+    use std::io::Write;
+
+    let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    for (name, contents) in files {
+        gz.write_all(&tar_header(name, contents.len() as u64, mtime))?;
+        gz.write_all(contents)?;
+        gz.write_all(&vec![
+            0;
+            contents.len().next_multiple_of(512) - contents.len()
+        ])?;
+    }
+    // End-of-archive marker: two zero blocks.
+    gz.write_all(&[0; 1024])?;
+    gz.finish()
+}
+
+/// Regular-file entries of a .tar.gz archive. Only flat names are accepted, so an
+/// archive can never write outside the directory it is extracted into.
+fn untar_gz(archive: &[u8]) -> std::io::Result<Vec<(String, Vec<u8>)>> {
+    use std::io::{Error, ErrorKind, Read};
+
+    let mut tar = Vec::new();
+    flate2::read::GzDecoder::new(archive).read_to_end(&mut tar)?;
+    let invalid = |msg: String| Error::new(ErrorKind::InvalidData, msg);
+    let field = |block: &[u8]| {
+        String::from_utf8_lossy(block)
+            .trim_matches(|c: char| c == '\0' || c == ' ')
+            .to_string()
+    };
+
+    let mut files = Vec::new();
+    let mut offset = 0;
+    while offset + 512 <= tar.len() && tar[offset] != 0 {
+        let header = &tar[offset..offset + 512];
+        let name = field(&header[..100]);
+        let size = usize::from_str_radix(&field(&header[124..136]), 8)
+            .map_err(|_| invalid(format!("bad size for {name:?}")))?;
+        let start = offset + 512;
+        let contents = tar
+            .get(start..start + size)
+            .ok_or_else(|| invalid(format!("truncated entry {name:?}")))?;
+        if matches!(header[156], b'0' | 0) {
+            if name.is_empty() || name.contains(['/', '\\']) || name == ".." {
+                return Err(invalid(format!("refusing to extract {name:?}")));
+            }
+            files.push((name, contents.to_vec()));
+        }
+        offset = start + size.next_multiple_of(512);
+    }
+    Ok(files)
+}
+
+// ── Templates ────────────────────────────────────────────────────────────────
+
+/// Template registry file, stored alongside Taskwarrior's own data.
+const TEMPLATES_FILE: &str = "mcp_templates.json";
+
+// ── Filter presets ───────────────────────────────────────────────────────────
+
+/// Read a JSON object from `path` into a map; a missing or unreadable file yields an
+/// empty map.
+fn load_json_map<T: serde::de::DeserializeOwned>(path: &std::path::Path) -> HashMap<String, T> {
+    match std::fs::read_to_string(path) {
+        Ok(text) => serde_json::from_str(&text).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable {}: {e}", path.display());
+            HashMap::new()
+        }),
+        Err(_) => HashMap::new(),
+    }
+}
+
+/// Filter preset file, stored alongside Taskwarrior's own data.
+const FILTER_PRESETS_FILE: &str = "mcp_filter_presets.json";
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+struct FilterPreset {
+    filter: String,
+    /// `None` runs the preset across all projects.
+    project: Option<String>,
+}
+
+/// Named filters saved with `save_filter_preset`, mirrored to [`FILTER_PRESETS_FILE`].
+#[derive(Debug, Default, Clone)]
+struct FilterPresets {
+    path: PathBuf,
+    presets: Arc<Mutex<HashMap<String, FilterPreset>>>,
+}
+
+impl FilterPresets {
+    /// Load presets from `path`; a missing or unreadable file starts empty.
+    fn load(path: PathBuf) -> Self {
+        Self {
+            presets: Arc::new(Mutex::new(load_json_map(&path))),
+            path,
+        }
+    }
+
+    /// Re-read the presets file, e.g. after task_restore replaced it.
+    fn reload(&self) {
+        *self.presets.lock().expect("preset lock poisoned") = load_json_map(&self.path);
+    }
+
+    fn get(&self, name: &str) -> Option<FilterPreset> {
+        self.presets
+            .lock()
+            .expect("preset lock poisoned")
+            .get(name)
+            .cloned()
+    }
+
+    /// All presets, sorted by name.
+    fn list(&self) -> Vec<(String, FilterPreset)> {
+        let presets = self.presets.lock().expect("preset lock poisoned");
+        let mut list: Vec<(String, FilterPreset)> = presets
+            .iter()
+            .map(|(name, preset)| (name.clone(), preset.clone()))
+            .collect();
+        list.sort_by(|a, b| a.0.cmp(&b.0));
+        list
+    }
+
+    /// Store a preset and persist; returns whether an existing preset was replaced.
+    async fn insert(&self, name: String, preset: FilterPreset) -> Result<bool, McpError> {
+        let replaced = self
+            .presets
+            .lock()
+            .expect("preset lock poisoned")
+            .insert(name, preset)
+            .is_some();
+        self.save().await?;
+        Ok(replaced)
+    }
+
+    /// Remove a preset and persist; returns whether it existed.
+    async fn remove(&self, name: &str) -> Result<bool, McpError> {
+        let removed = self
+            .presets
+            .lock()
+            .expect("preset lock poisoned")
+            .remove(name)
+            .is_some();
+        if removed {
+            self.save().await?;
+        }
+        Ok(removed)
+    }
+
+    async fn save(&self) -> Result<(), McpError> {
+        let json = {
+            let presets = self.presets.lock().expect("preset lock poisoned");
+            serde_json::to_string_pretty(&*presets)
+        }
+        .map_err(|e| McpError::internal_error(format!("Failed to encode presets: {e}"), None))?;
+        tokio::fs::write(&self.path, json).await.map_err(|e| {
+            McpError::internal_error(
+                format!("Failed to write {}: {e}", self.path.display()),
+                None,
+            )
+        })
+    }
+}
+
+// ── GTD lists ────────────────────────────────────────────────────────────────
+
+/// Default capture project for `task_capture`.
+const INBOX_PROJECT: &str = "Inbox";
+
+/// Project holding the someday/maybe list.
+const SOMEDAY_PROJECT: &str = "Someday";
+
+// ── Prompts ──────────────────────────────────────────────────────────────────
+
+/// Workflow prompts served via `prompts/list` / `prompts/get`: (name, description, body).
+/// `{project}` in the body is replaced by the caller's `project` argument.
+const WORKFLOW_PROMPTS: &[(&str, &str, &str)] = &[
+    (
+        "daily-standup",
+        "Prepare a daily standup from overdue work, today's tasks, and yesterday's completions",
+        "Prepare my daily standup for project {project}.\n\n\
+         1. Call list_tasks with project=\"{project}\" filter=\"+OVERDUE\" for overdue work.\n\
+         2. Call list_tasks with project=\"{project}\" filter=\"+TODAY\" for tasks due today.\n\
+         3. Call get_recently_completed to see what I finished in the last 24 hours.\n\n\
+         Summarize as Yesterday / Today / Blockers, calling out anything overdue.",
+    ),
+    (
+        "weekly-review",
+        "Run a GTD weekly review: completions, new work, overdue and upcoming deadlines",
+        "Run my weekly review for project {project}.\n\n\
+         1. Call get_weekly_review with project=\"{project}\" for the headline numbers.\n\
+         2. Call list_tasks with project=\"{project}\" filter=\"+OVERDUE\" and decide, per task, \
+         whether to reschedule (modify_task due:...), snooze (set_task_wait), or drop it.\n\
+         3. Call search_tasks_by_due_range with project=\"{project}\" due_after=\"today\" \
+         due_before=\"today+7d\" to preview next week.\n\n\
+         End with the three most important things to get done next week.",
+    ),
+    (
+        "inbox-processing",
+        "Triage tasks that were captured without a project",
+        "Process my task inbox — tasks filed without a project.\n\n\
+         1. Call list_tasks with project=\"\" all_projects=true filter=\"project:\" report=\"list\".\n\
+         2. For each task, call suggest_projects to find a fitting existing project, then \
+         modify_task with modifications=\"project:<name>\". Use suggest_due_date when the \
+         description implies a deadline.\n\
+         3. Propose completing or deleting tasks that are no longer relevant — ask before deleting.",
+    ),
+    (
+        "sprint-planning",
+        "Plan a sprint from high-priority pending work",
+        "Help me plan the next sprint for project {project}.\n\n\
+         1. Call list_tasks with project=\"{project}\" filter=\"+PENDING priority:H\" for the candidates.\n\
+         2. Call get_task_effort_report with project=\"{project}\" to gauge the estimated load.\n\
+         3. Propose a sprint scope, then tag the chosen tasks with modify_task \
+         modifications=\"+sprint-<n>\" and confirm with get_sprint_summary.",
+    ),
+];
+
+fn workflow_prompts() -> Vec<Prompt> {
+    WORKFLOW_PROMPTS
+        .iter()
+        .map(|(name, description, _)| {
+            Prompt::new(
+                *name,
+                Some(*description),
+                Some(vec![PromptArgument {
+                    name: "project".to_string(),
+                    title: None,
+                    description: Some("Project to scope the workflow to".to_string()),
+                    required: Some(false),
+                }]),
+            )
+        })
+        .collect()
+}
+
+/// Render a workflow prompt, or `None` for an unknown name.
+fn workflow_prompt(name: &str, project: Option<&str>) -> Option<GetPromptResult> {
+    let (_, description, body) = WORKFLOW_PROMPTS.iter().find(|(n, _, _)| *n == name)?;
+    let text = body.replace("{project}", project.unwrap_or("<project>"));
+    Some(GetPromptResult {
+        description: Some(description.to_string()),
+        messages: vec![PromptMessage::new_text(PromptMessageRole::User, text)],
+    })
+}
+
+// ── Request tracing ──────────────────────────────────────────────────────────
+
+tokio::task_local! {
+    /// Client-supplied request ID of the tool call being served, if any.
+    static REQUEST_ID: Option<String>;
+}
+
+/// Request ID of the tool call currently running on this task.
+fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(Clone::clone).ok().flatten()
+}
+
+/// Run `f` with `req_id` as the current request ID, so `run()` logs and error messages
+/// can be correlated with the client's own logs.
+async fn with_request_id<F: std::future::Future>(req_id: Option<String>, f: F) -> F::Output {
+    REQUEST_ID.scope(req_id, f).await
+}
+
+/// Pull a request ID out of an MCP `_meta` object (`requestId` or `request_id`).
+fn request_id_from(meta: &Meta) -> Option<String> {
+    ["requestId", "request_id"]
+        .iter()
+        .find_map(|key| meta.get(*key))
+        .map(|id| match id {
+            serde_json::Value::String(s) => s.clone(),
+            other => other.to_string(),
+        })
+}
+
+// ── Tool changelog ───────────────────────────────────────────────────────────
+
+/// Version in which each tool's description, parameters, or behaviour last changed, so
+/// agents that cache tool definitions know when to refresh. Bump a tool's entry whenever
+/// it changes; tools added or changed since the 0.1.1 release are listed under 0.2.0.
+const TOOL_CHANGELOG: &[(&str, &str)] = &[
+    ("add_task", "0.2.0"),
+    ("list_tasks", "0.2.0"),
+    ("search_tasks", "0.1.1"),
+    ("get_task", "0.1.1"),
+    ("modify_task", "0.1.1"),
+    ("complete_task", "0.1.1"),
+    ("delete_task", "0.1.1"),
+    ("annotate_task", "0.2.0"),
+    ("export_tasks_csv", "0.2.0"),
+    ("export_tasks_markdown", "0.2.0"),
+    ("import_tasks_from_markdown", "0.2.0"),
+    ("export_tasks_ical", "0.2.0"),
+    ("import_tasks_from_json_array", "0.2.0"),
+    ("get_completed_tasks", "0.2.0"),
+    ("get_recurring_tasks", "0.2.0"),
+    ("edit_annotation", "0.2.0"),
+    ("suggest_due_date", "0.2.0"),
+    ("get_next_actions", "0.2.0"),
+    ("get_focus_tasks", "0.2.0"),
+    ("set_task_wait", "0.2.0"),
+    ("clear_wait", "0.2.0"),
+    ("get_task_count_by_project", "0.2.0"),
+    ("get_task_count_by_tag", "0.2.0"),
+    ("get_task_count_by_priority", "0.2.0"),
+    ("get_weekly_review", "0.2.0"),
+    ("get_sprint_summary", "0.2.0"),
+    ("get_recently_completed", "0.2.0"),
+    ("get_recently_added", "0.2.0"),
+    ("get_task_changelog", "0.2.0"),
+    ("search_tasks_by_due_range", "0.2.0"),
+    ("get_task_dependencies_recursive", "0.2.0"),
+    ("cleanup_orphan_dependencies", "0.2.0"),
+    ("task_search_and_replace_description", "0.2.0"),
+    ("rename_project", "0.2.0"),
+    ("archive_project", "0.2.0"),
+    ("unarchive_project", "0.2.0"),
+    ("get_tag_usage_report", "0.2.0"),
+    ("get_tag_details", "0.2.0"),
+    ("normalize_tags", "0.2.0"),
+    ("register_template", "0.2.0"),
+    ("add_task_from_template", "0.2.0"),
+    ("list_templates", "0.2.0"),
+    ("delete_template", "0.2.0"),
+    ("stream_list_tasks", "0.2.0"),
+    ("suggest_projects", "0.2.0"),
+    ("suggest_tags", "0.2.0"),
+    ("get_task_effort_report", "0.2.0"),
+    ("health_check", "0.2.0"),
+    ("get_server_info", "0.2.0"),
+    ("get_metrics", "0.2.0"),
+    ("task_split", "0.2.0"),
+    ("merge_tasks", "0.2.0"),
+    ("create_task_chain", "0.2.0"),
+    ("task_inbox_process", "0.2.0"),
+    ("task_capture", "0.2.0"),
+    ("get_someday_maybe_list", "0.2.0"),
+    ("add_someday_task", "0.2.0"),
+    ("save_filter_preset", "0.2.0"),
+    ("list_filter_presets", "0.2.0"),
+    ("delete_filter_preset", "0.2.0"),
+    ("run_filter_preset", "0.2.0"),
+    ("task_quick_add", "0.2.0"),
+    ("fuzzy_search_tasks", "0.2.0"),
+    ("get_tasks_due_today", "0.2.0"),
+    ("get_tasks_due_this_week", "0.2.0"),
+    ("urgency_report", "0.2.0"),
+    ("task_review", "0.2.0"),
+    ("task_describe", "0.2.0"),
+    ("validate_task_data", "0.2.0"),
+    ("task_backup", "0.2.0"),
+    ("task_restore", "0.2.0"),
+    ("describe_filters", "0.2.0"),
+    ("prepend_to_task", "0.2.0"),
+    ("append_to_task", "0.2.0"),
+    ("task_done_with_note", "0.2.0"),
+    ("batch_import_from_csv", "0.2.0"),
+    ("get_completion_rate", "0.2.0"),
+    ("close_project", "0.2.0"),
+    ("get_task_age_report", "0.2.0"),
+    ("get_next_week_tasks", "0.2.0"),
+    ("deprioritize_project", "0.2.0"),
+    ("escalate_overdue", "0.2.0"),
+    ("get_tool_info", "0.2.0"),
+    ("get_task_urgency_factors", "0.2.0"),
+    ("batch_create_daily_recurring", "0.2.0"),
+    ("task_unblock", "0.2.0"),
+    ("link_to_github_issue", "0.2.0"),
+    ("get_tasks_with_url", "0.2.0"),
+    ("tag_tasks_by_project_prefix", "0.2.0"),
+    ("estimate_completion_date", "0.2.0"),
+    ("recurring_task_status", "0.2.0"),
+    ("mark_all_waiting_as_pending", "0.2.0"),
+    ("dependency_visualization", "0.2.0"),
+    ("task_notes_to_document", "0.2.0"),
+    ("get_task_siblings", "0.2.0"),
+    ("reorder_task_priority", "0.2.0"),
+    ("check_task_exists", "0.2.0"),
+    ("timew_start", "0.2.0"),
+    ("timew_stop", "0.2.0"),
+    ("get_timewarrior_summary", "0.2.0"),
+];
+
+/// [`TOOL_CHANGELOG`] entry for `tool`.
+fn tool_version(tool: &str) -> Option<&'static str> {
+    TOOL_CHANGELOG
+        .iter()
+        .find(|(name, _)| *name == tool)
+        .map(|(_, version)| *version)
+}
+
+/// [`TOOL_CHANGELOG`] condensed for the server instructions: tools are listed per version,
+/// except for the most common version, which stands in for "all others".
+fn changelog_summary() -> String {
+    let mut by_version: std::collections::BTreeMap<&str, Vec<&str>> = Default::default();
+    for (tool, version) in TOOL_CHANGELOG {
+        by_version.entry(version).or_default().push(tool);
+    }
+    let common = by_version
+        .iter()
+        .max_by_key(|(_, tools)| tools.len())
+        .map(|(version, _)| *version);
+    let versions: Vec<String> = by_version
+        .iter()
+        .map(|(version, tools)| {
+            if Some(*version) == common {
+                format!("{version}: all others")
+            } else {
+                format!("{version}: {}", tools.join(" · "))
+            }
+        })
+        .collect();
+    format!(
+        "Tool versions (last changed) — {}. get_tool_info returns a tool's schema and version.",
+        versions.join("; ")
+    )
+}
+
+// ── JSON envelope ────────────────────────────────────────────────────────────
+
+/// Add the [`CommonRequestFields`] properties to a tool's input schema.
+fn with_common_fields(mut tool: Tool) -> Tool {
+    let common = rmcp::handler::server::common::schema_for_type::<CommonRequestFields>();
+    let Some(extra) = common.get("properties").and_then(|p| p.as_object()) else {
+        return tool;
+    };
+    let mut schema = (*tool.input_schema).clone();
+    let properties = schema
+        .entry("properties")
+        .or_insert_with(|| serde_json::Value::Object(Default::default()));
+    if let Some(properties) = properties.as_object_mut() {
+        for (key, value) in extra {
+            properties
+                .entry(key.clone())
+                .or_insert_with(|| value.clone());
+        }
+    }
+    tool.input_schema = Arc::new(schema);
+    tool
+}
+
+/// Render a tool outcome as `{"tool", "status", "data", "timestamp"}`. Errors become an
+/// `is_error` result carrying the envelope, so clients always get JSON back.
+fn json_envelope(tool: &str, result: Result<CallToolResult, McpError>) -> CallToolResult {
+    let (status, data) = match &result {
+        Ok(r) if r.is_error == Some(true) => ("error", text_blocks(r)),
+        Ok(r) => ("ok", text_blocks(r)),
+        Err(e) => ("error", e.message.to_string()),
+    };
+    let envelope = serde_json::json!({
+        "tool": tool,
+        "status": status,
+        "data": data,
+        "timestamp": Utc::now().to_rfc3339(),
+    });
+    let content = vec![Content::text(envelope.to_string())];
+    if status == "ok" {
+        CallToolResult::success(content)
+    } else {
+        CallToolResult::error(content)
+    }
+}
+
+/// Every text block of a tool result, joined by newlines.
+fn text_blocks(result: &CallToolResult) -> String {
+    result
+        .content
+        .iter()
+        .filter_map(|c| c.as_text())
+        .map(|t| t.text.as_str())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// ── Change notifications ─────────────────────────────────────────────────────
+
+/// Logger name on the `notifications/message` events announcing task changes.
+const CHANGE_LOGGER: &str = "taskwarrior";
+
+/// `_meta` key on an `add_task` result holding the project the task went into, so the
+/// notification needn't resolve `project: "auto"` a second time.
+const PROJECT_META: &str = "taskwarrior/project";
+
+/// Event announced to the client when `tool` succeeds, for the tools that change a task.
+fn change_event(tool: &str) -> Option<&'static str> {
+    match tool {
+        "add_task" => Some("task_created"),
+        "modify_task" => Some("task_modified"),
+        "complete_task" => Some("task_completed"),
+        "delete_task" => Some("task_deleted"),
+        _ => None,
+    }
+}
+
+// ── Metrics ──────────────────────────────────────────────────────────────────
+
+#[derive(Debug, Default, Clone)]
+struct ToolMetrics {
+    calls: u64,
+    errors: u64,
+    total: Duration,
+    last_called: Option<DateTime<Local>>,
+}
+
+/// Per-tool call statistics, recorded by `call_tool` for every tool invocation.
+#[derive(Debug, Default, Clone)]
+struct Metrics {
+    tools: Arc<tokio::sync::Mutex<HashMap<String, ToolMetrics>>>,
+}
+
+impl Metrics {
+    async fn record(&self, tool: &str, elapsed: Duration, failed: bool) {
+        let mut tools = self.tools.lock().await;
+        let entry = tools.entry(tool.to_string()).or_default();
+        entry.calls += 1;
+        entry.errors += u64::from(failed);
+        entry.total += elapsed;
+        entry.last_called = Some(Local::now());
+    }
+
+    /// Render as a `Tool | Calls | Errors | Avg ms | Last called` table, busiest tool first.
+    async fn table(&self) -> String {
+        let tools = self.tools.lock().await;
+        let mut rows: Vec<(&String, &ToolMetrics)> = tools.iter().collect();
+        rows.sort_by(|a, b| b.1.calls.cmp(&a.1.calls).then_with(|| a.0.cmp(b.0)));
+        let width = rows
+            .iter()
+            .map(|(name, _)| name.len())
+            .chain(["Tool".len()])
+            .max()
+            .unwrap_or(0);
+        let mut out = format!(
+            "{:<width$} | Calls | Errors | Avg ms | Last called\n{}-|-------|--------|--------|--------------------",
+            "Tool",
+            "-".repeat(width)
+        );
+        for (name, m) in rows {
+            let avg_ms = m.total.as_secs_f64() * 1000.0 / m.calls.max(1) as f64;
+            let last = m
+                .last_called
+                .map(|t| t.format("%Y-%m-%d %H:%M:%S").to_string())
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "\n{name:<width$} | {:<5} | {:<6} | {avg_ms:<6.1} | {last}",
+                m.calls, m.errors
+            ));
+        }
+        out
+    }
+}
+
+// ── UDAs ─────────────────────────────────────────────────────────────────────
+
+/// Capability extension under which `get_info` advertises the configured UDAs.
+const UDA_EXTENSION: &str = "taskwarrior/udas";
+
+/// How often the cached UDA list is re-read from `task _udas`.
+const UDA_REFRESH_INTERVAL: Duration = Duration::from_secs(600);
+
+/// UDA that `link_to_github_issue` sets to the issue number, when the taskrc defines it.
+const GITHUB_ISSUE_UDA: &str = "github_issue";
+
+// ── Shutdown ─────────────────────────────────────────────────────────────────
+
+/// How long in-flight `task` invocations get to finish after a shutdown signal
+/// (`$TASKWARRIOR_DRAIN_SECS`).
+const DEFAULT_DRAIN_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Tracks in-flight `task` invocations so shutdown can wait for them to finish.
+#[derive(Debug, Default, Clone)]
+struct Drain {
+    inner: Arc<DrainState>,
+}
+
+#[derive(Debug, Default)]
+struct DrainState {
+    active: std::sync::atomic::AtomicUsize,
+    closed: std::sync::atomic::AtomicBool,
+    idle: tokio::sync::Notify,
+}
+
+/// Held for the duration of one `task` invocation.
+struct DrainGuard(Drain);
+
+impl Drain {
+    /// Register an invocation, or `None` once shutdown has begun.
+    fn enter(&self) -> Option<DrainGuard> {
+        use std::sync::atomic::Ordering::SeqCst;
+        // Count first, then check, so `wait_idle` can't miss an invocation that raced `close`.
+        self.inner.active.fetch_add(1, SeqCst);
+        let guard = DrainGuard(self.clone());
+        (!self.inner.closed.load(SeqCst)).then_some(guard)
+    }
+
+    /// Refuse new invocations from now on.
+    fn close(&self) {
+        self.inner
+            .closed
+            .store(true, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    /// Wait until no invocation is running. Returns `false` if `timeout` elapsed first.
+    async fn wait_idle(&self, timeout: Duration) -> bool {
+        let idle = async {
+            loop {
+                let notified = self.inner.idle.notified();
+                if self.inner.active.load(std::sync::atomic::Ordering::SeqCst) == 0 {
+                    return;
+                }
+                notified.await;
+            }
+        };
+        tokio::time::timeout(timeout, idle).await.is_ok()
+    }
+}
+
+impl Drop for DrainGuard {
+    fn drop(&mut self) {
+        let state = &self.0.inner;
+        if state
+            .active
+            .fetch_sub(1, std::sync::atomic::Ordering::SeqCst)
+            == 1
+        {
+            state.idle.notify_waiters();
+        }
+    }
+}
+
+/// Resolves on Ctrl-C, or on SIGTERM on Unix.
+async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        match signal(SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                tokio::select! {
+                    _ = tokio::signal::ctrl_c() => {}
+                    _ = sigterm.recv() => {}
+                }
+            }
+            Err(e) => {
+                tracing::warn!("Cannot listen for SIGTERM: {e}");
+                let _ = tokio::signal::ctrl_c().await;
+            }
+        }
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = tokio::signal::ctrl_c().await;
+    }
+}
+
+// ── Server ────────────────────────────────────────────────────────────────────
+
+/// Default for [`TaskWarriorServer::timeout`].
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Phrases (lowercase) that mark a `task` stderr line as a deprecation notice.
+const DEPRECATION_PATTERNS: &[&str] = &["deprecated", "deprecation", "will be removed"];
+
+/// Lines of `task` stderr that announce deprecated features.
+fn deprecation_notices(stderr: &str) -> Vec<&str> {
+    stderr
+        .lines()
+        .map(str::trim)
+        .filter(|line| {
+            let line = line.to_lowercase();
+            DEPRECATION_PATTERNS.iter().any(|p| line.contains(p))
+        })
+        .collect()
+}
+
+/// Parse `$TASKWARRIOR_RC_OVERRIDES` (`"hooks=off;gc=off"`) into key/value pairs. Blank
+/// entries are skipped, a leading `rc.` on a key is tolerated, and an entry without `=`
+/// gets an empty value.
+fn parse_rc_overrides(spec: &str) -> Vec<(&str, &str)> {
+    spec.split(';')
+        .map(str::trim)
+        .filter(|entry| !entry.is_empty())
+        .map(|entry| {
+            let entry = entry.trim_start_matches("rc.");
+            entry.split_once('=').unwrap_or((entry, ""))
+        })
+        .collect()
+}
+
+/// `task` commands that modify data (or the taskrc); skipped in dry-run mode.
+const WRITE_COMMANDS: &[&str] = &[
+    "add",
+    "annotate",
+    "append",
+    "config",
+    "context",
+    "delete",
+    "denotate",
+    "done",
+    "duplicate",
+    "import",
+    "log",
+    "modify",
+    "prepend",
+    "purge",
+    "start",
+    "stop",
+    "undo",
+];
+
+/// Read-only `task` commands and reports. Together with [`WRITE_COMMANDS`] these tell the
+/// command word apart from bare description words in the filter before it.
+const READ_COMMANDS: &[&str] = &[
+    "active",
+    "all",
+    "blocked",
+    "blocking",
+    "burndown",
+    "calendar",
+    "columns",
+    "commands",
+    "completed",
+    "count",
+    "diagnostics",
+    "export",
+    "ghistory",
+    "help",
+    "history",
+    "ids",
+    "info",
+    "information",
+    "list",
+    "long",
+    "ls",
+    "minimal",
+    "newest",
+    "next",
+    "oldest",
+    "overdue",
+    "projects",
+    "ready",
+    "recurring",
+    "reports",
+    "show",
+    "stats",
+    "summary",
+    "tags",
+    "timesheet",
+    "udas",
+    "unblocked",
+    "uuids",
+    "version",
+    "waiting",
+];
+
+/// Whether a `task` argument list runs a [`WRITE_COMMANDS`] command. Only the command
+/// word counts: `rc.` overrides and filter terms before it are skipped, and anything
+/// after it is arguments. Abbreviations of a write command count as writes.
+fn modifies_data(args: &[&str]) -> bool {
+    for arg in args.iter().filter(|arg| !arg.starts_with("rc.")) {
+        if WRITE_COMMANDS.contains(arg) {
+            return true;
+        }
+        if arg.starts_with('_') || READ_COMMANDS.contains(arg) {
+            return false;
+        }
+        // Taskwarrior accepts abbreviations down to rc.abbreviation.minimum (2).
+        if arg.len() >= 2 && WRITE_COMMANDS.iter().any(|cmd| cmd.starts_with(arg)) {
+            return true;
+        }
+        // Otherwise a filter term: an ID, UUID, attribute, tag or description word.
+    }
+    false
+}
+
+/// Fluent constructor for [`TaskWarriorServer`]. [`Default`] ignores the environment;
+/// [`TaskWarriorServerBuilder::from_env`] starts from it (see the README), and explicit
+/// settings override either.
+#[derive(Debug, Clone, Default)]
+struct TaskWarriorServerBuilder {
+    data_dir: Option<PathBuf>,
+    task_bin: Option<PathBuf>,
+    task_rc: Option<PathBuf>,
+    timeout: Option<Duration>,
+    dry_run: bool,
+    max_retries: u8,
+    rc_overrides: Vec<String>,
+}
+
+impl TaskWarriorServerBuilder {
+    /// Builder configured from `TASKWARRIOR_*` and `TASKRC`, as `main` uses it.
+    fn from_env() -> Self {
+        let env = |key| std::env::var(key).ok().filter(|v: &String| !v.is_empty());
+        let mut builder = Self::default();
+        // Unless set explicitly, leave data_dir unset when nothing exists yet so Taskwarrior
+        // falls back to the `data.location` in the user's taskrc.
+        let explicit = env("TASKWARRIOR_DATA_DIR").is_some();
+        if let Some(dir) = detect_data_dir().filter(|dir| explicit || dir.is_dir()) {
+            builder = builder.data_dir(dir);
+        }
+        if let Some(bin) = env("TASKWARRIOR_BIN") {
+            builder = builder.task_binary(bin);
+        }
+        if let Some(rc) = env("TASKRC") {
+            builder = builder.task_rc(rc);
+        }
+        if let Some(secs) = env("TASKWARRIOR_TIMEOUT_SECS").and_then(|s| s.parse().ok()) {
+            builder = builder.timeout(Duration::from_secs(secs));
+        }
+        if let Some(flag) = env("TASKWARRIOR_DRY_RUN") {
+            builder = builder.dry_run(matches!(flag.as_str(), "1" | "true" | "yes"));
+        }
+        if let Some(retries) = env("TASKWARRIOR_MAX_RETRIES").and_then(|s| s.parse().ok()) {
+            builder = builder.max_retries(retries);
+        }
+        if let Some(spec) = env("TASKWARRIOR_RC_OVERRIDES") {
+            for (key, value) in parse_rc_overrides(&spec) {
+                builder = builder.rc_override(key, value);
+            }
+        }
+        builder
+    }
+
+    /// Taskwarrior data directory, passed as `rc.data.location`. Need not exist yet.
+    fn data_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.data_dir = Some(dir.into());
+        self
+    }
+
+    /// Taskwarrior executable.
+    fn task_binary(mut self, path: impl Into<PathBuf>) -> Self {
+        self.task_bin = Some(path.into());
+        self
+    }
+
+    /// taskrc file, passed to `task` as `$TASKRC`.
+    fn task_rc(mut self, path: impl Into<PathBuf>) -> Self {
+        self.task_rc = Some(path.into());
+        self
+    }
+
+    /// Upper bound on a single `task` invocation.
+    fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Skip commands that modify data, returning the command line that would have run.
+    fn dry_run(mut self, dry_run: bool) -> Self {
+        self.dry_run = dry_run;
+        self
+    }
+
+    /// How often to retry an invocation that failed to start, or a read that timed out.
+    fn max_retries(mut self, retries: u8) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    /// Pass `rc.<key>=<value>` to every invocation. Later overrides of a key win.
+    fn rc_override(mut self, key: &str, value: &str) -> Self {
+        self.rc_overrides.push(format!("rc.{key}={value}"));
+        self
+    }
+
+    fn build(self) -> TaskWarriorServer {
+        #[allow(unused_mut)]
+        let mut tool_router = TaskWarriorServer::tool_router();
+        #[cfg(feature = "timew-integration")]
+        {
+            tool_router += TaskWarriorServer::timew_router();
+        }
+        let mut server = TaskWarriorServer {
+            tool_router,
+            data_dir: self.data_dir,
+            templates: Default::default(),
+            filter_presets: FilterPresets::default(),
+            task_bin: self.task_bin.unwrap_or_else(|| PathBuf::from("task")),
+            task_rc: self.task_rc,
+            timeout: self.timeout.unwrap_or(DEFAULT_TIMEOUT),
+            dry_run: self.dry_run,
+            max_retries: self.max_retries,
+            rc_overrides: self.rc_overrides,
+            udas: Default::default(),
+            metrics: Metrics::default(),
+            drain: Drain::default(),
+        };
+        server.templates = Arc::new(Mutex::new(load_json_map(&server.data_path(TEMPLATES_FILE))));
+        server.filter_presets = FilterPresets::load(server.data_path(FILTER_PRESETS_FILE));
+        server
+    }
+}
+
+#[derive(Clone)]
+struct TaskWarriorServer {
+    tool_router: ToolRouter<TaskWarriorServer>,
+    /// Overrides the taskwarrior data directory. Auto-detected via [`detect_data_dir`];
+    /// tests point it at a temp dir for isolation.
+    data_dir: Option<PathBuf>,
+    /// Named `add_task` presets, mirrored to `mcp_templates.json` in the data directory.
+    templates: Arc<Mutex<HashMap<String, AddTaskRequest>>>,
+    filter_presets: FilterPresets,
+    /// Taskwarrior executable: `$TASKWARRIOR_BIN`, or `task` looked up on `$PATH`.
+    task_bin: PathBuf,
+    /// taskrc passed as `$TASKRC`; unset leaves Taskwarrior's own lookup alone.
+    task_rc: Option<PathBuf>,
+    /// Upper bound on a single `task` invocation (`$TASKWARRIOR_TIMEOUT_SECS`).
+    timeout: Duration,
+    /// Skip [`WRITE_COMMANDS`], echoing the command line instead.
+    dry_run: bool,
+    /// Retries for an invocation that failed to start, or a read that timed out.
+    max_retries: u8,
+    /// User-defined attribute names from `task _udas`, advertised by `get_info`.
+    /// Filled by [`TaskWarriorServer::refresh_udas`].
+    udas: Arc<RwLock<Vec<String>>>,
+    /// Extra `rc.<key>=<value>` arguments passed to every invocation
+    /// (`$TASKWARRIOR_RC_OVERRIDES`).
+    rc_overrides: Vec<String>,
+    metrics: Metrics,
+    drain: Drain,
+}
+
+impl TaskWarriorServer {
+    /// Path of a file inside the Taskwarrior data directory.
+    /// Without `$HOME` or an explicit directory this is relative to the working directory.
+    fn data_path(&self, file: &str) -> PathBuf {
+        self.data_dir
+            .clone()
+            .or_else(detect_data_dir)
+            .unwrap_or_default()
+            .join(file)
+    }
+
+    /// Re-read the UDA list. On failure the previous list is kept.
+    async fn refresh_udas(&self) {
+        match self.run(&["_udas"]).await {
+            Ok(out) => {
+                let mut udas: Vec<String> = out.lines().map(|l| l.trim().to_string()).collect();
+                udas.retain(|uda| !uda.is_empty());
+                udas.sort();
+                *self.udas.write().expect("uda lock poisoned") = udas;
+            }
+            Err(e) => tracing::warn!("Failed to read UDAs: {}", e.message),
+        }
+    }
+
+    /// Write the template registry back to disk.
+    async fn save_templates(&self) -> Result<(), McpError> {
+        let json = {
+            let templates = self.templates.lock().expect("template lock poisoned");
+            serde_json::to_string_pretty(&*templates)
+        }
+        .map_err(|e| McpError::internal_error(format!("Failed to encode templates: {e}"), None))?;
+        let path = self.data_path(TEMPLATES_FILE);
+        tokio::fs::write(&path, json).await.map_err(|e| {
+            McpError::internal_error(format!("Failed to write {}: {e}", path.display()), None)
+        })
+    }
+
+    async fn run(&self, args: &[&str]) -> Result<String, McpError> {
+        let Some(_guard) = self.drain.enter() else {
+            return Err(McpError::internal_error("Server is shutting down", None));
+        };
+        if self.dry_run && modifies_data(args) {
+            tracing::info!(
+                request_id = current_request_id(),
+                ?args,
+                "dry run: skipping task"
+            );
+            return Ok(format!("Dry run: would run `task {}`", args.join(" ")));
+        }
+        let mut cmd = Command::new(&self.task_bin);
+        cmd.kill_on_drop(true);
+        if let Some(rc) = &self.task_rc {
+            cmd.env("TASKRC", rc);
+        }
+        cmd.arg("rc.confirmation=no");
+        if let Some(dir) = &self.data_dir {
+            cmd.arg(format!("rc.data.location={}", dir.display()));
+        }
+        cmd.args(&self.rc_overrides);
+        cmd.args(args);
+        tracing::debug!(request_id = current_request_id(), ?args, "running task");
+
+        let mut attempt = 0;
+        let output = loop {
+            let (err, retryable) = match tokio::time::timeout(self.timeout, cmd.output()).await {
+                Ok(Ok(output)) => break output,
+                Ok(Err(e)) => (format!("Failed to run task: {e}"), true),
+                // A write that timed out may already be committed; running it again could
+                // add the task twice.
+                Err(_) => (
+                    format!("task timed out after {}s", self.timeout.as_secs()),
+                    !modifies_data(args),
+                ),
+            };
+            if !retryable || attempt >= self.max_retries {
+                return Err(McpError::internal_error(err, None));
+            }
+            attempt += 1;
+            tracing::warn!(
+                request_id = current_request_id(),
+                attempt,
+                "{err}; retrying"
+            );
+        };
+
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        // Surface these even on success, when stderr is otherwise dropped.
+        for notice in deprecation_notices(&stderr) {
+            tracing::warn!(request_id = current_request_id(), "Taskwarrior: {notice}");
+        }
+
+        if !output.status.success() && stdout.is_empty() {
+            return Err(McpError::internal_error(
+                if !stderr.is_empty() {
+                    stderr
+                } else {
+                    format!("task exited with status {}", output.status)
+                },
+                None,
+            ));
+        }
+
+        Ok(if !stdout.is_empty() { stdout } else { stderr })
+    }
+
+    /// Run a read-only report. Taskwarrior exits non-zero when a filter matches nothing,
+    /// so errors and empty output both collapse to `empty_msg`.
+    async fn report(&self, args: &[String], empty_msg: &str) -> CallToolResult {
+        let refs: Vec<&str> = args.iter().map(String::as_str).collect();
+        let out = self
+            .run(&refs)
+            .await
+            .ok()
+            .filter(|out| !out.is_empty())
+            .unwrap_or_else(|| empty_msg.to_string());
+        CallToolResult::success(vec![Content::text(out)])
+    }
+
+    /// Run `task <filter> count` and parse the number of matching tasks.
+    async fn count(&self, filter: &[String]) -> Result<usize, McpError> {
+        let mut args: Vec<&str> = filter.iter().map(String::as_str).collect();
+        args.push("count");
+        let out = self.run(&args).await?;
+        out.parse().map_err(|_| {
+            McpError::internal_error(format!("Unexpected `task count` output: {out:?}"), None)
+        })
+    }
+
+    /// Run `command` (e.g. `done`, `annotate <note>`) on every task matching `filter` and
+    /// return how many matched. `rc.bulk=0` skips the per-task confirmation prompt, which
+    /// would otherwise fail on our null stdin once more than a couple of tasks match.
+    async fn bulk(&self, filter: &[String], command: &[&str]) -> Result<usize, McpError> {
+        let matched = self.count(filter).await?;
+        if matched == 0 {
+            return Ok(0);
+        }
+        let mut args = vec!["rc.bulk=0", "rc.recurrence.confirmation=no"];
+        args.extend(filter.iter().map(String::as_str));
+        args.extend(command);
+        self.run(&args).await?;
+        Ok(matched)
+    }
+
+    /// Apply `modifications` to every task matching `filter` and return how many matched.
+    async fn modify_all(
+        &self,
+        filter: &[String],
+        modifications: &[String],
+    ) -> Result<usize, McpError> {
+        let mut command = vec!["modify"];
+        command.extend(modifications.iter().map(String::as_str));
+        self.bulk(filter, &command).await
+    }
+
+    /// Clear the wait date on every waiting task in `project` (and its subprojects) and
+    /// return how many were released. Matches `+WAITING` rather than `status:waiting`, which
+    /// Taskwarrior 2.6 deprecated and 3.x dropped.
+    async fn release_waiting(&self, project: &str) -> Result<usize, McpError> {
+        validate_project_name("project", project)?;
+        let filter = [format!("project:{project}"), "+WAITING".to_string()];
+        self.modify_all(&filter, &["wait:".to_string()]).await
+    }
+
+    /// Resources advertised via `resources/list`: one per project, one per pending task.
+    async fn list_task_resources(&self) -> Result<Vec<Resource>, McpError> {
+        let pending = ["status:pending".to_string()];
+        let (projects, tasks) = tokio::join!(self.run(&["_projects"]), self.export(&pending));
+        let mut resources: Vec<Resource> = projects?
+            .lines()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .map(|p| {
+                let mut raw =
+                    RawResource::new(format!("task://project/{p}"), format!("project:{p}"));
+                raw.mime_type = Some("application/json".to_string());
+                raw.no_annotation()
+            })
+            .collect();
+        for task in tasks? {
+            let uuid = field_text(task.get("uuid"));
+            let mut raw = RawResource::new(
+                format!("task://task/{uuid}"),
+                field_text(task.get("description")),
+            );
+            raw.mime_type = Some("application/json".to_string());
+            resources.push(raw.no_annotation());
+        }
+        Ok(resources)
+    }
+
+    /// Resolve a `task://task/<uuid>` or `task://project/<name>` URI to its JSON export.
+    async fn read_task_resource(&self, uri: &str) -> Result<ReadResourceResult, McpError> {
+        let filter = if let Some(uuid) = uri.strip_prefix("task://task/") {
+            // Anything else would be exported as a filter, matching unrelated tasks.
+            if !is_uuid(uuid) {
+                return Err(McpError::resource_not_found(
+                    format!("Not a task UUID: {uuid:?}"),
+                    None,
+                ));
+            }
+            uuid.to_string()
+        } else if let Some(project) = uri.strip_prefix("task://project/") {
+            validate_project_name("project", project)?;
+            format!("project:{project}")
+        } else {
+            return Err(McpError::resource_not_found(
+                format!("Unknown resource URI {uri:?}"),
+                None,
+            ));
+        };
+        let tasks = self.export(&[filter]).await?;
+        if tasks.is_empty() {
+            return Err(McpError::resource_not_found(
+                format!("No tasks found for {uri}"),
+                None,
+            ));
+        }
+        // A task URI is a single object; a project URI is the array of its tasks.
+        let json = if uri.starts_with("task://task/") {
+            tasks[0].to_string()
+        } else {
+            serde_json::Value::from(tasks).to_string()
+        };
+        Ok(ReadResourceResult {
+            contents: vec![ResourceContents::TextResourceContents {
+                uri: uri.to_string(),
+                mime_type: Some("application/json".to_string()),
+                text: json,
+                meta: None,
+            }],
+        })
+    }
+
+    /// Run `task <filter> export` and parse the resulting JSON array.
+    async fn export(&self, filter: &[String]) -> Result<Vec<serde_json::Value>, McpError> {
+        let mut args: Vec<&str> = filter.iter().map(String::as_str).collect();
+        args.push("export");
+        let out = self.run(&args).await?;
+        if out.is_empty() {
+            return Ok(Vec::new());
+        }
+        serde_json::from_str(&out).map_err(|e| {
+            McpError::internal_error(format!("Failed to parse task export: {e}"), None)
+        })
+    }
+
+    /// Export the task with ID or UUID `id`.
+    async fn export_one(&self, id: &str) -> Result<serde_json::Value, McpError> {
+        self.export(&[id.to_string()])
+            .await?
+            .into_iter()
+            .next()
+            .ok_or_else(|| McpError::invalid_params(format!("Task {id} not found"), None))
+    }
+
+    /// Name of the active Taskwarrior context, if one is set.
+    async fn active_context(&self) -> Result<Option<String>, McpError> {
+        let context = self.run(&["_get", "rc.context"]).await?;
+        Ok(Some(context).filter(|c| !c.is_empty()))
+    }
+
+    /// Run an `add …` command line, appending the new task's UUID to the
+    /// "Created task N." confirmation.
+    async fn run_add(&self, args: &[String]) -> Result<String, McpError> {
+        let refs: Vec<&str> = args.iter().map(String::as_str).collect();
+        let out = self.run(&refs).await?;
+
+        // Numeric IDs shift as tasks complete; hand back the UUID so callers can keep a
+        // stable reference to the task they just created.
+        let created = out.lines().find_map(|line| {
+            line.strip_prefix("Created task ")?
+                .strip_suffix('.')
+                .map(str::to_string)
+        });
+        Ok(match created {
+            Some(id) => {
+                let uuid = self.run(&[&id, "_uuids"]).await?;
+                out.replace(
+                    &format!("Created task {id}."),
+                    &format!("Created task {id} (uuid: {uuid})."),
+                )
+            }
+            None => out,
+        })
+    }
+
+    /// Archive every file in the data directory to `output` (or to a timestamped file
+    /// inside it, when `output` is a directory). Returns the archive path, the number of
+    /// files, and the archive size in bytes.
+    async fn write_backup(&self, mut output: PathBuf) -> Result<(PathBuf, usize, usize), McpError> {
+        let data_dir = self.data_path("");
+        let mut entries = tokio::fs::read_dir(&data_dir)
+            .await
+            .map_err(|e| io_error("read", &data_dir, e))?;
+        let mut files = Vec::new();
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| io_error("read", &data_dir, e))?
+        {
+            let path = entry.path();
+            if !path.is_file() {
+                continue;
+            }
+            let contents = tokio::fs::read(&path)
+                .await
+                .map_err(|e| io_error("read", &path, e))?;
+            files.push((entry.file_name().to_string_lossy().into_owned(), contents));
+        }
+        files.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let now = Local::now();
+        if output.is_dir() {
+            output.push(format!(
+                "taskwarrior-backup-{}.tar.gz",
+                now.format("%Y%m%d-%H%M%S")
+            ));
+        }
+        let archive = tar_gz(&files, now.timestamp().max(0) as u64)
+            .map_err(|e| McpError::internal_error(format!("Failed to compress: {e}"), None))?;
+        tokio::fs::write(&output, &archive)
+            .await
+            .map_err(|e| io_error("write", &output, e))?;
+        Ok((output, files.len(), archive.len()))
+    }
+
+    /// Create a task through `add_task` and return its UUID.
+    async fn add_for_uuid(&self, req: AddTaskRequest) -> Result<String, McpError> {
+        let out = text_content(&self.add_task(Parameters(req)).await?);
+        parse_created_uuid(&out)
+            .map(str::to_string)
+            .ok_or_else(|| McpError::internal_error(format!("Unexpected add output: {out}"), None))
+    }
+}
+
+#[tool_router]
+impl TaskWarriorServer {
+    /// Server configured entirely from the environment.
+    fn new() -> Self {
+        TaskWarriorServerBuilder::from_env().build()
+    }
+
+    #[tool(description = "\
+        Add a new task. `project` is REQUIRED — every task must belong to a project. \
+        Supports due dates (today/tomorrow/eow/eom/friday/ISO datetime), tags, \
+        dot-notation subprojects (e.g. Work.Backend), priorities (H/M/L), \
+        wait dates (hide until actionable), and scheduled dates (when you plan to start). \
+        Returns the numeric ID and the UUID — prefer the UUID in follow-up calls, since \
+        numeric IDs renumber as tasks complete. `project=\"auto\"` files the task under the \
+        active context's name (error if no context is set).")]
+    async fn add_task(
+        &self,
+        Parameters(req): Parameters<AddTaskRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let project = if req.project == AUTO_PROJECT {
+            self.active_context().await?.ok_or_else(|| {
+                McpError::invalid_params(
+                    "project \"auto\" needs an active Taskwarrior context \
+                     (set one with `task context <name>`)",
+                    None,
+                )
+            })?
+        } else {
+            req.project
+        };
+        let mut args = vec!["add".to_string(), req.description];
+        args.push(format!("project:{project}"));
+        if let Some(v) = req.due {
+            args.push(format!("due:{v}"));
+        }
+        if let Some(v) = req.priority {
+            args.push(format!("priority:{v}"));
+        }
+        if let Some(v) = req.wait {
+            args.push(format!("wait:{v}"));
+        }
+        if let Some(v) = req.scheduled {
+            args.push(format!("scheduled:{v}"));
+        }
+        if let Some(tags) = req.tags {
+            for t in tags {
+                args.push(format!("+{t}"));
+            }
+        }
+
+        let mut result = CallToolResult::success(vec![Content::text(self.run_add(&args).await?)]);
+        let mut meta = Meta::new();
+        meta.insert(PROJECT_META.to_string(), project.into());
+        result.meta = Some(meta);
+        Ok(result)
+    }
+
+    #[tool(description = "\
+        List tasks sorted by urgency. `project` is REQUIRED. \
+        ALWAYS use the most specific `filter` and `report` for your intent — \
+        do NOT call with no filter unless you genuinely need every pending task. \
+        Quick reference: actionable work → filter='+READY' or report='next'; \
+        overdue → filter='+OVERDUE'; due today → filter='+TODAY'; \
+        blocked → filter='+BLOCKED' or report='blocked'; \
+        high priority → filter='priority:H'; waiting/snoozed → report='waiting'. \
+        Only set `all_projects=true` for explicit cross-project requests. \
+        For large result sets pass `page`/`page_size` to get urgency-sorted JSON pages.")]
+    async fn list_tasks(
+        &self,
+        Parameters(req): Parameters<ListTasksRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut args = scoped_filter(&req.project, req.filter.as_deref(), req.all_projects);
+        for (field, attr, value) in [
+            ("due_after", "due.after", &req.due_after),
+            ("due_before", "due.before", &req.due_before),
+            ("created_after", "entry.after", &req.created_after),
+            ("created_before", "entry.before", &req.created_before),
+            ("modified_after", "modified.after", &req.modified_after),
+        ] {
+            if let Some(v) = value {
+                validate_date(field, v)?;
+                args.push(format!("{attr}:{v}"));
+            }
+        }
+
+        if req.page.is_some() || req.page_size.is_some() {
+            let page = req.page.unwrap_or(1).max(1) as usize;
+            let page_size = req.page_size.unwrap_or(25).max(1) as usize;
+            // Reports aren't available as JSON, so mirror `next`: pending unless the
+            // caller's filter picks a status itself.
+            if !args.iter().any(|a| a.starts_with("status:")) {
+                args.push("status:pending".to_string());
+            }
+            let mut tasks = self.export(&args).await?;
+            sort_by_urgency(&mut tasks);
+            let total = tasks.len();
+            let payload = serde_json::json!({
+                "tasks": tasks
+                    .into_iter()
+                    .skip((page - 1) * page_size)
+                    .take(page_size)
+                    .collect::<Vec<_>>(),
+                "total": total,
+                "page": page,
+                "pages": total.div_ceil(page_size),
+            });
+            return Ok(CallToolResult::success(vec![Content::text(
+                payload.to_string(),
+            )]));
+        }
+
+        args.push(req.report.unwrap_or_else(|| "next".to_string()));
+        Ok(self.report(&args, "No tasks found.").await)
+    }
+
+    #[tool(description = "\
+        Search tasks by regex pattern across descriptions and annotations. \
+        `project` is REQUIRED and automatically scopes the search. \
+        Only set `all_projects=true` for explicit cross-project searches.")]
+    async fn search_tasks(
+        &self,
+        Parameters(req): Parameters<SearchTasksRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut args = scoped_filter(&req.project, req.filter.as_deref(), req.all_projects);
+        args.push(format!("/{}/", req.pattern));
+        args.push("list".to_string());
+        Ok(self.report(&args, "No matching tasks.").await)
+    }
+
+    #[tool(description = "\
+        Get full details of a task by ID or UUID: all attributes, annotations, \
+        urgency score, dependencies, and timestamps.")]
+    async fn get_task(
+        &self,
+        Parameters(req): Parameters<TaskIdRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let out = self.run(&[&req.id, "information"]).await?;
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Modify a task's attributes. Pass modifications as a space-separated string: \
+        'due:friday priority:H +newtag -oldtag project:Work'. \
+        Clear a field by omitting its value: 'due: priority:'.")]
+    async fn modify_task(
+        &self,
+        Parameters(req): Parameters<ModifyTaskRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut args = vec![req.id, "modify".to_string()];
+        args.extend(req.modifications.split_whitespace().map(str::to_string));
+        let refs: Vec<&str> = args.iter().map(String::as_str).collect();
+        Ok(CallToolResult::success(vec![Content::text(
+            self.run(&refs).await?,
+        )]))
+    }
+
+    #[tool(description = "Mark a task as completed.")]
+    async fn complete_task(
+        &self,
+        Parameters(req): Parameters<TaskIdRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::text(
+            self.run(&[&req.id, "done"]).await?,
+        )]))
+    }
+
+    #[tool(description = "Permanently delete a task.")]
+    async fn delete_task(
+        &self,
+        Parameters(req): Parameters<TaskIdRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::text(
+            self.run(&[&req.id, "delete"]).await?,
+        )]))
+    }
+
+    #[tool(description = "\
+        Attach a timestamped annotation (note) to a task. \
+        Use for progress updates, links, or context that shouldn't be lost. \
+        `note` is single-line (line breaks become spaces); pass `note_lines` for a \
+        multi-paragraph note that keeps its line breaks.")]
+    async fn annotate_task(
+        &self,
+        Parameters(req): Parameters<AnnotateTaskRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let note = annotation_text(&req.note, req.note_lines.as_deref());
+        if note.is_empty() {
+            return Err(McpError::invalid_params(
+                "Provide a non-empty `note` or `note_lines`",
+                None,
+            ));
+        }
+        Ok(CallToolResult::success(vec![Content::text(
+            self.run(&[&req.id, "annotate", &note]).await?,
+        )]))
+    }
+
+    #[tool(description = "\
+        Export tasks as CSV for spreadsheets. `project` is REQUIRED. \
+        Pick attributes with `columns` (default: id, uuid, description, project, status, \
+        priority, due, scheduled, wait, tags). Dates use Taskwarrior's compact ISO format \
+        (20250615T143000Z); tags are space-separated within their cell.")]
+    async fn export_tasks_csv(
+        &self,
+        Parameters(req): Parameters<ExportCsvRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let filter = scoped_filter(&req.project, req.filter.as_deref(), req.all_projects);
+        let tasks = self.export(&filter).await?;
+        let columns = req
+            .columns
+            .unwrap_or_else(|| DEFAULT_CSV_COLUMNS.iter().map(|c| c.to_string()).collect());
+        Ok(CallToolResult::success(vec![Content::text(tasks_to_csv(
+            &tasks, &columns,
+        ))]))
+    }
+
+    #[tool(description = "\
+        Export a project's tasks as a Markdown checklist: `- [ ] description` for open tasks, \
+        `- [x] description` for completed ones. Set `include_metadata=true` to append due date \
+        and priority. `project` is REQUIRED.")]
+    async fn export_tasks_markdown(
+        &self,
+        Parameters(req): Parameters<ExportMarkdownRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let filter = scoped_filter(&req.project, req.filter.as_deref(), None);
+        let tasks = self.export(&filter).await?;
+        let include_metadata = req.include_metadata.unwrap_or(false);
+        let lines: Vec<String> = tasks
+            .iter()
+            .filter_map(|t| task_to_markdown(t, include_metadata))
+            .collect();
+        Ok(CallToolResult::success(vec![Content::text(
+            if lines.is_empty() {
+                "No tasks found.".to_string()
+            } else {
+                lines.join("\n")
+            },
+        )]))
+    }
+
+    #[tool(description = "\
+        Create tasks from a Markdown checklist. Every `- [ ] description` line becomes a task in \
+        `project` (REQUIRED); an optional trailing group like '(due: tomorrow, priority: H)' \
+        sets due/priority/scheduled/wait. Checked items and other lines are skipped. \
+        Returns how many tasks were created.")]
+    async fn import_tasks_from_markdown(
+        &self,
+        Parameters(req): Parameters<ImportMarkdownRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut created = Vec::new();
+        let mut skipped = 0;
+        let mut failures = Vec::new();
+
+        for (n, line) in req.markdown.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let Some(item) = parse_checklist_item(line) else {
+                tracing::debug!(
+                    "import_tasks_from_markdown: skipping line {}: {line:?}",
+                    n + 1
+                );
+                skipped += 1;
+                continue;
+            };
+            let result = self
+                .add_task(Parameters(AddTaskRequest {
+                    description: item.description,
+                    project: req.project.clone(),
+                    due: item.due,
+                    tags: None,
+                    priority: item.priority.or_else(|| req.default_priority.clone()),
+                    wait: item.wait,
+                    scheduled: item.scheduled,
+                }))
+                .await;
+            match result {
+                Ok(r) => created.push(text_content(&r)),
+                Err(e) => failures.push(format!("line {}: {}", n + 1, e.message)),
+            }
+        }
+
+        let mut out = format!(
+            "Created {} task(s) in project {} ({} line(s) skipped, {} failed).",
+            created.len(),
+            req.project,
+            skipped,
+            failures.len()
+        );
+        for line in created.iter().chain(&failures) {
+            out.push_str(&format!("\n{line}"));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Export tasks as an iCalendar (.ics) document of VTODO entries for calendar clients. \
+        Each task carries SUMMARY, DUE, PRIORITY (H=1, M=5, L=9), UID (task UUID), and STATUS. \
+        `project` is REQUIRED.")]
+    async fn export_tasks_ical(
+        &self,
+        Parameters(req): Parameters<ExportIcalRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let filter = scoped_filter(&req.project, req.filter.as_deref(), req.all_projects);
+        let tasks = self.export(&filter).await?;
+        Ok(CallToolResult::success(vec![Content::text(tasks_to_ical(
+            &tasks,
+        ))]))
+    }
+
+    #[tool(description = "\
+        Bulk-import tasks from a JSON array in Taskwarrior export format. Each element needs a \
+        \"description\"; elements without one are skipped. Set `project_override` to file every \
+        task under one project. Objects carrying an existing \"uuid\" update that task instead \
+        of creating a new one. Returns how many tasks were imported and skipped.")]
+    async fn import_tasks_from_json_array(
+        &self,
+        Parameters(req): Parameters<ImportJsonArrayRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let (tasks, mut skipped) =
+            prepare_import(&req.json_array, req.project_override.as_deref())?;
+        if tasks.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "Imported 0 tasks, skipped {}.\n{}",
+                skipped.len(),
+                skipped.join("\n")
+            ))]));
+        }
+
+        let io_err = |e: std::io::Error| {
+            McpError::internal_error(format!("Failed to write import file: {e}"), None)
+        };
+        let mut file = tempfile::Builder::new()
+            .suffix(".json")
+            .tempfile()
+            .map_err(io_err)?;
+        serde_json::to_writer(&mut file, &tasks).map_err(|e| io_err(e.into()))?;
+        let path = file.path().display().to_string();
+        let out = self.run(&["import", &path]).await?;
+
+        // `task import` prints one " add"/" mod"/" skip" line per task.
+        let mut imported = 0;
+        for line in out.lines() {
+            match line.split_whitespace().next() {
+                Some("add" | "mod") => imported += 1,
+                Some("skip") => skipped.push(line.trim().to_string()),
+                _ => {}
+            }
+        }
+
+        let mut summary = format!("Imported {imported} task(s), skipped {}.", skipped.len());
+        for reason in &skipped {
+            summary.push_str(&format!("\n{reason}"));
+        }
+        summary.push_str(&format!("\n\n{out}"));
+        Ok(CallToolResult::success(vec![Content::text(summary)]))
+    }
+
+    #[tool(description = "\
+        List completed tasks, optionally within a completion-date window. `project` is REQUIRED. \
+        `since`/`until_date` accept any Taskwarrior date: 'today' → finished today, \
+        'sow' → this week, 'today-7d' → last seven days. Prefer this over list_tasks with \
+        report='completed'.")]
+    async fn get_completed_tasks(
+        &self,
+        Parameters(req): Parameters<CompletedTasksRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut args = scoped_filter(&req.project, None, req.all_projects);
+        if let Some(since) = req.since {
+            validate_date("since", &since)?;
+            args.push(format!("end.after:{since}"));
+        }
+        if let Some(until) = req.until_date {
+            validate_date("until_date", &until)?;
+            args.push(format!("end.before:{until}"));
+        }
+        args.push("completed".to_string());
+        Ok(self.report(&args, "No completed tasks found.").await)
+    }
+
+    #[tool(description = "\
+        List recurring task templates (status:recurring) — the parents that spawn a new \
+        pending instance each period. Pending reports only show the spawned instances, \
+        so use this to see what recurs and how often. `project` is REQUIRED.")]
+    async fn get_recurring_tasks(
+        &self,
+        Parameters(req): Parameters<RecurringTasksRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut args = scoped_filter(&req.project, None, req.all_projects);
+        args.push("status:recurring".to_string());
+        // `list` hard-codes status:pending in its report filter; `all` applies none.
+        args.push("all".to_string());
+        Ok(self.report(&args, "No recurring tasks found.").await)
+    }
+
+    #[tool(description = "\
+        Replace an annotation's text. Every annotation containing `old_pattern` is removed \
+        and a single annotation with `new_text` is added in its place (with a fresh timestamp). \
+        Errors if no annotation matches.")]
+    async fn edit_annotation(
+        &self,
+        Parameters(req): Parameters<EditAnnotationRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let task = &self.export_one(&req.id).await?;
+        let matches: Vec<String> = task
+            .get("annotations")
+            .and_then(|a| a.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|a| a.get("description").and_then(|d| d.as_str()))
+            .filter(|d| d.contains(&req.old_pattern))
+            .map(str::to_string)
+            .collect();
+        if matches.is_empty() {
+            return Err(McpError::invalid_params(
+                format!(
+                    "No annotation on task {} matches {:?}",
+                    req.id, req.old_pattern
+                ),
+                None,
+            ));
+        }
+
+        // Denotate by full text: Taskwarrior prefers an exact match over a partial one,
+        // so each call removes exactly the annotation we found.
+        let mut out = Vec::new();
+        for text in &matches {
+            out.push(self.run(&[&req.id, "denotate", text]).await?);
+        }
+        out.push(self.run(&[&req.id, "annotate", &req.new_text]).await?);
+        Ok(CallToolResult::success(vec![Content::text(out.join("\n"))]))
+    }
+
+    #[tool(description = "\
+        Suggest Taskwarrior due-date tokens for a task description using keyword heuristics \
+        (e.g. 'by friday' → friday, 'this week' → eow, 'end of month' → eom, 'in 3 days' → \
+        today+3d). Returns suggestions ranked by confidence; pass one as `due` to add_task. \
+        Runs locally — no Taskwarrior call.")]
+    async fn suggest_due_date(
+        &self,
+        Parameters(req): Parameters<SuggestDueDateRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let suggestions = suggest_due_dates(&req.description, req.priority.as_deref());
+        let out = if suggestions.is_empty() {
+            "No date cues found; consider leaving `due` unset.".to_string()
+        } else {
+            suggestions
+                .iter()
+                .map(|(token, confidence, reason)| {
+                    format!("{token} (confidence {confidence:.2}) — {reason}")
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        GTD next actions: the top-N ready (+READY) tasks across ALL projects, most urgent first. \
+        Use for 'what should I work on next?' when the user hasn't named a project. \
+        `exclude_projects` drops whole projects (and their subprojects) before the limit applies.")]
+    async fn get_next_actions(
+        &self,
+        Parameters(req): Parameters<NextActionsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut args = vec!["+READY".to_string()];
+        // Exclude in the filter rather than post-processing, so `limit` still yields N rows.
+        for project in req.exclude_projects.unwrap_or_default() {
+            validate_project_name("exclude_projects", &project)?;
+            args.push(format!("project.not:{project}"));
+        }
+        args.push(format!("limit:{}", req.limit.unwrap_or(10)));
+        args.push("next".to_string());
+        Ok(self.report(&args, "No ready tasks.").await)
+    }
+
+    #[tool(description = "\
+        'What should I do right now in this project?' Returns the top-N most urgent pending \
+        tasks in `project` that are neither blocked nor waiting. `project` is REQUIRED.")]
+    async fn get_focus_tasks(
+        &self,
+        Parameters(req): Parameters<GetFocusRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut args = scoped_filter(&req.project, Some("-BLOCKED -WAITING +PENDING"), None);
+        args.push(format!("limit:{}", req.limit.unwrap_or(5)));
+        args.push("next".to_string());
+        Ok(self
+            .report(&args, "Nothing actionable in this project.")
+            .await)
+    }
+
+    #[tool(description = "\
+        Defer a task: hide it from reports until `wait_until` (e.g. 'monday', 'eow', \
+        '2025-07-01', 'today+3d'). The task reappears automatically on that date. \
+        Undo with clear_wait.")]
+    async fn set_task_wait(
+        &self,
+        Parameters(req): Parameters<SetWaitRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        validate_date("wait_until", &req.wait_until)?;
+        let wait = format!("wait:{}", req.wait_until);
+        Ok(CallToolResult::success(vec![Content::text(
+            self.run(&[&req.id, "modify", &wait]).await?,
+        )]))
+    }
+
+    #[tool(description = "Clear a task's wait date so it shows up in reports again immediately.")]
+    async fn clear_wait(
+        &self,
+        Parameters(req): Parameters<TaskIdRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::text(
+            self.run(&[&req.id, "modify", "wait:"]).await?,
+        )]))
+    }
+
+    #[tool(description = "\
+        Workload overview: number of pending (including waiting) tasks per project across ALL \
+        projects, busiest first. Subprojects are listed under their full dotted name. \
+        Tasks without a project are counted as '(none)'.")]
+    async fn get_task_count_by_project(&self) -> Result<CallToolResult, McpError> {
+        // One export instead of a `count` per project.
+        let tasks = self
+            .export(&filter_tokens("( status:pending or status:waiting )"))
+            .await?;
+        let rows = count_by(&tasks, |t| {
+            vec![t
+                .get("project")
+                .and_then(|p| p.as_str())
+                .unwrap_or("(none)")
+                .to_string()]
+        });
+        let total: usize = rows.iter().map(|(_, n)| n).sum();
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "{}\n\n{} project(s), {total} task(s)",
+            count_table("Project", &rows),
+            rows.len()
+        ))]))
+    }
+
+    #[tool(description = "\
+        Number of pending (including waiting) tasks per tag, most used first. \
+        Optionally limited to one `project`; untagged tasks are not counted.")]
+    async fn get_task_count_by_tag(
+        &self,
+        Parameters(req): Parameters<CountByTagRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut filter = filter_tokens("( status:pending or status:waiting )");
+        if let Some(project) = &req.project {
+            filter.push(format!("project:{project}"));
+        }
+        let tasks = self.export(&filter).await?;
+        let rows = count_by(&tasks, |t| {
+            t.get("tags")
+                .and_then(|tags| tags.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|tag| tag.as_str().map(str::to_string))
+                .collect()
+        });
+        if rows.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No tagged tasks found.",
+            )]));
+        }
+        Ok(CallToolResult::success(vec![Content::text(count_table(
+            "Tag", &rows,
+        ))]))
+    }
+
+    #[tool(description = "\
+        Count pending tasks by priority: returns 'H: N, M: N, L: N, None: N'. \
+        `project` is REQUIRED.")]
+    async fn get_task_count_by_priority(
+        &self,
+        Parameters(req): Parameters<CountByPriorityRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let with = |priority: &str| {
+            let mut filter = scoped_filter(&req.project, None, req.all_projects);
+            filter.extend(
+                FilterBuilder::new()
+                    .status("pending")
+                    .priority(priority)
+                    .into_args(),
+            );
+            filter
+        };
+        let (h, m, l, none) = (with("H"), with("M"), with("L"), with(""));
+        let (h, m, l, none) = tokio::join!(
+            self.count(&h),
+            self.count(&m),
+            self.count(&l),
+            self.count(&none)
+        );
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "H: {}, M: {}, L: {}, None: {}",
+            h?, m?, l?, none?
+        ))]))
+    }
+
+    #[tool(description = "\
+        GTD weekly review for a project in one call: tasks completed and added in the last \
+        7 days, overdue tasks, tasks due in the next 7 days, and the current pending count. \
+        `project` is REQUIRED. Follow up with list_tasks (filter='+OVERDUE') or \
+        get_completed_tasks for the underlying tasks.")]
+    async fn get_weekly_review(
+        &self,
+        Parameters(req): Parameters<WeeklyReviewRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let scoped = |filter: &str| scoped_filter(&req.project, Some(filter), None);
+        let completed = scoped("status:completed end.after:today-7d");
+        // `or` makes Taskwarrior read completed.data too, so finished tasks still count.
+        let added =
+            scoped("( status:pending or status:waiting or status:completed ) entry.after:today-7d");
+        let overdue = scoped("+OVERDUE");
+        let due_soon = scoped("status:pending due.after:now due.before:today+7d");
+        let pending = scoped("status:pending");
+
+        let (completed, added, overdue, due_soon, pending) = tokio::join!(
+            self.count(&completed),
+            self.count(&added),
+            self.count(&overdue),
+            self.count(&due_soon),
+            self.count(&pending)
+        );
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Weekly review — project:{}\n\
+             Completed (last 7 days): {}\n\
+             Added (last 7 days):     {}\n\
+             Overdue:                 {}\n\
+             Due in next 7 days:      {}\n\
+             Pending:                 {}",
+            req.project, completed?, added?, overdue?, due_soon?, pending?
+        ))]))
+    }
+
+    #[tool(description = "\
+        Agile sprint report for every task in `project` tagged `sprint_tag`: total vs. completed \
+        counts, percent done, story points (from a numeric `story_points` UDA, when present), \
+        and the list of incomplete tasks.")]
+    async fn get_sprint_summary(
+        &self,
+        Parameters(req): Parameters<SprintSummaryRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let tag = req.sprint_tag.trim_start_matches('+');
+        let filter = scoped_filter(
+            &req.project,
+            Some(&format!(
+                "+{tag} ( status:pending or status:waiting or status:completed )"
+            )),
+            None,
+        );
+        let tasks = self.export(&filter).await?;
+        if tasks.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No tasks tagged +{tag} in project {}.",
+                req.project
+            ))]));
+        }
+
+        let (done, open): (Vec<_>, Vec<_>) = tasks
+            .iter()
+            .partition(|t| t.get("status").and_then(|s| s.as_str()) == Some("completed"));
+        let mut out = format!(
+            "Sprint +{tag} — project:{}\nTasks: {} total, {} completed, {} incomplete ({:.0}% done)",
+            req.project,
+            tasks.len(),
+            done.len(),
+            open.len(),
+            done.len() as f64 * 100.0 / tasks.len() as f64
+        );
+
+        if tasks
+            .iter()
+            .any(|t| numeric_field(t, "story_points").is_some())
+        {
+            let points = |set: &[&serde_json::Value]| -> f64 {
+                set.iter()
+                    .filter_map(|t| numeric_field(t, "story_points"))
+                    .sum()
+            };
+            let all: Vec<&serde_json::Value> = tasks.iter().collect();
+            out.push_str(&format!(
+                "\nStory points: {} total, {} completed, {} remaining",
+                points(&all),
+                points(&done),
+                points(&open)
+            ));
+        }
+
+        if !open.is_empty() {
+            out.push_str("\nIncomplete:");
+            for task in &open {
+                out.push_str(&format!(
+                    "\n  - [{}] {}",
+                    field_text(task.get("id")),
+                    field_text(task.get("description"))
+                ));
+            }
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Show tasks completed in the last N hours (default 24) across all projects — \
+        the quick answer to 'what did I finish today?'. Use get_completed_tasks for \
+        project-scoped or calendar-based windows.")]
+    async fn get_recently_completed(
+        &self,
+        Parameters(req): Parameters<RecentlyCompletedRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let hours = req.hours.unwrap_or(24);
+        let args = [format!("end.after:now-{hours}h"), "completed".to_string()];
+        Ok(self
+            .report(&args, "No tasks completed in that window.")
+            .await)
+    }
+
+    #[tool(description = "\
+        Show pending tasks created in the last N hours (default 24) across all projects, \
+        oldest first — the quick answer to 'what did I capture today?'.")]
+    async fn get_recently_added(
+        &self,
+        Parameters(req): Parameters<RecentlyAddedRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let hours = req.hours.unwrap_or(24);
+        let args = [
+            "rc.report.list.sort=entry+".to_string(),
+            format!("entry.after:now-{hours}h"),
+            "list".to_string(),
+        ];
+        Ok(self.report(&args, "No tasks added in that window.").await)
+    }
+
+    #[tool(description = "\
+        Show the full change history of one task — creation, every modified attribute \
+        (old → new), annotations, completion — oldest first, read from Taskwarrior's undo log. \
+        Use to answer 'when did this become high priority?' or 'who moved the due date?'.")]
+    async fn get_task_changelog(
+        &self,
+        Parameters(req): Parameters<TaskChangelogRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let task = self.export_one(&req.id).await?;
+        let uuid = field_text(task.get("uuid"));
+
+        let path = self.data_path("undo.data");
+        let undo = match tokio::fs::read_to_string(&path).await {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                return Err(McpError::internal_error(
+                    format!("Failed to read {}: {e}", path.display()),
+                    None,
+                ))
+            }
+        };
+
+        let lines = task_changelog(&undo, &uuid);
+        let text = if lines.is_empty() {
+            format!("No history recorded for task {}.", req.id)
+        } else {
+            lines.join("\n")
+        };
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(description = "\
+        List pending tasks due inside a date window, e.g. due_after='today' \
+        due_before='eow' for 'what's due this week'. `project` is REQUIRED. Prefer this \
+        over list_tasks when the question is about a deadline window.")]
+    async fn search_tasks_by_due_range(
+        &self,
+        Parameters(req): Parameters<DueRangeSearchRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        validate_date("due_after", &req.due_after)?;
+        validate_date("due_before", &req.due_before)?;
+        let mut args = scoped_filter(&req.project, req.filter.as_deref(), req.all_projects);
+        args.extend(
+            FilterBuilder::new()
+                .due_after(&req.due_after)
+                .due_before(&req.due_before)
+                .into_args(),
+        );
+        args.push("list".to_string());
+        Ok(self.report(&args, "No tasks due in that window.").await)
+    }
+
+    #[tool(description = "\
+        Show everything a task transitively depends on as an indented tree — the blockers, \
+        their blockers, and so on (default 10 levels). Each line shows description, short \
+        UUID, and status. Use to find the real root cause of a +BLOCKED task.")]
+    async fn get_task_dependencies_recursive(
+        &self,
+        Parameters(req): Parameters<RecursiveDepsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let max_depth = usize::from(req.max_depth.unwrap_or(10));
+        let root = self.export_one(&req.id).await?;
+        let root_uuid = field_text(root.get("uuid"));
+
+        // Breadth-first fetch of the dependency graph; `tasks` doubles as the visited set.
+        let mut tasks = std::collections::HashMap::from([(root_uuid.clone(), root)]);
+        let mut queue = std::collections::VecDeque::from([(root_uuid.clone(), 0)]);
+        while let Some((uuid, depth)) = queue.pop_front() {
+            if depth >= max_depth {
+                continue;
+            }
+            for dep in depends_of(&tasks[&uuid]) {
+                if tasks.contains_key(&dep) {
+                    continue;
+                }
+                if let Some(task) = self.export(std::slice::from_ref(&dep)).await?.pop() {
+                    tasks.insert(dep.clone(), task);
+                    queue.push_back((dep, depth + 1));
+                }
+            }
+        }
+
+        fn render(
+            out: &mut String,
+            tasks: &std::collections::HashMap<String, serde_json::Value>,
+            uuid: &str,
+            depth: usize,
+            max_depth: usize,
+            path: &mut Vec<String>,
+        ) {
+            let indent = "  ".repeat(depth);
+            let Some(task) = tasks.get(uuid) else {
+                out.push_str(&format!("{indent}- (missing task {uuid})\n"));
+                return;
+            };
+            let short = uuid.get(..8).unwrap_or(uuid);
+            out.push_str(&format!(
+                "{indent}- {} [{short}, {}]",
+                field_text(task.get("description")),
+                field_text(task.get("status")),
+            ));
+            if path.iter().any(|p| p == uuid) {
+                out.push_str(" (cycle)\n");
+                return;
+            }
+            out.push('\n');
+            if depth >= max_depth {
+                return;
+            }
+            path.push(uuid.to_string());
+            for dep in depends_of(task) {
+                render(out, tasks, &dep, depth + 1, max_depth, path);
+            }
+            path.pop();
+        }
+
+        let mut out = String::new();
+        render(&mut out, &tasks, &root_uuid, 0, max_depth, &mut Vec::new());
+        if tasks.len() == 1 {
+            out.push_str("(no dependencies)");
+        }
+        Ok(CallToolResult::success(vec![Content::text(
+            out.trim_end().to_string(),
+        )]))
+    }
+
+    #[tool(description = "\
+        Remove dependencies that point at deleted or purged tasks. Such dangling \
+        `depends` entries can leave tasks stuck as +BLOCKED. Scans every task and \
+        reports how many were cleaned.")]
+    async fn cleanup_orphan_dependencies(&self) -> Result<CallToolResult, McpError> {
+        let tasks = self.export(&[]).await?;
+        let live: std::collections::HashSet<String> = tasks
+            .iter()
+            .filter(|t| field_text(t.get("status")) != "deleted")
+            .map(|t| field_text(t.get("uuid")))
+            .collect();
+
+        let mut report = Vec::new();
+        let mut removed = 0;
+        for task in tasks
+            .iter()
+            .filter(|t| field_text(t.get("status")) != "deleted")
+        {
+            let orphans: Vec<String> = depends_of(task)
+                .into_iter()
+                .filter(|dep| !live.contains(dep))
+                .collect();
+            if orphans.is_empty() {
+                continue;
+            }
+            let uuid = field_text(task.get("uuid"));
+            let mut args = vec![uuid.clone(), "modify".to_string()];
+            args.extend(orphans.iter().map(|dep| format!("depends:-{dep}")));
+            let refs: Vec<&str> = args.iter().map(String::as_str).collect();
+            self.run(&refs).await?;
+
+            removed += orphans.len();
+            report.push(format!(
+                "{}: removed {}",
+                field_text(task.get("description")),
+                orphans.join(", ")
+            ));
+        }
+
+        let mut out = format!(
+            "Cleaned {} task(s), removed {removed} orphaned dependenc{}.",
+            report.len(),
+            if removed == 1 { "y" } else { "ies" }
+        );
+        for line in &report {
+            out.push_str(&format!("\n{line}"));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Find-and-replace text in pending task descriptions, e.g. pattern='Draft:' \
+        replacement='Final:'. Literal, case-sensitive match. `project` is REQUIRED. \
+        Run with dry_run=true first to preview every old → new description.")]
+    async fn task_search_and_replace_description(
+        &self,
+        Parameters(req): Parameters<SearchReplaceRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        if req.pattern.is_empty() {
+            return Err(McpError::invalid_params("pattern must not be empty", None));
+        }
+        let mut filter = scoped_filter(&req.project, None, req.all_projects);
+        filter.push("status:pending".to_string());
+        let dry_run = req.dry_run.unwrap_or(false);
+
+        let mut changes = Vec::new();
+        for task in self.export(&filter).await? {
+            let old = field_text(task.get("description"));
+            if !old.contains(&req.pattern) {
+                continue;
+            }
+            let new = old.replace(&req.pattern, &req.replacement);
+            if !dry_run {
+                // One argument, so the new text can't be re-parsed as tags or attributes.
+                let uuid = field_text(task.get("uuid"));
+                self.run(&[&uuid, "modify", &format!("description:{new}")])
+                    .await?;
+            }
+            changes.push(format!("- {old} → {new}"));
+        }
+
+        if changes.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No pending task descriptions contain {:?}.",
+                req.pattern
+            ))]));
+        }
+        let verb = if dry_run { "Would update" } else { "Updated" };
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "{verb} {} task(s):\n{}",
+            changes.len(),
+            changes.join("\n")
+        ))]))
+    }
+
+    #[tool(description = "\
+        Rename a project by moving all of its pending, waiting, and recurring tasks to \
+        `new_project`. Matches `old_project` exactly — subprojects like Old.Sub are not moved. \
+        Completed history keeps the old name.")]
+    async fn rename_project(
+        &self,
+        Parameters(req): Parameters<RenameProjectRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        validate_project_name("old_project", &req.old_project)?;
+        validate_project_name("new_project", &req.new_project)?;
+
+        let modification = [format!("project:{}", req.new_project)];
+        let mut moved = Vec::new();
+        for status in ["pending", "waiting", "recurring"] {
+            let filter = [
+                format!("project.is:{}", req.old_project),
+                format!("status:{status}"),
+            ];
+            moved.push(self.modify_all(&filter, &modification).await?);
+        }
+
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Renamed project {} → {}: {} task(s) moved ({} pending, {} waiting, {} recurring).",
+            req.old_project,
+            req.new_project,
+            moved.iter().sum::<usize>(),
+            moved[0],
+            moved[1],
+            moved[2]
+        ))]))
+    }
+
+    #[tool(description = "\
+        Pause a project without deleting anything: every pending task in it (and its \
+        subprojects) gets a wait date, hiding it from reports. Defaults to waiting until \
+        2099-12-31. Reverse with unarchive_project.")]
+    async fn archive_project(
+        &self,
+        Parameters(req): Parameters<ArchiveProjectRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        validate_project_name("project", &req.project)?;
+        let wait_until = req.wait_until.unwrap_or_else(|| "2099-12-31".to_string());
+        validate_date("wait_until", &wait_until)?;
+
+        let filter = [
+            format!("project:{}", req.project),
+            "status:pending".to_string(),
+        ];
+        let archived = self
+            .modify_all(&filter, &[format!("wait:{wait_until}")])
+            .await?;
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Archived project {}: {archived} task(s) hidden until {wait_until}.",
+            req.project
+        ))]))
+    }
+
+    #[tool(description = "\
+        Resume an archived project: clears the wait date on every waiting task in it \
+        (and its subprojects) so they show up in reports again. Note this also wakes \
+        tasks that were snoozed individually.")]
+    async fn unarchive_project(
+        &self,
+        Parameters(req): Parameters<UnarchiveProjectRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let restored = self.release_waiting(&req.project).await?;
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Unarchived project {}: {restored} task(s) visible again.",
+            req.project
+        ))]))
+    }
+
+    #[tool(description = "\
+        Show every tag in use with how many tasks carry it, across all projects. \
+        Use for tag hygiene: spotting typos, near-duplicates, and stale tags. \
+        Follow up with get_tag_details to see the tasks behind a tag.")]
+    async fn get_tag_usage_report(&self) -> Result<CallToolResult, McpError> {
+        Ok(self.report(&["tags".to_string()], "No tags in use.").await)
+    }
+
+    #[tool(description = "\
+        List the pending tasks carrying a tag, across all projects.")]
+    async fn get_tag_details(
+        &self,
+        Parameters(req): Parameters<TagDetailsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let tag = req.tag.trim_start_matches('+');
+        if tag.is_empty() || tag.contains(char::is_whitespace) {
+            return Err(McpError::invalid_params(
+                format!("Invalid tag {:?}: expected a single word", req.tag),
+                None,
+            ));
+        }
+        let mut args = FilterBuilder::new().tag(tag).into_args();
+        args.push("list".to_string());
+        Ok(self
+            .report(&args, &format!("No pending tasks tagged +{tag}."))
+            .await)
+    }
+
+    #[tool(description = "\
+        Lowercase every mixed-case tag in a project (+Urgent → +urgent) so tag filters \
+        match consistently. Covers pending and waiting tasks and reports each change.")]
+    async fn normalize_tags(
+        &self,
+        Parameters(req): Parameters<NormalizeTagsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let filter = scoped_filter(
+            &req.project,
+            Some("( status:pending or status:waiting )"),
+            None,
+        );
+        let mut changes = Vec::new();
+        let mut renamed = 0;
+        for task in self.export(&filter).await? {
+            let tags: Vec<String> = task
+                .get("tags")
+                .and_then(|t| t.as_array())
+                .into_iter()
+                .flatten()
+                .filter_map(|t| t.as_str())
+                .filter(|t| t.chars().any(char::is_uppercase))
+                .map(str::to_string)
+                .collect();
+            if tags.is_empty() {
+                continue;
+            }
+            let uuid = field_text(task.get("uuid"));
+            let mut args = vec![uuid, "modify".to_string()];
+            for tag in &tags {
+                args.push(format!("-{tag}"));
+                args.push(format!("+{}", tag.to_lowercase()));
+            }
+            let refs: Vec<&str> = args.iter().map(String::as_str).collect();
+            self.run(&refs).await?;
+
+            renamed += tags.len();
+            let pairs: Vec<String> = tags
+                .iter()
+                .map(|t| format!("{t} → {}", t.to_lowercase()))
+                .collect();
+            changes.push(format!(
+                "- {}: {}",
+                field_text(task.get("description")),
+                pairs.join(", ")
+            ));
+        }
+
+        if changes.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "All tags are already lowercase.",
+            )]));
+        }
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Normalized {renamed} tag(s) on {} task(s):\n{}",
+            changes.len(),
+            changes.join("\n")
+        ))]))
+    }
+
+    #[tool(description = "\
+        Save a named task template — a preset of add_task fields (project, tags, priority, \
+        due, …) for recurring kinds of work such as 'bug' or 'weekly-report'. Templates \
+        persist across sessions. Instantiate with add_task_from_template.")]
+    async fn register_template(
+        &self,
+        Parameters(req): Parameters<RegisterTemplateRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let name = req.name.trim().to_string();
+        if name.is_empty() {
+            return Err(McpError::invalid_params("name must not be empty", None));
+        }
+        validate_project_name("template.project", &req.template.project)?;
+        let replaced = self
+            .templates
+            .lock()
+            .expect("template lock poisoned")
+            .insert(name.clone(), req.template)
+            .is_some();
+        self.save_templates().await?;
+        let verb = if replaced { "Updated" } else { "Registered" };
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "{verb} template {name:?}."
+        ))]))
+    }
+
+    #[tool(description = "\
+        Add a task from a registered template. The template supplies every field; \
+        `description_override` and `due_override` replace the template's values.")]
+    async fn add_task_from_template(
+        &self,
+        Parameters(req): Parameters<UseTemplateRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let template = self
+            .templates
+            .lock()
+            .expect("template lock poisoned")
+            .get(&req.template_name)
+            .cloned();
+        let Some(mut task) = template else {
+            return Err(McpError::invalid_params(
+                format!("No template named {:?}", req.template_name),
+                None,
+            ));
+        };
+        if let Some(description) = req.description_override {
+            task.description = description;
+        }
+        if let Some(due) = req.due_override {
+            task.due = Some(due);
+        }
+        self.add_task(Parameters(task)).await
+    }
+
+    #[tool(description = "\
+        List registered task templates: name, description, and project of each.")]
+    async fn list_templates(&self) -> Result<CallToolResult, McpError> {
+        let templates = self.templates.lock().expect("template lock poisoned");
+        if templates.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No templates registered.",
+            )]));
+        }
+        let mut names: Vec<&String> = templates.keys().collect();
+        names.sort();
+        let lines: Vec<String> = names
+            .into_iter()
+            .map(|name| {
+                let t = &templates[name];
+                format!("- {name}: {} (project:{})", t.description, t.project)
+            })
+            .collect();
+        Ok(CallToolResult::success(vec![Content::text(
+            lines.join("\n"),
+        )]))
+    }
+
+    #[tool(description = "Delete a registered task template by name.")]
+    async fn delete_template(
+        &self,
+        Parameters(req): Parameters<DeleteTemplateRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let removed = self
+            .templates
+            .lock()
+            .expect("template lock poisoned")
+            .remove(&req.name)
+            .is_some();
+        if !removed {
+            return Err(McpError::invalid_params(
+                format!("No template named {:?}", req.name),
+                None,
+            ));
+        }
+        self.save_templates().await?;
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Deleted template {:?}.",
+            req.name
+        ))]))
+    }
+
+    #[tool(description = "\
+        List pending tasks for very large projects, returned as several content chunks \
+        (JSON arrays of `chunk_size` tasks, default 25, most urgent first) instead of one \
+        huge report, so the first tasks can be read before the rest. `project` is REQUIRED. \
+        For normal-sized projects use list_tasks.")]
+    async fn stream_list_tasks(
+        &self,
+        Parameters(req): Parameters<StreamListRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut args = scoped_filter(&req.project, req.filter.as_deref(), req.all_projects);
+        if !args.iter().any(|a| a.starts_with("status:")) {
+            args.push("status:pending".to_string());
+        }
+        let mut tasks = self.export(&args).await?;
+        if tasks.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No tasks found.",
+            )]));
+        }
+        sort_by_urgency(&mut tasks);
+
+        let chunk_size = req.chunk_size.unwrap_or(25).max(1) as usize;
+        let chunks = tasks
+            .chunks(chunk_size)
+            .map(|chunk| Content::text(serde_json::Value::from(chunk.to_vec()).to_string()))
+            .collect();
+        Ok(CallToolResult::success(chunks))
+    }
+
+    #[tool(description = "\
+        Autocomplete a project name: returns up to 10 existing projects starting with \
+        `prefix` (case-insensitive), alphabetically. Call before add_task to reuse an \
+        existing project instead of inventing a near-duplicate.")]
+    async fn suggest_projects(
+        &self,
+        Parameters(req): Parameters<SuggestProjectsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let out = self.run(&["_projects"]).await?;
+        let prefix = req.prefix.to_lowercase();
+        let mut projects: Vec<&str> = out
+            .lines()
+            .map(str::trim)
+            .filter(|p| !p.is_empty() && p.to_lowercase().starts_with(&prefix))
+            .collect();
+        projects.sort_by_key(|p| p.to_lowercase());
+        projects.truncate(10);
+        let text = if projects.is_empty() {
+            format!("No projects start with {:?}.", req.prefix)
+        } else {
+            projects.join("\n")
+        };
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(description = "\
+        Autocomplete a tag: returns up to 10 existing tags starting with `prefix` \
+        (case-insensitive), most-used first, each with its task count. Call before \
+        tagging to reuse established tags instead of creating variants.")]
+    async fn suggest_tags(
+        &self,
+        Parameters(req): Parameters<SuggestTagsRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let (tags, usage) = tokio::join!(self.run(&["_tags"]), self.run(&["tags"]));
+        let tags = tags?;
+        // `task tags` renders a "Tag  Count" table; rows are the ones ending in a number.
+        // Fails when no task has tags, which just means every count is zero.
+        let counts: HashMap<&str, usize> = usage
+            .as_deref()
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| {
+                let mut cols = line.split_whitespace();
+                Some((cols.next()?, cols.next()?.parse().ok()?))
+            })
+            .collect();
+
+        let prefix = req.prefix.trim_start_matches('+').to_lowercase();
+        let mut matches: Vec<(&str, usize)> = tags
+            .lines()
+            .map(str::trim)
+            .filter(|t| !t.is_empty() && t.to_lowercase().starts_with(&prefix))
+            .map(|t| (t, counts.get(t).copied().unwrap_or(0)))
+            .collect();
+        matches.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        matches.truncate(10);
+
+        let text = if matches.is_empty() {
+            format!("No tags start with {:?}.", req.prefix)
+        } else {
+            matches
+                .iter()
+                .map(|(tag, n)| format!("{tag} ({n})"))
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        Ok(CallToolResult::success(vec![Content::text(text)]))
+    }
+
+    #[tool(description = "\
+        Sum the `effort` UDA (estimated hours) of open tasks per project: task count, \
+        total, average, and max effort. Tasks without an effort value count as 0. \
+        `project` is REQUIRED; set all_projects=true for a per-project breakdown.")]
+    async fn get_task_effort_report(
+        &self,
+        Parameters(req): Parameters<EffortReportRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let filter = scoped_filter(
+            &req.project,
+            Some("( status:pending or status:waiting )"),
+            req.all_projects,
+        );
+        let tasks = self.export(&filter).await?;
+        if tasks.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No open tasks found.",
+            )]));
+        }
+
+        let mut by_project: std::collections::BTreeMap<String, Vec<f64>> = Default::default();
+        for task in &tasks {
+            let project = task
+                .get("project")
+                .and_then(|p| p.as_str())
+                .unwrap_or("(none)")
+                .to_string();
+            let effort = numeric_field(task, "effort").unwrap_or(0.0);
+            by_project.entry(project).or_default().push(effort);
+        }
+
+        let width = by_project
+            .keys()
+            .map(|p| p.chars().count())
+            .chain(["Project".len()])
+            .max()
+            .unwrap_or(0);
+        let mut out = format!(
+            "{:<width$} | Tasks | Total | Avg  | Max\n{}-|-------|-------|------|-----",
+            "Project",
+            "-".repeat(width)
+        );
+        for (project, efforts) in &by_project {
+            let total: f64 = efforts.iter().sum();
+            let max = efforts.iter().copied().fold(0.0, f64::max);
+            out.push_str(&format!(
+                "\n{project:<width$} | {:<5} | {total:<5.1} | {:<4.1} | {max:.1}",
+                efforts.len(),
+                total / efforts.len() as f64,
+            ));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Check that the server can do its job: the `task` binary runs, the data directory \
+        is readable, and a trivial query succeeds. Returns JSON with status 'ok' or \
+        'degraded', the Taskwarrior version, the data directory, and any errors.")]
+    async fn health_check(&self) -> Result<CallToolResult, McpError> {
+        let mut errors = Vec::new();
+
+        let version = match self.run(&["_version"]).await {
+            Ok(v) => Some(v),
+            Err(e) => {
+                errors.push(format!("taskwarrior: {}", e.message));
+                None
+            }
+        };
+
+        let data_dir = self.data_path("");
+        if let Err(e) = tokio::fs::read_dir(&data_dir).await {
+            errors.push(format!("data_dir {}: {e}", data_dir.display()));
+        }
+
+        if version.is_some() {
+            if let Err(e) = self.count(&[]).await {
+                errors.push(format!("task count: {}", e.message));
+            }
+        }
+
+        let report = serde_json::json!({
+            "status": if errors.is_empty() { "ok" } else { "degraded" },
+            "taskwarrior_version": version,
+            "data_dir": data_dir,
+            "errors": errors,
+        });
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&report).unwrap_or_default(),
+        )]))
+    }
+
+    #[tool(description = "\
+        Show how this server is configured: name and version, Taskwarrior data directory, \
+        `task` binary, command timeout, and enabled optional features.")]
+    async fn get_server_info(&self) -> Result<CallToolResult, McpError> {
+        // Show the home directory as `~` so usernames don't leak into transcripts.
+        let mask = |path: &std::path::Path| {
+            let home = std::env::var("HOME").ok().filter(|h| !h.is_empty());
+            match home.and_then(|h| path.strip_prefix(h).ok().map(|rest| rest.to_path_buf())) {
+                Some(rest) => format!("~/{}", rest.display()),
+                None => path.display().to_string(),
+            }
+        };
+        let data_dir = match &self.data_dir {
+            Some(dir) => mask(dir),
+            None => "(taskrc data.location)".to_string(),
+        };
+        let task_bin = if self.task_bin.components().count() > 1 {
+            self.task_bin.clone()
+        } else {
+            std::env::var_os("PATH")
+                .into_iter()
+                .flat_map(|paths| std::env::split_paths(&paths).collect::<Vec<_>>())
+                .map(|dir| dir.join(&self.task_bin))
+                .find(|candidate| candidate.is_file())
+                .unwrap_or_else(|| self.task_bin.clone())
+        };
+        let features: Vec<&str> = [("timew-integration", cfg!(feature = "timew-integration"))]
+            .into_iter()
+            .filter_map(|(name, enabled)| enabled.then_some(name))
+            .collect();
+
+        let info = format!(
+            "Server: {} {}\nData directory: {data_dir}\nTaskwarrior binary: {}\n\
+             Timeout: {}s\nFeatures: {}",
+            env!("CARGO_PKG_NAME"),
+            env!("CARGO_PKG_VERSION"),
+            mask(&task_bin),
+            self.timeout.as_secs(),
+            if features.is_empty() {
+                "(none)".to_string()
+            } else {
+                features.join(", ")
+            }
+        );
+        Ok(CallToolResult::success(vec![Content::text(info)]))
+    }
+
+    #[tool(description = "\
+        Show per-tool usage since the server started: call count, error count, average \
+        latency, and when each tool was last called.")]
+    async fn get_metrics(&self) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::text(
+            self.metrics.table().await,
+        )]))
+    }
+
+    #[tool(description = "\
+        Break a task into subtasks. Each subtask inherits the original's project, priority, \
+        and tags. Set add_dependency=true to make each subtask depend on the original, and \
+        complete_original=true to mark the original done. Returns the new subtask IDs.")]
+    async fn task_split(
+        &self,
+        Parameters(req): Parameters<SplitTaskRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        if req.subtask_descriptions.iter().all(|d| d.trim().is_empty()) {
+            return Err(McpError::invalid_params(
+                "subtask_descriptions must contain at least one description",
+                None,
+            ));
+        }
+        let original = self.export_one(&req.id).await?;
+        let original_uuid = field_text(original.get("uuid"));
+        let tags: Vec<String> = original
+            .get("tags")
+            .and_then(|t| t.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|t| t.as_str().map(str::to_string))
+            .collect();
+
+        let mut created = Vec::new();
+        for description in req
+            .subtask_descriptions
+            .iter()
+            .filter(|d| !d.trim().is_empty())
+        {
+            let uuid = self
+                .add_for_uuid(AddTaskRequest {
+                    description: description.clone(),
+                    project: field_text(original.get("project")),
+                    due: None,
+                    tags: (!tags.is_empty()).then(|| tags.clone()),
+                    priority: original
+                        .get("priority")
+                        .and_then(|p| p.as_str())
+                        .map(str::to_string),
+                    wait: None,
+                    scheduled: None,
+                })
+                .await?;
+            created.push((description.clone(), uuid));
+        }
+
+        let add_dependency = req.add_dependency.unwrap_or(false);
+        if add_dependency {
+            let subtasks: Vec<String> = created.iter().map(|(_, uuid)| uuid.clone()).collect();
+            self.modify_all(&subtasks, &[format!("depends:{original_uuid}")])
+                .await?;
+        }
+        let complete = req.complete_original.unwrap_or(false);
+        if complete {
+            self.run(&[&original_uuid, "done"]).await?;
+        }
+
+        let mut out = format!(
+            "Split {} into {} subtask(s):",
+            field_text(original.get("description")),
+            created.len()
+        );
+        for (description, uuid) in &created {
+            out.push_str(&format!("\n- {uuid} {description}"));
+        }
+        if add_dependency {
+            out.push_str("\nEach subtask depends on the original task.");
+        }
+        if complete {
+            out.push_str("\nOriginal task marked done.");
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Merge two duplicate tasks: copies every annotation from `secondary_id` onto \
+        `primary_id`, optionally replaces the primary's description with \
+        `combined_description`, then deletes the secondary. Returns the merged task's details.")]
+    async fn merge_tasks(
+        &self,
+        Parameters(req): Parameters<MergeTasksRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let ids = [req.primary_id.clone(), req.secondary_id.clone()];
+        let mut tasks = Vec::new();
+        for id in &ids {
+            let task = self.export_one(id).await?;
+            tasks.push(task);
+        }
+        let primary = field_text(tasks[0].get("uuid"));
+        let secondary = field_text(tasks[1].get("uuid"));
+        if primary == secondary {
+            return Err(McpError::invalid_params(
+                "primary_id and secondary_id refer to the same task",
+                None,
+            ));
+        }
+
+        let notes: Vec<&str> = tasks[1]
+            .get("annotations")
+            .and_then(|a| a.as_array())
+            .into_iter()
+            .flatten()
+            .filter_map(|a| a.get("description").and_then(|d| d.as_str()))
+            .collect();
+        for note in notes {
+            self.run(&[&primary, "annotate", note]).await?;
+        }
+        if let Some(description) = req.combined_description.filter(|d| !d.trim().is_empty()) {
+            self.run(&[&primary, "modify", &format!("description:{description}")])
+                .await?;
+        }
+        self.run(&[&secondary, "delete"]).await?;
+
+        self.get_task(Parameters(TaskIdRequest { id: primary }))
+            .await
+    }
+
+    #[tool(description = "\
+        Create a sequential chain of tasks for a multi-step process: each step depends on \
+        the previous one, so only the first step is actionable (+READY) until it's done. \
+        `project` is REQUIRED; `priority` and `tags` apply to every step. \
+        Returns the created UUIDs in order.")]
+    async fn create_task_chain(
+        &self,
+        Parameters(req): Parameters<TaskChainRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let steps: Vec<&String> = req
+            .descriptions
+            .iter()
+            .filter(|d| !d.trim().is_empty())
+            .collect();
+        if steps.is_empty() {
+            return Err(McpError::invalid_params(
+                "descriptions must contain at least one step",
+                None,
+            ));
+        }
+
+        let mut created: Vec<String> = Vec::new();
+        for description in &steps {
+            let uuid = self
+                .add_for_uuid(AddTaskRequest {
+                    description: description.to_string(),
+                    project: req.project.clone(),
+                    due: None,
+                    tags: req.tags.clone(),
+                    priority: req.priority.clone(),
+                    wait: None,
+                    scheduled: None,
+                })
+                .await?;
+            if let Some(previous) = created.last() {
+                self.run(&[&uuid, "modify", &format!("depends:{previous}")])
+                    .await?;
+            }
+            created.push(uuid);
+        }
+
+        let mut out = format!("Created a {}-step chain in {}:", created.len(), req.project);
+        for (n, (uuid, description)) in created.iter().zip(&steps).enumerate() {
+            out.push_str(&format!("\n{}. {uuid} {description}", n + 1));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        GTD inbox processing: list every pending task in the inbox project so each one \
+        can be clarified and filed. For each task decide: do it now if it takes under two \
+        minutes (complete_task), move it to a real project (modify_task \
+        modifications='project:<name>', see suggest_projects), defer it (set_task_wait or a \
+        due date), or drop it (delete_task). Repeat until the inbox is empty.")]
+    async fn task_inbox_process(
+        &self,
+        Parameters(req): Parameters<InboxProcessRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        validate_project_name("project_for_inbox", &req.project_for_inbox)?;
+        let filter = scoped_filter(&req.project_for_inbox, Some("status:pending"), None);
+        if self.count(&filter).await? == 0 {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "Inbox {} is empty — nothing to process.",
+                req.project_for_inbox
+            ))]));
+        }
+        let listing = self
+            .list_tasks(Parameters(ListTasksRequest {
+                project: req.project_for_inbox.clone(),
+                report: Some("list".to_string()),
+                ..Default::default()
+            }))
+            .await?;
+        let listing = text_content(&listing);
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "{listing}\n\nAssign each task above to a real project with modify_task \
+             (modifications=\"project:<name>\"), complete anything that takes under two \
+             minutes, and delete what is no longer relevant. Nothing should remain in {}.",
+            req.project_for_inbox
+        ))]))
+    }
+
+    #[tool(description = "\
+        Quick capture: add a task without deciding where it belongs yet. Files into the \
+        \"Inbox\" project unless `project` is given; sort it later with task_inbox_process.")]
+    async fn task_capture(
+        &self,
+        Parameters(req): Parameters<CaptureTaskRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        self.add_task(Parameters(AddTaskRequest {
+            description: req.description,
+            project: req.project.unwrap_or_else(|| INBOX_PROJECT.to_string()),
+            due: req.due,
+            tags: req.tags,
+            priority: None,
+            wait: None,
+            scheduled: None,
+        }))
+        .await
+    }
+
+    #[tool(description = "\
+        List the GTD someday/maybe list: ideas parked in the \"Someday\" project that aren't \
+        commitments yet. Review it during the weekly review and promote anything that has \
+        become actionable with modify_task (project:<real project>, due:...).")]
+    async fn get_someday_maybe_list(
+        &self,
+        Parameters(req): Parameters<SomedayMaybeRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut args = scoped_filter(SOMEDAY_PROJECT, req.filter.as_deref(), None);
+        args.push("list".to_string());
+        Ok(self.report(&args, "The someday/maybe list is empty.").await)
+    }
+
+    #[tool(description = "\
+        Park an idea on the someday/maybe list: creates it in the \"Someday\" project with \
+        low priority and no due date, so it stays out of next/ready reports until promoted.")]
+    async fn add_someday_task(
+        &self,
+        Parameters(req): Parameters<AddSomedayTaskRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        self.add_task(Parameters(AddTaskRequest {
+            description: req.description,
+            project: SOMEDAY_PROJECT.to_string(),
+            due: None,
+            tags: req.tags,
+            priority: Some("L".to_string()),
+            wait: None,
+            scheduled: None,
+        }))
+        .await
+    }
+
+    #[tool(description = "\
+        Save a named filter for reuse, e.g. name='urgent-backend' filter='+READY priority:H' \
+        project='Work.Backend'. Presets persist across sessions; run one with \
+        run_filter_preset. Omitting `project` makes the preset cross-project.")]
+    async fn save_filter_preset(
+        &self,
+        Parameters(req): Parameters<SavePresetRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let name = req.name.trim().to_string();
+        if name.is_empty() {
+            return Err(McpError::invalid_params("name must not be empty", None));
+        }
+        if let Some(project) = &req.project {
+            validate_project_name("project", project)?;
+        }
+        let replaced = self
+            .filter_presets
+            .insert(
+                name.clone(),
+                FilterPreset {
+                    filter: req.filter,
+                    project: req.project,
+                },
+            )
+            .await?;
+        let verb = if replaced { "Updated" } else { "Saved" };
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "{verb} filter preset {name:?}."
+        ))]))
+    }
+
+    #[tool(description = "List saved filter presets with their filter strings and projects.")]
+    async fn list_filter_presets(&self) -> Result<CallToolResult, McpError> {
+        let presets = self.filter_presets.list();
+        if presets.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No filter presets saved.",
+            )]));
+        }
+        let lines: Vec<String> = presets
+            .iter()
+            .map(|(name, preset)| match &preset.project {
+                Some(project) => format!("- {name}: {} (project:{project})", preset.filter),
+                None => format!("- {name}: {} (all projects)", preset.filter),
+            })
+            .collect();
+        Ok(CallToolResult::success(vec![Content::text(
+            lines.join("\n"),
+        )]))
+    }
+
+    #[tool(description = "Delete a saved filter preset by name.")]
+    async fn delete_filter_preset(
+        &self,
+        Parameters(req): Parameters<RunPresetRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        if !self.filter_presets.remove(&req.name).await? {
+            return Err(McpError::invalid_params(
+                format!("No filter preset named {:?}", req.name),
+                None,
+            ));
+        }
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Deleted filter preset {:?}.",
+            req.name
+        ))]))
+    }
+
+    #[tool(description = "\
+        Run a saved filter preset through list_tasks and return the matching tasks.")]
+    async fn run_filter_preset(
+        &self,
+        Parameters(req): Parameters<RunPresetRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let Some(preset) = self.filter_presets.get(&req.name) else {
+            return Err(McpError::invalid_params(
+                format!("No filter preset named {:?}", req.name),
+                None,
+            ));
+        };
+        self.list_tasks(Parameters(ListTasksRequest {
+            all_projects: Some(preset.project.is_none()),
+            project: preset.project.unwrap_or_default(),
+            filter: Some(preset.filter),
+            ..Default::default()
+        }))
+        .await
+    }
+
+    #[tool(description = "\
+        Add a task from Taskwarrior's shorthand in one string, e.g. \
+        'Fix the login bug project:Work priority:H +bug due:friday'. Words become the \
+        description; +tag, project:, due:, priority:, wait:, scheduled:, depends: are applied \
+        as modifiers (repeat depends: for several blockers). A project:<name> token is \
+        REQUIRED. Returns the numeric ID and UUID.")]
+    async fn task_quick_add(
+        &self,
+        Parameters(req): Parameters<QuickAddRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut args = vec!["add".to_string()];
+        args.extend(quick_add_args(&req.text)?);
+        Ok(CallToolResult::success(vec![Content::text(
+            self.run_add(&args).await?,
+        )]))
+    }
+
+    #[tool(description = "\
+        Typo-tolerant search over pending task descriptions, for when search_tasks' exact \
+        regex finds nothing ('implment feture' finds 'implement feature'). Returns matches \
+        scoring at least `threshold` (0.0–1.0, default 0.6), best first. `project` is REQUIRED.")]
+    async fn fuzzy_search_tasks(
+        &self,
+        Parameters(req): Parameters<FuzzySearchRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let threshold = req.threshold.unwrap_or(0.6);
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(McpError::invalid_params(
+                format!("threshold must be between 0.0 and 1.0, got {threshold}"),
+                None,
+            ));
+        }
+        if req.query.trim().is_empty() {
+            return Err(McpError::invalid_params("query must not be empty", None));
+        }
+        let mut filter = scoped_filter(&req.project, None, req.all_projects);
+        filter.push("status:pending".to_string());
+        let mut matches: Vec<(f32, serde_json::Value)> = self
+            .export(&filter)
+            .await?
+            .into_iter()
+            .map(|task| {
+                let description = field_text(task.get("description"));
+                (fuzzy_score(&req.query, &description), task)
+            })
+            .filter(|(score, _)| *score >= threshold)
+            .collect();
+        if matches.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No tasks resemble {:?}.",
+                req.query
+            ))]));
+        }
+        matches.sort_by(|a, b| b.0.total_cmp(&a.0));
+        let lines: Vec<String> = matches
+            .iter()
+            .map(|(score, task)| {
+                format!(
+                    "{score:.2}  {}  {} (project:{})",
+                    field_text(task.get("uuid")),
+                    field_text(task.get("description")),
+                    field_text(task.get("project"))
+                )
+            })
+            .collect();
+        Ok(CallToolResult::success(vec![Content::text(
+            lines.join("\n"),
+        )]))
+    }
+
+    #[tool(description = "\
+        List pending tasks due today (+TODAY) — the daily planning query. Scoped to \
+        `project` when given, otherwise across all projects.")]
+    async fn get_tasks_due_today(
+        &self,
+        Parameters(req): Parameters<DueTodayRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut filter = FilterBuilder::new();
+        if let Some(project) = &req.project {
+            filter = filter.project(project);
+        }
+        let mut args = filter.tag("TODAY").into_args();
+        args.push("list".to_string());
+        Ok(self.report(&args, "Nothing due today.").await)
+    }
+
+    #[tool(description = "\
+        List pending tasks due from today through the end of the week (due.after:yesterday \
+        due.before:eow). Scoped to `project` when given, otherwise across all projects.")]
+    async fn get_tasks_due_this_week(
+        &self,
+        Parameters(req): Parameters<DueThisWeekRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut filter = FilterBuilder::new();
+        if let Some(project) = &req.project {
+            filter = filter.project(project);
+        }
+        let mut args = filter.due_before("eow").due_after("yesterday").into_args();
+        args.push("list".to_string());
+        Ok(self.report(&args, "Nothing due this week.").await)
+    }
+
+    #[tool(description = "\
+        Rank pending tasks by urgency score, most urgent first, as an 'Urgency | Task' \
+        table (default top 10). Use to explain why something is at the top of the \
+        `next` report. `project` is REQUIRED.")]
+    async fn urgency_report(
+        &self,
+        Parameters(req): Parameters<UrgencyReportRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let limit = req.limit.unwrap_or(10).max(1) as usize;
+        let mut filter = scoped_filter(&req.project, None, req.all_projects);
+        filter.push("status:pending".to_string());
+        let mut tasks = self.export(&filter).await?;
+        if tasks.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No pending tasks.",
+            )]));
+        }
+        let urgency =
+            |t: &serde_json::Value| t.get("urgency").and_then(|u| u.as_f64()).unwrap_or(0.0);
+        tasks.sort_by(|a, b| urgency(b).total_cmp(&urgency(a)));
+
+        let mut out = "Urgency | Task\n--------|-----".to_string();
+        for task in tasks.iter().take(limit) {
+            out.push_str(&format!(
+                "\n{:>7.2} | {}",
+                urgency(task),
+                field_text(task.get("description"))
+            ));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        List pending tasks in a project that haven't been touched in `stale_days` days \
+        (default 14). Go through them one by one and decide: update (modify_task), \
+        finish (complete_task), snooze (set_task_wait), or drop (delete_task). \
+        `project` is REQUIRED.")]
+    async fn task_review(
+        &self,
+        Parameters(req): Parameters<TaskReviewRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let days = req.stale_days.unwrap_or(14);
+        let mut args = scoped_filter(&req.project, None, None);
+        args.push(format!("modified.before:now-{days}d"));
+        args.push("list".to_string());
+        Ok(self
+            .report(
+                &args,
+                &format!("No tasks in {} untouched for {days} days.", req.project),
+            )
+            .await)
+    }
+
+    #[tool(description = "\
+        Describe a task in readable form: description, project, every set attribute \
+        (dates in local time), and annotations as a dated timeline. Set \
+        `include_similar=true` to also list look-alike pending tasks in the same project — \
+        handy for spotting duplicates before merge_tasks.")]
+    async fn task_describe(
+        &self,
+        Parameters(req): Parameters<DescribeTaskRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let task = self.export_one(&req.id).await?;
+        let mut out = describe_task(&task);
+
+        let project = field_text(task.get("project"));
+        if req.include_similar.unwrap_or(false) && !project.is_empty() {
+            let uuid = field_text(task.get("uuid"));
+            let description = field_text(task.get("description"));
+            let mut filter = scoped_filter(&project, None, None);
+            filter.push("status:pending".to_string());
+            let mut similar: Vec<(f32, String)> = self
+                .export(&filter)
+                .await?
+                .iter()
+                .filter(|other| field_text(other.get("uuid")) != uuid)
+                .map(|other| {
+                    let text = field_text(other.get("description"));
+                    (fuzzy_score(&description, &text), text)
+                })
+                .filter(|(score, _)| *score >= 0.5)
+                .collect();
+            similar.sort_by(|a, b| b.0.total_cmp(&a.0));
+            out.push_str(&format!("\nSimilar tasks in {project}:"));
+            if similar.is_empty() {
+                out.push_str(" none");
+            }
+            for (score, text) in similar.iter().take(5) {
+                out.push_str(&format!("\n  - {text} ({score:.2})"));
+            }
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Check the task database for consistency problems: dependencies on missing or \
+        deleted tasks, recurring tasks without a due date, invalid priorities, and waiting \
+        tasks whose wait date already passed. Appends Taskwarrior's own `diagnostics` \
+        checks. Read-only — use cleanup_orphan_dependencies to fix broken dependencies.")]
+    async fn validate_task_data(&self) -> Result<CallToolResult, McpError> {
+        let pending = ["status:pending".to_string()];
+        let (tasks, pending, diagnostics) = tokio::join!(
+            self.export(&[]),
+            self.count(&pending),
+            self.run(&["diagnostics"])
+        );
+        let tasks = tasks?;
+        let issues = data_issues(&tasks, Utc::now());
+        let total: usize = issues.iter().map(|(_, found)| found.len()).sum();
+
+        let mut out = format!(
+            "Checked {} task(s) ({} pending): {}.",
+            tasks.len(),
+            pending?,
+            match total {
+                0 => "no issues found".to_string(),
+                1 => "1 issue found".to_string(),
+                n => format!("{n} issues found"),
+            }
+        );
+        for (check, found) in &issues {
+            out.push_str(&format!("\n\n{check}: {}", found.len()));
+            for line in found {
+                out.push_str(&format!("\n  - {line}"));
+            }
+        }
+        // Only the self-test section of `task diagnostics` is about the data; the rest
+        // describes the platform and build.
+        if let Some((_, checks)) = diagnostics
+            .ok()
+            .as_deref()
+            .and_then(|d| d.split_once("\nTests"))
+        {
+            out.push_str(&format!("\n\nTaskwarrior diagnostics:{checks}"));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Back up the Taskwarrior data directory (task data plus this server's templates and \
+        presets) to a .tar.gz archive. `output_path` is either the archive file or an \
+        existing directory, in which a timestamped archive is created. Returns the archive \
+        path and size. Restore with task_restore.")]
+    async fn task_backup(
+        &self,
+        Parameters(req): Parameters<BackupRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let (output, files, bytes) = self.write_backup(PathBuf::from(req.output_path)).await?;
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Backed up {files} file(s) to {} ({bytes} bytes).",
+            output.display()
+        ))]))
+    }
+
+    #[tool(description = "\
+        Restore the Taskwarrior data directory from a task_backup archive, overwriting the \
+        current task data. DESTRUCTIVE: requires `confirm=true` — ask the user first. The \
+        current data is saved to a pre-restore archive in the temp directory before \
+        anything is overwritten; its path is returned along with the restored file count.")]
+    async fn task_restore(
+        &self,
+        Parameters(req): Parameters<RestoreRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        if !req.confirm {
+            return Err(McpError::invalid_params(
+                "Restoring overwrites the current task data; pass confirm=true to proceed",
+                None,
+            ));
+        }
+        let backup_path = PathBuf::from(&req.backup_path);
+        let archive = tokio::fs::read(&backup_path)
+            .await
+            .map_err(|e| io_error("read", &backup_path, e))?;
+        let files = untar_gz(&archive).map_err(|e| {
+            McpError::invalid_params(
+                format!(
+                    "{} is not a task_backup archive: {e}",
+                    backup_path.display()
+                ),
+                None,
+            )
+        })?;
+        if files.is_empty() {
+            return Err(McpError::invalid_params(
+                format!("{} contains no files", backup_path.display()),
+                None,
+            ));
+        }
+
+        let safety = std::env::temp_dir().join(format!(
+            "taskwarrior-pre-restore-{}.tar.gz",
+            Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let (safety, _, _) = self.write_backup(safety).await?;
+
+        // Files the archive doesn't have (newer data files, SQLite -wal/-shm journals) would
+        // otherwise override the restored state; the safety archive still holds them.
+        let data_dir = self.data_path("");
+        let archive_path = backup_path.canonicalize().ok();
+        let mut entries = tokio::fs::read_dir(&data_dir)
+            .await
+            .map_err(|e| io_error("read", &data_dir, e))?;
+        while let Some(entry) = entries
+            .next_entry()
+            .await
+            .map_err(|e| io_error("read", &data_dir, e))?
+        {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if !path.is_file()
+                || files.iter().any(|(file, _)| *file == name)
+                || path.canonicalize().ok() == archive_path
+            {
+                continue;
+            }
+            tokio::fs::remove_file(&path)
+                .await
+                .map_err(|e| io_error("remove", &path, e))?;
+        }
+        for (name, contents) in &files {
+            let path = data_dir.join(name);
+            tokio::fs::write(&path, contents)
+                .await
+                .map_err(|e| io_error("write", &path, e))?;
+        }
+        *self.templates.lock().expect("template lock poisoned") =
+            load_json_map(&self.data_path(TEMPLATES_FILE));
+        self.filter_presets.reload();
+
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Restored {} file(s) from {}. Previous data saved to {}.",
+            files.len(),
+            backup_path.display(),
+            safety.display()
+        ))]))
+    }
+
+    #[tool(description = "\
+        Explain in plain English what a Taskwarrior filter matches, e.g. \
+        'project:Work +urgent priority:H -WAITING' → tasks in project 'Work' that are \
+        tagged 'urgent', have priority H, and are not waiting. Use to double-check a \
+        filter before running bulk changes. Runs locally — no Taskwarrior call.")]
+    async fn describe_filters(
+        &self,
+        Parameters(req): Parameters<DescribeFilterRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::text(
+            describe_filter(&req.filter),
+        )]))
+    }
+
+    #[tool(description = "\
+        Prepend text to a task's description (Taskwarrior adds the separating space), \
+        e.g. 'URGENT:' turns 'Fix login' into 'URGENT: Fix login'.")]
+    async fn prepend_to_task(
+        &self,
+        Parameters(req): Parameters<PrependTextRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::text(
+            self.run(&[&req.id, "prepend", &req.text]).await?,
+        )]))
+    }
+
+    #[tool(description = "\
+        Append text to a task's description (Taskwarrior adds the separating space). \
+        For notes and context prefer annotate_task, which keeps a timestamp.")]
+    async fn append_to_task(
+        &self,
+        Parameters(req): Parameters<AppendTextRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::text(
+            self.run(&[&req.id, "append", &req.text]).await?,
+        )]))
+    }
+
+    #[tool(description = "\
+        Mark a task done and record why in one step: annotates the task with `note`, then \
+        completes it. Nothing is completed if the note can't be added.")]
+    async fn task_done_with_note(
+        &self,
+        Parameters(req): Parameters<CompleteWithNoteRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let annotated = self
+            .annotate_task(Parameters(AnnotateTaskRequest {
+                id: req.id.clone(),
+                note: req.note,
+                note_lines: None,
+            }))
+            .await?;
+        let annotated = text_content(&annotated);
+        let completed = self
+            .complete_task(Parameters(TaskIdRequest { id: req.id.clone() }))
+            .await
+            .map_err(|e| {
+                McpError::internal_error(
+                    format!(
+                        "Note added ({annotated}), but completing task {} failed: {}",
+                        req.id, e.message
+                    ),
+                    None,
+                )
+            })?;
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "{annotated}\n{}",
+            text_content(&completed)
+        ))]))
+    }
+
+    #[tool(description = "\
+        Create tasks from CSV text, one per data row. The header row names the columns; \
+        description, project, due, and priority are matched case-insensitively (rename them \
+        with the *_column fields). Rows without a project use `default_project`. Returns \
+        what was created and which rows failed, by row number.")]
+    async fn batch_import_from_csv(
+        &self,
+        Parameters(req): Parameters<ImportCsvRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut rows = parse_csv(&req.csv).into_iter();
+        let header = rows
+            .next()
+            .ok_or_else(|| McpError::invalid_params("csv is empty", None))?;
+        let column = |name: Option<&String>, default: &str| {
+            let name = name.map(String::as_str).unwrap_or(default);
+            header
+                .iter()
+                .position(|h| h.trim().eq_ignore_ascii_case(name))
+        };
+        let Some(description_col) = column(req.description_column.as_ref(), "description") else {
+            return Err(McpError::invalid_params(
+                format!(
+                    "No {:?} column in header {header:?}",
+                    req.description_column.as_deref().unwrap_or("description")
+                ),
+                None,
+            ));
+        };
+        let project_col = column(req.project_column.as_ref(), "project");
+        let due_col = column(req.due_column.as_ref(), "due");
+        let priority_col = column(req.priority_column.as_ref(), "priority");
+
+        let mut created = Vec::new();
+        let mut failures = Vec::new();
+        for (n, row) in rows.enumerate() {
+            let cell = |col: Option<usize>| {
+                col.and_then(|i| row.get(i))
+                    .map(|v| v.trim().to_string())
+                    .filter(|v| !v.is_empty())
+            };
+            let Some(description) = cell(Some(description_col)) else {
+                failures.push(format!("row {}: missing description", n + 1));
+                continue;
+            };
+            let Some(project) = cell(project_col).or_else(|| req.default_project.clone()) else {
+                failures.push(format!("row {}: no project and no default_project", n + 1));
+                continue;
+            };
+            let result = self
+                .add_task(Parameters(AddTaskRequest {
+                    description,
+                    project,
+                    due: cell(due_col),
+                    tags: None,
+                    priority: cell(priority_col),
+                    wait: None,
+                    scheduled: None,
+                }))
+                .await;
+            match result {
+                Ok(r) => created.push(format!("row {}: {}", n + 1, text_content(&r))),
+                Err(e) => failures.push(format!("row {}: {}", n + 1, e.message)),
+            }
+        }
+
+        let mut out = format!(
+            "Created {} task(s) ({} failed).",
+            created.len(),
+            failures.len()
+        );
+        for line in created.iter().chain(&failures) {
+            out.push_str(&format!("\n{line}"));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Measure a project's throughput over the last `days` days (default 7): tasks \
+        completed vs. tasks added, velocity in tasks/day, and the net daily change, i.e. \
+        whether the backlog is shrinking or growing. `project` is REQUIRED.")]
+    async fn get_completion_rate(
+        &self,
+        Parameters(req): Parameters<CompletionRateRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        validate_project_name("project", &req.project)?;
+        let days = req.days.unwrap_or(7).max(1);
+        let completed = scoped_filter(
+            &req.project,
+            Some(&format!("end.after:today-{days}d status:completed")),
+            None,
+        );
+        let added = scoped_filter(
+            &req.project,
+            Some(&format!("entry.after:today-{days}d")),
+            None,
+        );
+        let (completed, added) = tokio::join!(self.count(&completed), self.count(&added));
+        let (completed, added) = (completed?, added?);
+
+        let velocity = completed as f64 / f64::from(days);
+        let net = (completed as f64 - added as f64) / f64::from(days);
+        let trend = match completed.cmp(&added) {
+            std::cmp::Ordering::Greater => "backlog shrinking",
+            std::cmp::Ordering::Less => "backlog growing",
+            std::cmp::Ordering::Equal => "backlog steady",
+        };
+        let ratio = if added == 0 {
+            "n/a (nothing added)".to_string()
+        } else {
+            format!("{:.2}", completed as f64 / added as f64)
+        };
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Last {days} day(s) in project {}: {completed} completed, {added} added.\n\
+             Completion ratio: {ratio}\n\
+             Velocity: {velocity:.1} tasks/day, net: {net:+.1}/day ({trend})",
+            req.project
+        ))]))
+    }
+
+    #[tool(description = "\
+        Close out a finished project: completes every pending task in it (and its \
+        subprojects), optionally annotating each with `note` first. Returns how many tasks \
+        were completed. To shelve a project you may resume, use archive_project instead.")]
+    async fn close_project(
+        &self,
+        Parameters(req): Parameters<CloseProjectRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        validate_project_name("project", &req.project)?;
+        let filter = FilterBuilder::new()
+            .project(&req.project)
+            .status("pending")
+            .into_args();
+        if let Some(note) = req.note.as_deref() {
+            let note = annotation_text(note, None);
+            if !note.is_empty() {
+                self.bulk(&filter, &["annotate", &note]).await?;
+            }
+        }
+        let completed = self.bulk(&filter, &["done"]).await?;
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Closed project {}: completed {completed} task(s).",
+            req.project
+        ))]))
+    }
+
+    #[tool(description = "\
+        Show how long each pending task in a project has been open, oldest first, as an \
+        'Age (days) | Task' table. Use `older_than_days` to focus on long-lingering work. \
+        `project` is REQUIRED.")]
+    async fn get_task_age_report(
+        &self,
+        Parameters(req): Parameters<AgeReportRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        validate_project_name("project", &req.project)?;
+        let filter = FilterBuilder::new()
+            .project(&req.project)
+            .status("pending")
+            .into_args();
+        let now = Utc::now();
+        let min_age = i64::from(req.older_than_days.unwrap_or(0));
+        let mut rows: Vec<(i64, String)> = self
+            .export(&filter)
+            .await?
+            .iter()
+            .filter_map(|task| {
+                let entry = parse_tw_date(task.get("entry")?.as_str()?)?;
+                let age = (now - entry).num_days();
+                (age >= min_age).then(|| (age, field_text(task.get("description"))))
+            })
+            .collect();
+        if rows.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "No pending tasks in {} open for at least {min_age} day(s).",
+                req.project
+            ))]));
+        }
+        rows.sort_by_key(|(age, _)| std::cmp::Reverse(*age));
+
+        let mut out = "Age (days) | Task\n-----------|-----".to_string();
+        for (age, description) in &rows {
+            out.push_str(&format!("\n{age:>10} | {description}"));
+        }
+        let average = rows.iter().map(|(age, _)| age).sum::<i64>() as f64 / rows.len() as f64;
+        out.push_str(&format!(
+            "\n\n{} task(s), average age {average:.1} days.",
+            rows.len()
+        ));
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Plan the week ahead: pending tasks due in the next seven days, grouped under one \
+        heading per day. Scoped to `project` when given, otherwise across all projects.")]
+    async fn get_next_week_tasks(
+        &self,
+        Parameters(req): Parameters<NextWeekRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut filter = FilterBuilder::new();
+        if let Some(project) = &req.project {
+            filter = filter.project(project);
+        }
+        let filter = filter
+            .status("pending")
+            .due_after("today")
+            .due_before("today+7d")
+            .into_args();
+        let mut days: std::collections::BTreeMap<DateTime<Local>, Vec<String>> = Default::default();
+        for task in self.export(&filter).await? {
+            let Some(due) = task
+                .get("due")
+                .and_then(|d| d.as_str())
+                .and_then(parse_tw_date)
+            else {
+                continue;
+            };
+            let due = due.with_timezone(&Local);
+            let mut line = format!("  - {}", field_text(task.get("description")));
+            if req.project.is_none() {
+                line.push_str(&format!(" (project:{})", field_text(task.get("project"))));
+            }
+            if due.time() != NaiveTime::MIN {
+                line.push_str(&format!(" at {}", due.format("%H:%M")));
+            }
+            days.entry(due).or_default().push(line);
+        }
+        if days.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "Nothing due in the next seven days.",
+            )]));
+        }
+
+        let mut out = Vec::new();
+        let mut current = None;
+        for (due, lines) in days {
+            let day = due.date_naive();
+            if current != Some(day) {
+                out.push(day.format("%A %Y-%m-%d:").to_string());
+                current = Some(day);
+            }
+            out.extend(lines);
+        }
+        Ok(CallToolResult::success(vec![Content::text(out.join("\n"))]))
+    }
+
+    #[tool(description = "\
+        Put a project in the background without hiding it: sets priority:L on every \
+        pending and waiting task in it (and its subprojects), so its work sinks in the \
+        `next` report. Returns how many tasks changed. To hide a project entirely use \
+        archive_project.")]
+    async fn deprioritize_project(
+        &self,
+        Parameters(req): Parameters<DeprioritizeProjectRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        validate_project_name("project", &req.project)?;
+        let filter = scoped_filter(
+            &req.project,
+            Some("( status:pending or status:waiting ) priority.not:L"),
+            None,
+        );
+        let changed = self
+            .modify_all(&filter, &["priority:L".to_string()])
+            .await?;
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Deprioritized project {}: {changed} task(s) set to priority L.",
+            req.project
+        ))]))
+    }
+
+    #[tool(description = "\
+        Morning maintenance: raise every overdue task to `new_priority` (default H) so it \
+        surfaces at the top of the `next` report. Scoped to `project` when given, \
+        otherwise across all projects. Returns how many tasks were escalated.")]
+    async fn escalate_overdue(
+        &self,
+        Parameters(req): Parameters<EscalateOverdueRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let priority = req.new_priority.unwrap_or_else(|| "H".to_string());
+        if !["H", "M", "L"].contains(&priority.as_str()) {
+            return Err(McpError::invalid_params(
+                format!("Invalid new_priority {priority:?}: expected H, M, or L"),
+                None,
+            ));
+        }
+        let mut filter = FilterBuilder::new();
+        if let Some(project) = &req.project {
+            validate_project_name("project", project)?;
+            filter = filter.project(project);
+        }
+        let filter = filter
+            .tag("OVERDUE")
+            .raw(&format!("priority.not:{priority}"))
+            .into_args();
+        let escalated = self
+            .modify_all(&filter, &[format!("priority:{priority}")])
+            .await?;
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Escalated {escalated} overdue task(s) to priority {priority}."
+        ))]))
+    }
+
+    #[tool(description = "\
+        Look up one tool: its description, JSON parameter schema, and the server version in \
+        which it last changed. Use it to check whether a cached tool definition is stale.")]
+    async fn get_tool_info(
+        &self,
+        Parameters(req): Parameters<GetToolInfoRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let tool = self.get_tool(&req.name).ok_or_else(|| {
+            McpError::invalid_params(format!("Unknown tool {:?}", req.name), None)
+        })?;
+        let info = serde_json::json!({
+            "name": tool.name,
+            "description": tool.description,
+            "input_schema": tool.input_schema,
+            "version": tool_version(&req.name),
+        });
+        Ok(CallToolResult::success(vec![Content::text(
+            serde_json::to_string_pretty(&info).unwrap_or_default(),
+        )]))
+    }
+
+    #[tool(description = "\
+        Explain a task's urgency score: each contributing factor (priority, due, age, \
+        tags, project, …) with its value, coefficient, and contribution, largest first. \
+        Use it to understand why a task ranks where it does in the `next` report.")]
+    async fn get_task_urgency_factors(
+        &self,
+        Parameters(req): Parameters<TaskIdRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let task = self.export_one(&req.id).await?;
+        let mut factors = parse_urgency_factors(&self.run(&[&req.id, "information"]).await?);
+        factors.sort_by(|a, b| b.3.abs().total_cmp(&a.3.abs()));
+
+        let urgency = task.get("urgency").and_then(|u| u.as_f64()).unwrap_or(0.0);
+        let mut out = format!(
+            "Urgency of \"{}\" ({}): {urgency:.3}",
+            field_text(task.get("description")),
+            field_text(task.get("uuid")),
+        );
+        if factors.is_empty() {
+            out.push_str("\nNo urgency factors reported.");
+        }
+        let width = factors.iter().map(|f| f.0.len()).max().unwrap_or(0);
+        for (factor, value, coefficient, contribution) in &factors {
+            out.push_str(&format!(
+                "\n  {factor:<width$}  {value:.3} × {coefficient:.3} = {contribution:+.3}"
+            ));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Set up habit tracking: create one daily recurring task (recur:daily) per description \
+        in `project`. `due_time` sets when the first occurrence is due — a time of day such \
+        as \"07:30\" means today at that time; default today. Returns the created UUIDs.")]
+    async fn batch_create_daily_recurring(
+        &self,
+        Parameters(req): Parameters<DailyHabitRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        validate_project_name("project", &req.project)?;
+        let habits: Vec<&str> = req
+            .descriptions
+            .iter()
+            .map(|d| d.trim())
+            .filter(|d| !d.is_empty())
+            .collect();
+        if habits.is_empty() {
+            return Err(McpError::invalid_params(
+                "descriptions must contain at least one habit",
+                None,
+            ));
+        }
+        let due = match req.due_time.as_deref().map(str::trim) {
+            None | Some("") => "today".to_string(),
+            Some(time) => match NaiveTime::parse_from_str(time, "%H:%M") {
+                Ok(time) => Local::now()
+                    .date_naive()
+                    .and_time(time)
+                    .format("%Y-%m-%dT%H:%M")
+                    .to_string(),
+                Err(_) => {
+                    validate_date("due_time", time)?;
+                    time.to_string()
+                }
+            },
+        };
+
+        let mut out = format!("Created {} daily habits in {}:", habits.len(), req.project);
+        for description in habits {
+            let mut args = vec![
+                "add".to_string(),
+                description.to_string(),
+                format!("project:{}", req.project),
+                "recur:daily".to_string(),
+                format!("due:{due}"),
+            ];
+            for tag in req.tags.iter().flatten() {
+                args.push(format!("+{}", tag.trim_start_matches('+')));
+            }
+            let created = self.run_add(&args).await?;
+            let uuid = parse_created_uuid(&created).unwrap_or("?");
+            out.push_str(&format!("\n- {uuid} {description}"));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Clear every dependency of a task at once (`depends:`), so a +BLOCKED task becomes \
+        actionable without looking up each blocker's UUID. Returns the removed dependency \
+        UUIDs.")]
+    async fn task_unblock(
+        &self,
+        Parameters(req): Parameters<TaskIdRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let task = self.export_one(&req.id).await?;
+        let depends = depends_of(&task);
+        if depends.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(format!(
+                "Task {} has no dependencies.",
+                req.id
+            ))]));
+        }
+        self.run(&[&req.id, "modify", "depends:"]).await?;
+
+        let mut out = format!(
+            "Removed {} dependencies from task {}:",
+            depends.len(),
+            req.id
+        );
+        for uuid in &depends {
+            out.push_str(&format!("\n- {uuid}"));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Link a task to a GitHub issue: annotates it with \
+        https://github.com/<repo>/issues/<issue_number>, and also sets the `github_issue` \
+        UDA to the issue number when that UDA is configured in the taskrc.")]
+    async fn link_to_github_issue(
+        &self,
+        Parameters(req): Parameters<GithubLinkRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let valid_part = |part: &str| {
+            !part.is_empty()
+                && part
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || "-_.".contains(c))
+        };
+        if !matches!(req.repo.split_once('/'), Some((owner, name)) if valid_part(owner) && valid_part(name))
+        {
+            return Err(McpError::invalid_params(
+                format!("Invalid repo {:?}: expected \"owner/name\"", req.repo),
+                None,
+            ));
+        }
+        let url = format!(
+            "https://github.com/{}/issues/{}",
+            req.repo, req.issue_number
+        );
+        let mut out = self.run(&[&req.id, "annotate", &url]).await?;
+
+        let udas = self.run(&["_udas"]).await.unwrap_or_default();
+        if udas.lines().any(|uda| uda.trim() == GITHUB_ISSUE_UDA) {
+            let value = format!("{GITHUB_ISSUE_UDA}:{}", req.issue_number);
+            self.run(&[&req.id, "modify", &value]).await?;
+            out.push_str(&format!("\nSet {value}."));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Collect the links in a project: every http(s) URL found in task annotations, as a \
+        table of task ID, URL, and description. Pass `domain` (e.g. \"github.com\") to keep \
+        only links to that site. Completed tasks are included, shown by short UUID.")]
+    async fn get_tasks_with_url(
+        &self,
+        Parameters(req): Parameters<GetTasksWithUrlRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let mut filter = scoped_filter(&req.project, None, None);
+        filter.push("status.not:deleted".to_string());
+        let mut rows: Vec<(String, String, String)> = Vec::new();
+        for task in self.export(&filter).await? {
+            let id = match task.get("id").and_then(|id| id.as_u64()) {
+                Some(id) if id > 0 => id.to_string(),
+                _ => field_text(task.get("uuid")).chars().take(8).collect(),
+            };
+            let notes = task.get("annotations").and_then(|a| a.as_array());
+            for note in notes.into_iter().flatten() {
+                let text = note.get("description").and_then(|d| d.as_str());
+                for url in extract_urls(text.unwrap_or_default()) {
+                    if req.domain.as_deref().is_none_or(|d| url_in_domain(url, d)) {
+                        let description = field_text(task.get("description"));
+                        rows.push((id.clone(), url.to_string(), description));
+                    }
+                }
+            }
+        }
+        if rows.is_empty() {
+            return Ok(CallToolResult::success(vec![Content::text(
+                "No annotated URLs found.",
+            )]));
+        }
+
+        let id_width = rows
+            .iter()
+            .map(|r| r.0.len())
+            .chain(["ID".len()])
+            .max()
+            .unwrap_or(0);
+        let url_width = rows
+            .iter()
+            .map(|r| r.1.len())
+            .chain(["URL".len()])
+            .max()
+            .unwrap_or(0);
+        let mut out = format!(
+            "{:<id_width$} | {:<url_width$} | Description\n{}-|-{}-|------------",
+            "ID",
+            "URL",
+            "-".repeat(id_width),
+            "-".repeat(url_width)
+        );
+        for (id, url, description) in &rows {
+            out.push_str(&format!(
+                "\n{id:<id_width$} | {url:<url_width$} | {description}"
+            ));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Tagging rule: add `tag` to every pending and waiting task in `project_prefix` and \
+        its subprojects (prefix Work covers Work.Backend and Work.Frontend) that doesn't \
+        carry it yet. Returns how many tasks were tagged.")]
+    async fn tag_tasks_by_project_prefix(
+        &self,
+        Parameters(req): Parameters<AutoTagRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        validate_project_name("project_prefix", &req.project_prefix)?;
+        let tag = req.tag.trim_start_matches('+');
+        if tag.is_empty() || tag.contains(char::is_whitespace) {
+            return Err(McpError::invalid_params(
+                format!("Invalid tag {:?}: expected a single word", req.tag),
+                None,
+            ));
+        }
+        let filter = FilterBuilder::new()
+            .project(&req.project_prefix)
+            .raw("( status:pending or status:waiting )")
+            .not_tag(tag)
+            .into_args();
+        let tagged = self.modify_all(&filter, &[format!("+{tag}")]).await?;
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Tagged {tagged} task(s) in {} with +{tag}.",
+            req.project_prefix
+        ))]))
+    }
+
+    #[tool(description = "\
+        Forecast when a project will be finished: divides its pending tasks by the \
+        velocity (tasks completed per day over the last `velocity_window_days`, default \
+        14) and returns the estimated date along with the assumptions behind it. \
+        `project` is REQUIRED.")]
+    async fn estimate_completion_date(
+        &self,
+        Parameters(req): Parameters<EstimateCompletionRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        validate_project_name("project", &req.project)?;
+        let days = req.velocity_window_days.unwrap_or(14).max(1);
+        let completed = scoped_filter(
+            &req.project,
+            Some(&format!("end.after:today-{days}d status:completed")),
+            None,
+        );
+        let pending = scoped_filter(&req.project, Some("status:pending"), None);
+        let (completed, pending) = tokio::join!(self.count(&completed), self.count(&pending));
+        let (completed, pending) = (completed?, pending?);
+
+        let velocity = completed as f64 / f64::from(days);
+        let assumptions = format!(
+            "Assumptions: {pending} pending task(s); velocity {velocity:.1} tasks/day \
+             ({completed} completed in the last {days} day(s)); no new tasks are added.",
+        );
+        let today = Local::now().date_naive();
+        let estimate = if pending == 0 {
+            format!("Project {} has no pending tasks.", req.project)
+        } else if let Some(date) = estimate_finish(pending, completed, days, today) {
+            format!(
+                "Estimated completion of {}: {date} (in {} day(s)).",
+                req.project,
+                (date - today).num_days()
+            )
+        } else {
+            format!(
+                "Cannot estimate completion of {}: nothing was completed in the last \
+                 {days} day(s).",
+                req.project
+            )
+        };
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "{estimate}\n{assumptions}"
+        ))]))
+    }
+
+    #[tool(description = "\
+        Inspect a recurring task: its recurrence period, the next due date, and every \
+        instance generated so far (with ID, status, and due date). `id` may be the \
+        recurring template or any of its instances.")]
+    async fn recurring_task_status(
+        &self,
+        Parameters(req): Parameters<RecurringStatusRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let task = self.export_one(&req.id).await?;
+        let template = match task.get("parent").and_then(|p| p.as_str()) {
+            Some(parent) => self
+                .export(&[parent.to_string()])
+                .await?
+                .into_iter()
+                .next()
+                .ok_or_else(|| {
+                    McpError::internal_error(format!("Recurring template {parent} not found"), None)
+                })?,
+            None if field_text(task.get("status")) == "recurring" => task,
+            None => {
+                return Err(McpError::invalid_params(
+                    format!("Task {} is not a recurring task", req.id),
+                    None,
+                ))
+            }
+        };
+        let uuid = field_text(template.get("uuid"));
+        let recur = field_text(template.get("recur"));
+        let mut children = self.export(&[format!("parent:{uuid}")]).await?;
+        children
+            .sort_by_key(|child| child.get("imask").and_then(|i| i.as_f64()).unwrap_or(0.0) as i64);
+
+        let open = |child: &&serde_json::Value| {
+            matches!(
+                field_text(child.get("status")).as_str(),
+                "pending" | "waiting"
+            )
+        };
+        let next_due = children
+            .iter()
+            .filter(open)
+            .filter_map(|child| child.get("due").and_then(|d| d.as_str()))
+            .min()
+            .map(display_date)
+            .or_else(|| {
+                // Nothing open: the next instance is one period past the last generated.
+                let due = parse_tw_date(template.get("due")?.as_str()?)?;
+                let generated = field_text(template.get("mask")).len() as u32;
+                let next = advance_recurrence(due, &recur, generated)?;
+                Some(display_date(&next.format("%Y%m%dT%H%M%SZ").to_string()))
+            })
+            .unwrap_or_else(|| "unknown".to_string());
+
+        let mut out = format!(
+            "Recurring task \"{}\" ({uuid}), recur: {recur}\nNext due: {next_due}",
+            field_text(template.get("description"))
+        );
+        if let Some(until) = template.get("until").and_then(|u| u.as_str()) {
+            out.push_str(&format!("\nUntil: {}", display_date(until)));
+        }
+        if children.is_empty() {
+            out.push_str("\nNo instances generated yet.");
+        } else {
+            out.push_str("\nInstances (index · ID · status · due):");
+        }
+        for child in &children {
+            let id = match child.get("id").and_then(|id| id.as_u64()) {
+                Some(id) if id > 0 => id.to_string(),
+                _ => field_text(child.get("uuid")).chars().take(8).collect(),
+            };
+            out.push_str(&format!(
+                "\n  {} · {id} · {} · {}",
+                field_text(child.get("imask")),
+                field_text(child.get("status")),
+                display_date(&field_text(child.get("due"))),
+            ));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Release everything deferred in a project: clears the wait date on every waiting \
+        task in it (and its subprojects) so they show up in list_tasks again right away. \
+        Returns how many tasks were released.")]
+    async fn mark_all_waiting_as_pending(
+        &self,
+        Parameters(req): Parameters<UnwaitAllRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let released = self.release_waiting(&req.project).await?;
+        Ok(CallToolResult::success(vec![Content::text(format!(
+            "Released {released} waiting task(s) in {}.",
+            req.project
+        ))]))
+    }
+
+    #[tool(description = "\
+        Draw a project's task dependencies as a text graph (`A ─▶ B` means A depends on B), \
+        up to 5 levels deep. By default every end goal — a task with dependencies that \
+        nothing depends on — is a root; pass `root_id` to draw just one task's graph. \
+        Completed blockers are marked ✓.")]
+    async fn dependency_visualization(
+        &self,
+        Parameters(req): Parameters<DependencyGraphRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let root = match &req.root_id {
+            Some(id) => {
+                let task = self.export_one(id).await?;
+                Some(field_text(task.get("uuid")))
+            }
+            None => None,
+        };
+        let mut filter = scoped_filter(&req.project, None, None);
+        filter.push("status.not:deleted".to_string());
+        let tasks = self.export(&filter).await?;
+
+        let graph = render_dependency_graph(&tasks, root.as_deref());
+        let out = if root.is_none() && graph.is_empty() {
+            format!("No dependencies in project {}.", req.project)
+        } else {
+            format!(
+                "Dependency graph for {} (A ─▶ B: A depends on B):\n{graph}",
+                req.project
+            )
+        };
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Turn a task's annotations into a dated journal document, oldest note first. \
+        `format` \"markdown\" (default) gives `# Task:` / `## Annotations` / `### <date time>` \
+        headings; \"plain\" gives the same layout without Markdown. Use it to hand a \
+        task's history to a report or another tool.")]
+    async fn task_notes_to_document(
+        &self,
+        Parameters(req): Parameters<NotesToDocumentRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let markdown = match req.format.as_deref().unwrap_or("markdown") {
+            "markdown" => true,
+            "plain" => false,
+            other => {
+                return Err(McpError::invalid_params(
+                    format!("Invalid format {other:?}: expected \"markdown\" or \"plain\""),
+                    None,
+                ))
+            }
+        };
+        let task = self.export_one(&req.id).await?;
+        Ok(CallToolResult::success(vec![Content::text(
+            notes_document(&task, markdown),
+        )]))
+    }
+
+    #[tool(description = "\
+        List a task's siblings: the other pending tasks in exactly the same project (not \
+        subprojects) with the same priority, most urgent first. Useful for comparing \
+        tasks that compete for the same slot.")]
+    async fn get_task_siblings(
+        &self,
+        Parameters(req): Parameters<TaskIdRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let task = self.export_one(&req.id).await?;
+        let uuid = field_text(task.get("uuid"));
+        let project = field_text(task.get("project"));
+        let priority = field_text(task.get("priority"));
+        let filter = FilterBuilder::new()
+            .raw(&format!("project.is:{project}"))
+            .priority(&priority)
+            .status("pending")
+            .into_args();
+
+        let mut siblings: Vec<serde_json::Value> = self
+            .export(&filter)
+            .await?
+            .into_iter()
+            .filter(|other| field_text(other.get("uuid")) != uuid)
+            .collect();
+        let urgency =
+            |t: &serde_json::Value| t.get("urgency").and_then(|u| u.as_f64()).unwrap_or(0.0);
+        siblings.sort_by(|a, b| urgency(b).total_cmp(&urgency(a)));
+
+        let project = if project.is_empty() {
+            "(none)"
+        } else {
+            &project
+        };
+        let priority = if priority.is_empty() {
+            "none"
+        } else {
+            &priority
+        };
+        let mut out = format!(
+            "{} sibling(s) of task {} in project {project} at priority {priority}",
+            siblings.len(),
+            req.id
+        );
+        out.push(if siblings.is_empty() { '.' } else { ':' });
+        for sibling in &siblings {
+            out.push_str(&format!(
+                "\n- {} {} (urgency {:.2})",
+                field_text(sibling.get("id")),
+                field_text(sibling.get("description")),
+                urgency(sibling)
+            ));
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Make one task outrank another in urgency. If `higher_id` has the lower priority \
+        of the two, their priorities are swapped; if it still doesn't rank higher (e.g. \
+        same priority), it gets the +next tag and `lower_id` loses it. Returns both new \
+        urgency scores.")]
+    async fn reorder_task_priority(
+        &self,
+        Parameters(req): Parameters<ReorderRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let urgency =
+            |t: &serde_json::Value| t.get("urgency").and_then(|u| u.as_f64()).unwrap_or(0.0);
+        let rank = |t: &serde_json::Value| match field_text(t.get("priority")).as_str() {
+            "H" => 3,
+            "M" => 2,
+            "L" => 1,
+            _ => 0,
+        };
+        let (higher, lower) = (
+            self.export_one(&req.higher_id).await?,
+            self.export_one(&req.lower_id).await?,
+        );
+        let higher_uuid = field_text(higher.get("uuid"));
+        let lower_uuid = field_text(lower.get("uuid"));
+        if higher_uuid == lower_uuid {
+            return Err(McpError::invalid_params(
+                "higher_id and lower_id refer to the same task",
+                None,
+            ));
+        }
+
+        let mut actions = Vec::new();
+        if rank(&higher) < rank(&lower) {
+            let (up, down) = (
+                field_text(lower.get("priority")),
+                field_text(higher.get("priority")),
+            );
+            self.run(&[&higher_uuid, "modify", &format!("priority:{up}")])
+                .await?;
+            self.run(&[&lower_uuid, "modify", &format!("priority:{down}")])
+                .await?;
+            actions.push(format!("swapped priorities ({up} ↔ {down})"));
+        }
+        let (mut higher, mut lower) = (
+            self.export_one(&higher_uuid).await?,
+            self.export_one(&lower_uuid).await?,
+        );
+        if urgency(&higher) <= urgency(&lower) {
+            self.run(&[&higher_uuid, "modify", "+next"]).await?;
+            self.run(&[&lower_uuid, "modify", "-next"]).await?;
+            actions.push("tagged the higher task +next".to_string());
+            (higher, lower) = (
+                self.export_one(&higher_uuid).await?,
+                self.export_one(&lower_uuid).await?,
+            );
+        }
+
+        let mut out = if actions.is_empty() {
+            "Already in order; nothing changed.".to_string()
+        } else {
+            format!("Reordered: {}.", actions.join(", then "))
+        };
+        out.push_str(&format!(
+            "\nHigher: {} — urgency {:.2}\nLower: {} — urgency {:.2}",
+            field_text(higher.get("description")),
+            urgency(&higher),
+            field_text(lower.get("description")),
+            urgency(&lower)
+        ));
+        if urgency(&higher) <= urgency(&lower) {
+            out.push_str(
+                "\nThe lower task still outranks it (due date or other factors dominate).",
+            );
+        }
+        Ok(CallToolResult::success(vec![Content::text(out)]))
+    }
+
+    #[tool(description = "\
+        Cheap pre-flight check that a task ID or UUID refers to an existing task. Returns \
+        JSON: {\"exists\": true, \"uuid\": \"…\"} or {\"exists\": false}. Anything that is \
+        not a numeric ID or a (possibly abbreviated) UUID is reported as not existing.")]
+    async fn check_task_exists(
+        &self,
+        Parameters(req): Parameters<TaskIdRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let id = req.id.trim();
+        // Anything else would be taken as a description search by `task <filter> count`.
+        let looks_like_id = !id.is_empty() && id.chars().all(|c| c.is_ascii_digit());
+        let looks_like_uuid = id.len() >= 8
+            && id.len() <= 36
+            && id.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+        let mut found = None;
+        if looks_like_id || looks_like_uuid {
+            let filter = [id.to_string()];
+            if self.count(&filter).await? > 0 {
+                let uuids = self.run(&[id, "_uuids"]).await?;
+                found = uuids.lines().next().map(str::to_string);
+            }
+        }
+        let result = match found {
+            Some(uuid) => serde_json::json!({ "exists": true, "uuid": uuid }),
+            None => serde_json::json!({ "exists": false }),
+        };
+        Ok(CallToolResult::success(vec![Content::text(
+            result.to_string(),
+        )]))
+    }
+}
+
+// ── Timewarrior ──────────────────────────────────────────────────────────────
+
+#[cfg(feature = "timew-integration")]
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct TimewRequest {
+    /// Task ID (numeric) or UUID
+    id: String,
+}
+
+#[cfg(feature = "timew-integration")]
+#[derive(Debug, serde::Deserialize, schemars::JsonSchema)]
+struct TimewSummaryRequest {
+    /// Only intervals carrying this tag, e.g. a task description passed to timew_start
+    tag: Option<String>,
+    /// Timewarrior range hint or range (default ":week"), e.g. ":day", ":lastweek", ":month"
+    period: Option<String>,
+}
+
+#[cfg(feature = "timew-integration")]
+impl TaskWarriorServer {
+    /// Run `timew` with the given arguments. Unlike `task`, Timewarrior is optional, so a
+    /// missing binary gets an explicit error instead of a bare spawn failure.
+    async fn run_timew(&self, args: &[&str]) -> Result<String, McpError> {
+        let mut cmd = Command::new("timew");
+        cmd.args(args).stdin(std::process::Stdio::null());
+        // Keep tests off the user's real Timewarrior database.
+        #[cfg(test)]
+        if let Some(dir) = &self.data_dir {
+            cmd.env("TIMEWARRIORDB", dir.join("timewarrior"));
+        }
+
+        let output = cmd.output().await.map_err(|e| {
+            if e.kind() == std::io::ErrorKind::NotFound {
+                McpError::internal_error(
+                    "Timewarrior is not installed: `timew` was not found on $PATH",
+                    None,
+                )
+            } else {
+                McpError::internal_error(format!("Failed to run timew: {e}"), None)
+            }
+        })?;
+
+        let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if !output.status.success() {
+            return Err(McpError::internal_error(
+                if !stderr.is_empty() {
+                    stderr
+                } else {
+                    format!("timew exited with status {}", output.status)
+                },
+                None,
+            ));
+        }
+        Ok(if !stdout.is_empty() { stdout } else { stderr })
+    }
+}
+
+#[cfg(feature = "timew-integration")]
+#[tool_router(router = timew_router)]
+impl TaskWarriorServer {
+    #[tool(description = "\
+        Start Timewarrior time tracking for a task, tagged with the task's description. \
+        Stops any interval already running. Requires `timew` on $PATH.")]
+    async fn timew_start(
+        &self,
+        Parameters(req): Parameters<TimewRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let task = self.export_one(&req.id).await?;
+        let description = field_text(task.get("description"));
+        Ok(CallToolResult::success(vec![Content::text(
+            self.run_timew(&["start", &description]).await?,
+        )]))
+    }
+
+    #[tool(description = "Stop the running Timewarrior interval. Requires `timew` on $PATH.")]
+    async fn timew_stop(&self) -> Result<CallToolResult, McpError> {
+        Ok(CallToolResult::success(vec![Content::text(
+            self.run_timew(&["stop"]).await?,
+        )]))
+    }
+
+    #[tool(description = "\
+        Report time tracked in Timewarrior for a period (default this week), optionally \
+        limited to one tag. Requires `timew` on $PATH.")]
+    async fn get_timewarrior_summary(
+        &self,
+        Parameters(req): Parameters<TimewSummaryRequest>,
+    ) -> Result<CallToolResult, McpError> {
+        let period = req.period.unwrap_or_else(|| ":week".to_string());
+        let mut args = vec!["summary", period.as_str()];
+        if let Some(tag) = &req.tag {
+            args.push(tag);
+        }
+        Ok(CallToolResult::success(vec![Content::text(
+            self.run_timew(&args).await?,
+        )]))
+    }
+}
+
+// Hand-rolled instead of `#[tool_handler]` so every call is timed into `metrics`.
+impl ServerHandler for TaskWarriorServer {
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParams,
+        context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, McpError> {
+        let name = request.name.clone();
+        let common: CommonRequestFields = request
+            .arguments
+            .as_ref()
+            .and_then(|args| serde_json::from_value(serde_json::Value::Object(args.clone())).ok())
+            .unwrap_or_default();
+        let req_id = request
+            .meta
+            .as_ref()
+            .and_then(request_id_from)
+            .or_else(|| request_id_from(&context.meta));
+        let span = tracing::info_span!("tool", %name, request_id = req_id.as_deref());
+        let event = change_event(&name);
+        let arg = |key: &str| {
+            let args = request.arguments.as_ref()?;
+            args.get(key)?.as_str().map(str::to_string)
+        };
+        let id = arg("id");
+        // Completing or deleting a task drops its numeric ID, so look it up beforehand.
+        let target = match (event, &id) {
+            (Some("task_completed" | "task_deleted"), Some(id)) => self.export_one(id).await.ok(),
+            _ => None,
+        };
+        let peer = context.peer.clone();
+        let started = std::time::Instant::now();
+        let result = with_request_id(
+            req_id.clone(),
+            self.tool_router
+                .call(ToolCallContext::new(self, request, context)),
+        )
+        .instrument(span)
+        .await
+        .map_err(|mut e| {
+            if let Some(id) = &req_id {
+                e.message = format!("{} (request_id: {id})", e.message).into();
+            }
+            e
+        });
+        let failed = match &result {
+            Ok(r) => r.is_error == Some(true),
+            Err(_) => true,
+        };
+        self.metrics.record(&name, started.elapsed(), failed).await;
+        if let (Some(event), Ok(result), false) = (event, &result, failed) {
+            let target = match (target, &id) {
+                (None, Some(id)) if event == "task_modified" => self.export_one(id).await.ok(),
+                (target, _) => target,
+            };
+            let (task_id, project) = match target {
+                Some(task) => (task["uuid"].clone(), task["project"].clone()),
+                // add_task: the UUID is in the confirmation, the resolved project in `_meta`.
+                None => (
+                    serde_json::json!(parse_created_uuid(&text_content(result))),
+                    result
+                        .meta
+                        .as_ref()
+                        .and_then(|meta| meta.get(PROJECT_META))
+                        .cloned()
+                        .unwrap_or_default(),
+                ),
+            };
+            let data =
+                serde_json::json!({ "event": event, "task_id": task_id, "project": project });
+            // Sent from a separate task so it follows, rather than delays, the tool result.
+            tokio::spawn(async move {
+                let notification = LoggingMessageNotificationParam {
+                    level: LoggingLevel::Info,
+                    logger: Some(CHANGE_LOGGER.to_string()),
+                    data,
+                };
+                if let Err(e) = peer.notify_logging_message(notification).await {
+                    tracing::debug!("Failed to send {event} notification: {e}");
+                }
+            });
+        }
+        if common.json_envelope.unwrap_or(false) {
+            return Ok(json_envelope(&name, result));
+        }
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult::with_all_items(
+            self.tool_router
+                .list_all()
+                .into_iter()
+                .map(with_common_fields)
+                .collect(),
+        ))
+    }
+
+    fn get_tool(&self, name: &str) -> Option<Tool> {
+        self.tool_router.get(name).cloned().map(with_common_fields)
+    }
+
+    async fn list_resources(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListResourcesResult, McpError> {
+        Ok(ListResourcesResult::with_all_items(
+            self.list_task_resources().await?,
+        ))
+    }
+
+    async fn read_resource(
+        &self,
+        request: ReadResourceRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ReadResourceResult, McpError> {
+        self.read_task_resource(&request.uri).await
+    }
+
+    async fn list_prompts(
+        &self,
+        _request: Option<PaginatedRequestParams>,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListPromptsResult, McpError> {
+        Ok(ListPromptsResult::with_all_items(workflow_prompts()))
+    }
+
+    async fn get_prompt(
+        &self,
+        request: GetPromptRequestParams,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<GetPromptResult, McpError> {
+        let project = request
+            .arguments
+            .as_ref()
+            .and_then(|args| args.get("project"))
+            .and_then(|p| p.as_str());
+        workflow_prompt(&request.name, project).ok_or_else(|| {
+            McpError::invalid_params(format!("Unknown prompt {:?}", request.name), None)
+        })
+    }
+
+    fn get_info(&self) -> ServerInfo {
+        let udas = self.udas.read().expect("uda lock poisoned").clone();
+        let mut instructions = "Taskwarrior MCP server. PROJECT SCOPING IS MANDATORY: \
+                add_task requires `project`, list_tasks and search_tasks require `project` and \
+                automatically prepend it as a filter — this prevents thousands of unrelated tasks \
+                from flooding context. Only pass all_projects=true when the user explicitly asks \
+                for a cross-project view. \
+                FILTER DISCIPLINE: never call list_tasks without a filter or a non-default report \
+                unless every pending task is genuinely needed. Match filter/report to intent: \
+                actionable → filter='+READY'; overdue → filter='+OVERDUE'; today → filter='+TODAY'; \
+                blocked → filter='+BLOCKED'; snoozed → report='waiting'; history → report='completed'. \
+                Tools: add_task · list_tasks · search_tasks · get_task · modify_task · complete_task · delete_task · annotate_task · \
+                export_tasks_csv · export_tasks_markdown · import_tasks_from_markdown · export_tasks_ical · \
+                import_tasks_from_json_array · get_completed_tasks · get_recurring_tasks · edit_annotation · \
+                suggest_due_date · get_next_actions · get_focus_tasks · set_task_wait · clear_wait · \
+                get_task_count_by_project · get_task_count_by_tag · get_task_count_by_priority · \
+                get_weekly_review · get_sprint_summary · get_recently_completed · get_recently_added · \
+                get_task_changelog · search_tasks_by_due_range · get_task_dependencies_recursive · \
+                cleanup_orphan_dependencies · task_search_and_replace_description · rename_project · \
+                archive_project · unarchive_project · get_tag_usage_report · get_tag_details · normalize_tags · \
+                register_template · add_task_from_template · list_templates · delete_template · \
+                stream_list_tasks · suggest_projects · suggest_tags · get_task_effort_report · health_check · \
+                get_server_info · get_metrics · task_split · merge_tasks · create_task_chain · \
+                task_inbox_process · task_capture · get_someday_maybe_list · add_someday_task · \
+                save_filter_preset · list_filter_presets · delete_filter_preset · run_filter_preset · \
+                task_quick_add · fuzzy_search_tasks · get_tasks_due_today · get_tasks_due_this_week · \
+                urgency_report · task_review · task_describe · validate_task_data · task_backup · \
+                task_restore · describe_filters · prepend_to_task · append_to_task · task_done_with_note · \
+                batch_import_from_csv · get_completion_rate · close_project · get_task_age_report · \
+                get_next_week_tasks · deprioritize_project · escalate_overdue · get_tool_info · \
+                get_task_urgency_factors · batch_create_daily_recurring · task_unblock · link_to_github_issue · \
+                get_tasks_with_url · tag_tasks_by_project_prefix · estimate_completion_date · \
+                recurring_task_status · mark_all_waiting_as_pending · dependency_visualization · \
+                task_notes_to_document · get_task_siblings · reorder_task_priority · check_task_exists. \
+                Resources: task://project/<name> · task://task/<uuid> (JSON exports). \
+                Prompts: daily-standup · weekly-review · inbox-processing · sprint-planning. \
+                Date syntax: today · tomorrow · eow · eom · friday · 2025-06-15 · 2025-06-15T14:30. \
+                Virtual filter tags: +OVERDUE · +DUE · +READY · +BLOCKED · +BLOCKING · +ACTIVE · +WAITING · +TODAY."
+            .to_string();
+        instructions.push(' ');
+        instructions.push_str(&changelog_summary());
+        if !udas.is_empty() {
+            instructions.push_str(&format!(
+                " User-defined attributes (set as name:value in modifications): {}.",
+                udas.join(" · ")
+            ));
+        }
+        let mut extensions = ExtensionCapabilities::new();
+        extensions.insert(
+            UDA_EXTENSION.to_string(),
+            JsonObject::from_iter([("udas".to_string(), serde_json::json!(udas))]),
+        );
+
+        ServerInfo {
+            protocol_version: ProtocolVersion::V_2024_11_05,
+            capabilities: ServerCapabilities::builder()
+                .enable_extensions_with(extensions)
+                .enable_logging()
+                .enable_tools()
+                .enable_resources()
+                .enable_prompts()
+                .build(),
+            server_info: Implementation {
+                name: env!("CARGO_PKG_NAME").to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                title: None,
+                description: None,
+                icons: None,
+                website_url: None,
+            },
+            instructions: Some(instructions),
+        }
+    }
+}
+
+// ── Entry point ───────────────────────────────────────────────────────────────
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
+        .with_writer(std::io::stderr)
+        .with_ansi(false)
+        .init();
+
+    tracing::info!("Starting task-warrior-mcp");
+
+    let server = TaskWarriorServer::new();
+    server.refresh_udas().await;
+    let refresher = server.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(UDA_REFRESH_INTERVAL);
+        interval.tick().await; // the first tick completes immediately
+        loop {
+            interval.tick().await;
+            refresher.refresh_udas().await;
+        }
+    });
+    let drain = server.drain.clone();
+    let drain_timeout = std::env::var("TASKWARRIOR_DRAIN_SECS")
+        .ok()
+        .and_then(|s| s.parse().ok())
+        .map_or(DEFAULT_DRAIN_TIMEOUT, Duration::from_secs);
+
+    let service = server
+        .serve(stdio())
+        .await
+        .inspect_err(|e| tracing::error!("Serving error: {e:?}"))?;
+    let ct = service.cancellation_token();
+
+    tokio::select! {
+        result = service.waiting() => {
+            result?;
+        }
+        _ = shutdown_signal() => {
+            tracing::info!("Shutdown requested; draining in-flight task calls");
+            ct.cancel();
+            drain.close();
+            if !drain.wait_idle(drain_timeout).await {
+                // Returning drops the remaining futures; `kill_on_drop` reaps their children.
+                tracing::warn!("Drain timed out after {drain_timeout:?}; killing remaining task processes");
+            }
+        }
+    }
+    Ok(())
+}
+
+// ── Tests ─────────────────────────────────────────────────────────────────────
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    // ── helpers ───────────────────────────────────────────────────────────────
+
+    fn test_server() -> (TempDir, TaskWarriorServer) {
+        let dir = TempDir::new().expect("failed to create temp dir");
+        let server = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .build();
+        (dir, server)
+    }
+
+    fn text_of(result: &CallToolResult) -> &str {
+        result
+            .content
+            .first()
+            .expect("no content")
+            .as_text()
+            .expect("expected text content")
+            .text
+            .as_str()
+    }
+
+    /// Parse "Created task 5 (uuid: …)." → "5"
+    fn created_id(output: &str) -> String {
+        output
+            .split_whitespace()
+            .nth(2)
+            .unwrap_or("1")
+            .trim_end_matches('.')
+            .to_string()
+    }
+
+    /// Parse "Created task 5 (uuid: abc-123)." → "abc-123"
+    fn created_uuid(output: &str) -> String {
+        parse_created_uuid(output)
+            .expect("no uuid in add_task output")
+            .to_string()
+    }
+
+    async fn add_task(server: &TaskWarriorServer, desc: &str, project: &str) -> String {
+        let result = server
+            .add_task(Parameters(AddTaskRequest {
+                description: desc.to_string(),
+                project: project.to_string(),
+                due: None,
+                tags: None,
+                priority: None,
+                wait: None,
+                scheduled: None,
+            }))
+            .await
+            .expect("add_task failed");
+        created_id(text_of(&result))
+    }
+
+    /// Resolve a numeric ID to its UUID, which stays stable as IDs renumber.
+    async fn uuid_of(server: &TaskWarriorServer, id: &str) -> String {
+        server.run(&[id, "_uuids"]).await.expect("_uuids failed")
+    }
+
+    /// Pending tasks in `project`, rendered by the flat `list` report.
+    async fn list_text(server: &TaskWarriorServer, project: &str) -> String {
+        let result = server
+            .list_tasks(Parameters(ListTasksRequest {
+                project: project.to_string(),
+                filter: None,
+                report: Some("list".to_string()),
+                all_projects: None,
+                ..Default::default()
+            }))
+            .await
+            .expect("list_tasks failed");
+        text_of(&result).to_string()
+    }
+
+    async fn annotate(server: &TaskWarriorServer, id: &str, note: &str) {
+        server
+            .annotate_task(Parameters(AnnotateTaskRequest {
+                id: id.to_string(),
+                note: note.to_string(),
+                note_lines: None,
+            }))
+            .await
+            .expect("annotate_task failed");
+    }
+
+    fn annotation_texts(task: &serde_json::Value) -> Vec<String> {
+        task["annotations"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .map(|a| a["description"].as_str().unwrap_or_default().to_string())
+            .collect()
+    }
+
+    // ── detect_data_dir ───────────────────────────────────────────────────────
+
+    fn env_from<'a>(vars: &'a [(&'a str, String)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |key| vars.iter().find(|(k, _)| *k == key).map(|(_, v)| v.clone())
+    }
+
+    #[test]
+    fn test_detect_data_dir_explicit_env_wins() {
+        let home = TempDir::new().unwrap();
+        std::fs::create_dir_all(home.path().join(".task")).unwrap();
+        let vars = [
+            ("TASKWARRIOR_DATA_DIR", "/explicit/dir".to_string()),
+            ("HOME", home.path().display().to_string()),
+        ];
+
+        assert_eq!(
+            detect_data_dir_from(env_from(&vars)),
+            Some(PathBuf::from("/explicit/dir"))
+        );
+    }
+
+    #[test]
+    fn test_detect_data_dir_precedence() {
+        let home = TempDir::new().unwrap();
+        let xdg = TempDir::new().unwrap();
+        let vars = [
+            ("HOME", home.path().display().to_string()),
+            ("XDG_DATA_HOME", xdg.path().display().to_string()),
+        ];
+        let detect = || detect_data_dir_from(env_from(&vars));
+
+        // Nothing exists yet → 2.x default
+        assert_eq!(detect(), Some(home.path().join(".task")));
+
+        std::fs::create_dir_all(home.path().join(".task")).unwrap();
+        assert_eq!(detect(), Some(home.path().join(".task")));
+
+        std::fs::create_dir_all(home.path().join(".local/share/task")).unwrap();
+        assert_eq!(detect(), Some(home.path().join(".local/share/task")));
+
+        std::fs::create_dir_all(xdg.path().join("task")).unwrap();
+        assert_eq!(detect(), Some(xdg.path().join("task")));
+    }
+
+    #[test]
+    fn test_detect_data_dir_without_home() {
+        let xdg = TempDir::new().unwrap();
+        let vars = [("XDG_DATA_HOME", xdg.path().display().to_string())];
+        assert_eq!(detect_data_dir_from(env_from(&vars)), None);
+
+        std::fs::create_dir_all(xdg.path().join("task")).unwrap();
+        assert_eq!(
+            detect_data_dir_from(env_from(&vars)),
+            Some(xdg.path().join("task"))
+        );
+    }
+
+    #[test]
+    fn test_detect_data_dir_ignores_empty_vars() {
+        let home = TempDir::new().unwrap();
+        std::fs::create_dir_all(home.path().join(".local/share/task")).unwrap();
+        let vars = [
+            ("TASKWARRIOR_DATA_DIR", String::new()),
+            ("XDG_DATA_HOME", String::new()),
+            ("HOME", home.path().display().to_string()),
+        ];
+
+        assert_eq!(
+            detect_data_dir_from(env_from(&vars)),
+            Some(home.path().join(".local/share/task"))
+        );
+    }
+
+    // ── add_task ──────────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_add_task_basic() {
+        let (_dir, server) = test_server();
+        let result = server
+            .add_task(Parameters(AddTaskRequest {
+                description: "Write the docs".to_string(),
+                project: "myproject".to_string(),
+                due: None,
+                tags: None,
+                priority: None,
+                wait: None,
+                scheduled: None,
+            }))
+            .await
+            .unwrap();
+
+        assert!(!result.is_error.unwrap_or(false));
+        assert!(text_of(&result).contains("Created task"));
+    }
+
+    #[tokio::test]
+    async fn test_add_task_returns_uuid() {
+        let (_dir, server) = test_server();
+        let earlier = add_task(&server, "Finish first", "uuid-test").await;
+        let result = server
+            .add_task(Parameters(AddTaskRequest {
+                description: "Track me by uuid".to_string(),
+                project: "uuid-test".to_string(),
+                due: None,
+                tags: None,
+                priority: None,
+                wait: None,
+                scheduled: None,
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&result);
+        let id = created_id(out);
+        let uuid = created_uuid(out);
+        assert_eq!(uuid, uuid_of(&server, &id).await);
+
+        // Completing the earlier task renumbers this one; the UUID still resolves.
+        server
+            .complete_task(Parameters(TaskIdRequest { id: earlier }))
+            .await
+            .unwrap();
+        let info = server
+            .get_task(Parameters(TaskIdRequest { id: uuid }))
+            .await
+            .unwrap();
+        assert!(text_of(&info).contains("Track me by uuid"));
+    }
+
+    #[tokio::test]
+    async fn test_add_task_with_all_fields() {
+        let (_dir, server) = test_server();
+        let result = server
+            .add_task(Parameters(AddTaskRequest {
+                description: "Full featured task".to_string(),
+                project: "myproject".to_string(),
+                due: Some("tomorrow".to_string()),
+                tags: Some(vec!["urgent".to_string(), "review".to_string()]),
+                priority: Some("H".to_string()),
+                wait: None,
+                scheduled: None,
+            }))
+            .await
+            .unwrap();
+
+        assert!(!result.is_error.unwrap_or(false));
+        assert!(text_of(&result).contains("Created task"));
+    }
+
+    #[tokio::test]
+    async fn test_add_task_project_is_stored() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Verify project stored", "stored-proj").await;
+
+        let info = server
+            .get_task(Parameters(TaskIdRequest { id }))
+            .await
+            .unwrap();
+
+        assert!(text_of(&info).contains("stored-proj"));
+    }
+
+    // ── list_tasks ────────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_list_tasks_scoped_to_project() {
+        let (_dir, server) = test_server();
+        add_task(&server, "Task in alpha", "alpha").await;
+        add_task(&server, "Task in beta", "beta").await;
+
+        let result = server
+            .list_tasks(Parameters(ListTasksRequest {
+                project: "alpha".to_string(),
+                filter: None,
+                report: Some("list".to_string()),
+                all_projects: None,
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let out = text_of(&result);
+        assert!(out.contains("Task in alpha"), "alpha task must appear");
+        assert!(
+            !out.contains("Task in beta"),
+            "beta task must not leak into alpha scope"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_tasks_all_projects_override() {
+        let (_dir, server) = test_server();
+        add_task(&server, "Task in alpha", "alpha").await;
+        add_task(&server, "Task in beta", "beta").await;
+
+        let result = server
+            .list_tasks(Parameters(ListTasksRequest {
+                project: "alpha".to_string(),
+                filter: None,
+                report: Some("list".to_string()),
+                all_projects: Some(true),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let out = text_of(&result);
+        assert!(out.contains("Task in alpha"));
+        assert!(
+            out.contains("Task in beta"),
+            "all_projects=true should surface both projects"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_list_tasks_with_filter() {
+        let (_dir, server) = test_server();
+        add_task(&server, "High priority task", "filter-test").await;
+        add_task(&server, "Low priority task", "filter-test").await;
+
+        // Modify the first to H priority so we can filter on it
+        server
+            .modify_task(Parameters(ModifyTaskRequest {
+                id: "1".to_string(),
+                modifications: "priority:H".to_string(),
+            }))
+            .await
+            .unwrap();
+
+        let result = server
+            .list_tasks(Parameters(ListTasksRequest {
+                project: "filter-test".to_string(),
+                filter: Some("priority:H".to_string()),
+                report: Some("list".to_string()),
+                all_projects: None,
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        let out = text_of(&result);
+        assert!(out.contains("High priority task"));
+        assert!(!out.contains("Low priority task"));
+    }
+
+    #[tokio::test]
+    async fn test_list_tasks_due_range() {
+        let (_dir, server) = test_server();
+        for (desc, due) in [
+            ("Overdue report", "today-3d"),
+            ("Due this week", "today+2d"),
+            ("Due next month", "today+30d"),
+        ] {
+            server
+                .add_task(Parameters(AddTaskRequest {
+                    description: desc.to_string(),
+                    project: "due-range".to_string(),
+                    due: Some(due.to_string()),
+                    tags: None,
+                    priority: None,
+                    wait: None,
+                    scheduled: None,
+                }))
+                .await
+                .unwrap();
+        }
+
+        let result = server
+            .list_tasks(Parameters(ListTasksRequest {
+                project: "due-range".to_string(),
+                report: Some("list".to_string()),
+                due_after: Some("yesterday".to_string()),
+                due_before: Some("today+7d".to_string()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&result);
+        assert!(out.contains("Due this week"));
+        assert!(!out.contains("Overdue report"));
+        assert!(!out.contains("Due next month"));
+    }
+
+    #[tokio::test]
+    async fn test_list_tasks_created_range() {
+        let (_dir, server) = test_server();
+        add_task(&server, "Captured early", "entry-range").await;
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let middle = Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        add_task(&server, "Captured late", "entry-range").await;
+
+        let after = server
+            .list_tasks(Parameters(ListTasksRequest {
+                project: "entry-range".to_string(),
+                report: Some("list".to_string()),
+                created_after: Some(middle.clone()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&after);
+        assert!(out.contains("Captured late"));
+        assert!(!out.contains("Captured early"));
+
+        let before = server
+            .list_tasks(Parameters(ListTasksRequest {
+                project: "entry-range".to_string(),
+                report: Some("list".to_string()),
+                created_before: Some(middle),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&before);
+        assert!(out.contains("Captured early"));
+        assert!(!out.contains("Captured late"));
+    }
+
+    #[tokio::test]
+    async fn test_list_tasks_modified_after() {
+        let (_dir, server) = test_server();
+        let touched = add_task(&server, "Touched later", "sync").await;
+        add_task(&server, "Left alone", "sync").await;
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        let checkpoint = Local::now().format("%Y-%m-%dT%H:%M:%S").to_string();
+        tokio::time::sleep(Duration::from_millis(1100)).await;
+        server
+            .modify_task(Parameters(ModifyTaskRequest {
+                id: touched,
+                modifications: "priority:H".to_string(),
+            }))
+            .await
+            .unwrap();
+
+        let result = server
+            .list_tasks(Parameters(ListTasksRequest {
+                project: "sync".to_string(),
+                report: Some("list".to_string()),
+                modified_after: Some(checkpoint),
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&result);
+        assert!(out.contains("Touched later"));
+        assert!(!out.contains("Left alone"));
+    }
+
+    #[tokio::test]
+    async fn test_list_tasks_pagination_covers_all_tasks() {
+        let (_dir, server) = test_server();
+        for n in 1..=6 {
+            add_task(&server, &format!("Paged task {n}"), "paging").await;
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        for page in 1..=3 {
+            let result = server
+                .list_tasks(Parameters(ListTasksRequest {
+                    project: "paging".to_string(),
+                    page: Some(page),
+                    page_size: Some(2),
+                    ..Default::default()
+                }))
+                .await
+                .unwrap();
+            let payload: serde_json::Value = serde_json::from_str(text_of(&result)).unwrap();
+            assert_eq!(payload["total"], 6);
+            assert_eq!(payload["pages"], 3);
+            assert_eq!(payload["page"], page);
+            let tasks = payload["tasks"].as_array().unwrap();
+            assert_eq!(tasks.len(), 2);
+            for task in tasks {
+                assert!(seen.insert(field_text(task.get("uuid"))), "task repeated");
+            }
+        }
+        assert_eq!(seen.len(), 6);
+    }
+
+    #[tokio::test]
+    async fn test_list_tasks_rejects_invalid_due_range() {
+        let (_dir, server) = test_server();
+        let err = server
+            .list_tasks(Parameters(ListTasksRequest {
+                project: "due-range".to_string(),
+                due_before: Some("next week".to_string()),
+                ..Default::default()
+            }))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("due_before"));
+    }
+
+    // ── search_tasks ──────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_search_tasks_finds_match() {
+        let (_dir, server) = test_server();
+        add_task(&server, "Fix the flibbertigibbet bug", "search-test").await;
+        add_task(&server, "Unrelated task", "search-test").await;
+
+        let result = server
+            .search_tasks(Parameters(SearchTasksRequest {
+                pattern: "flibbertigibbet".to_string(),
+                project: "search-test".to_string(),
+                filter: None,
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+
+        let out = text_of(&result);
+        assert!(
+            out.contains("flibbertigibbet"),
+            "should find the matching task"
+        );
+        assert!(
+            !out.contains("Unrelated task"),
+            "should not include non-matching tasks"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_tasks_scoped_to_project() {
+        let (_dir, server) = test_server();
+        add_task(&server, "needle in project A", "proj-a").await;
+        add_task(&server, "needle in project B", "proj-b").await;
+
+        let result = server
+            .search_tasks(Parameters(SearchTasksRequest {
+                pattern: "needle".to_string(),
+                project: "proj-a".to_string(),
+                filter: None,
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+
+        let out = text_of(&result);
+        assert!(
+            out.contains("needle in project A"),
+            "should find proj-a task"
+        );
+        assert!(
+            !out.contains("needle in project B"),
+            "should not leak proj-b results"
+        );
+    }
+
+    // ── get_task ──────────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_task_returns_details() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Fetch me by ID", "get-test").await;
+
+        let result = server
+            .get_task(Parameters(TaskIdRequest { id }))
+            .await
+            .unwrap();
+
+        assert!(!result.is_error.unwrap_or(false));
+        assert!(text_of(&result).contains("Fetch me by ID"));
+    }
+
+    // ── modify_task ───────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_modify_task_changes_priority() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Task to modify", "modify-test").await;
+
+        let result = server
+            .modify_task(Parameters(ModifyTaskRequest {
+                id: id.clone(),
+                modifications: "priority:H".to_string(),
+            }))
+            .await
+            .unwrap();
+
+        assert!(!result.is_error.unwrap_or(false));
+
+        let info = server
+            .get_task(Parameters(TaskIdRequest { id }))
+            .await
+            .unwrap();
+
+        // taskwarrior shows "H" or "High" in task info
+        let out = text_of(&info);
+        assert!(out.contains('H'), "priority should be H after modify");
+    }
+
+    #[tokio::test]
+    async fn test_modify_task_adds_tag() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Tag me", "tag-test").await;
+
+        server
+            .modify_task(Parameters(ModifyTaskRequest {
+                id: id.clone(),
+                modifications: "+newtag".to_string(),
+            }))
+            .await
+            .unwrap();
+
+        let info = server
+            .get_task(Parameters(TaskIdRequest { id }))
+            .await
+            .unwrap();
+
+        assert!(text_of(&info).contains("newtag"));
+    }
+
+    // ── complete_task ─────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_complete_task_removes_from_pending() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Task to complete", "done-test").await;
+
+        let result = server
+            .complete_task(Parameters(TaskIdRequest { id }))
+            .await
+            .unwrap();
+
+        assert!(!result.is_error.unwrap_or(false));
+
+        // Should no longer appear in the pending list
+        let list = server
+            .list_tasks(Parameters(ListTasksRequest {
+                project: "done-test".to_string(),
+                filter: None,
+                report: Some("list".to_string()),
+                all_projects: None,
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        assert!(!text_of(&list).contains("Task to complete"));
+    }
+
+    // ── delete_task ───────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_delete_task_removes_from_list() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Task to delete", "delete-test").await;
+
+        let result = server
+            .delete_task(Parameters(TaskIdRequest { id }))
+            .await
+            .unwrap();
+
+        assert!(!result.is_error.unwrap_or(false));
+
+        let list = server
+            .list_tasks(Parameters(ListTasksRequest {
+                project: "delete-test".to_string(),
+                filter: None,
+                report: Some("list".to_string()),
+                all_projects: None,
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+
+        assert!(!text_of(&list).contains("Task to delete"));
+    }
+
+    // ── annotate_task ─────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_annotate_task_appears_in_info() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Task to annotate", "annotate-test").await;
+
+        let result = server
+            .annotate_task(Parameters(AnnotateTaskRequest {
+                id: id.clone(),
+                note: "Important context note xyzzy".to_string(),
+                note_lines: None,
+            }))
+            .await
+            .unwrap();
+
+        assert!(!result.is_error.unwrap_or(false));
+
+        let info = server
+            .get_task(Parameters(TaskIdRequest { id }))
+            .await
+            .unwrap();
+
+        assert!(text_of(&info).contains("Important context note xyzzy"));
+    }
+
+    #[test]
+    fn test_annotation_text_normalization() {
+        assert_eq!(
+            annotation_text("  Line one\\nLine two\nthree ", None),
+            "Line one Line two three"
+        );
+        let lines = vec!["First para".to_string(), "Second para".to_string()];
+        assert_eq!(
+            annotation_text("Summary", Some(&lines)),
+            "Summary\nFirst para\nSecond para"
+        );
+        assert_eq!(annotation_text("", Some(&lines)), "First para\nSecond para");
+        assert_eq!(annotation_text(" \\n ", None), "");
+    }
+
+    #[tokio::test]
+    async fn test_annotate_task_preserves_note_lines() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Multi-line annotate", "annotate-test").await;
+
+        server
+            .annotate_task(Parameters(AnnotateTaskRequest {
+                id: id.clone(),
+                note: String::new(),
+                note_lines: Some(vec!["First line".to_string(), "Second line".to_string()]),
+            }))
+            .await
+            .unwrap();
+
+        let tasks = server.export(&[id]).await.unwrap();
+        assert_eq!(
+            tasks[0]["annotations"][0]["description"],
+            "First line\nSecond line"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_annotate_task_rejects_empty_note() {
+        let (_dir, server) = test_server();
+        let err = server
+            .annotate_task(Parameters(AnnotateTaskRequest {
+                id: "1".to_string(),
+                note: "\\n".to_string(),
+                note_lines: None,
+            }))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("note"));
+    }
+
+    // ── export_tasks_csv ──────────────────────────────────────────────────────
+
+    #[test]
+    fn test_tasks_to_csv_escapes_and_blanks() {
+        let tasks = vec![serde_json::json!({
+            "description": "Say \"hi\", then leave",
+            "tags": ["a", "b"],
+        })];
+        let columns: Vec<String> = ["description", "tags", "due"]
+            .iter()
+            .map(|c| c.to_string())
+            .collect();
+
+        assert_eq!(
+            tasks_to_csv(&tasks, &columns),
+            "description,tags,due\r\n\"Say \"\"hi\"\", then leave\",a b,\r\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_export_tasks_csv_round_trip() {
+        let (_dir, server) = test_server();
+        server
+            .add_task(Parameters(AddTaskRequest {
+                description: "Ship, then celebrate".to_string(),
+                project: "csv-test".to_string(),
+                due: Some("2030-01-15".to_string()),
+                tags: Some(vec!["release".to_string(), "party".to_string()]),
+                priority: Some("H".to_string()),
+                wait: Some("2030-01-01".to_string()),
+                scheduled: Some("2030-01-10".to_string()),
+            }))
+            .await
+            .unwrap();
+
+        let result = server
+            .export_tasks_csv(Parameters(ExportCsvRequest {
+                project: "csv-test".to_string(),
+                filter: None,
+                columns: Some(
+                    ["description", "project", "priority", "due", "tags", "until"]
+                        .iter()
+                        .map(|c| c.to_string())
+                        .collect(),
+                ),
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+
+        let out = text_of(&result);
+        let mut lines = out.lines();
+        assert_eq!(
+            lines.next(),
+            Some("description,project,priority,due,tags,until")
+        );
+        let row = lines.next().expect("missing data row");
+        assert!(row.starts_with("\"Ship, then celebrate\",csv-test,H,2030011"));
+        assert!(row.contains(",release party,") || row.contains(",party release,"));
+        assert!(row.ends_with(','), "absent `until` must be an empty cell");
+        assert_eq!(lines.next(), None);
+    }
+
+    // ── export_tasks_markdown ─────────────────────────────────────────────────
+
+    #[test]
+    fn test_task_to_markdown_variants() {
+        let pending = serde_json::json!({
+            "description": "Write docs",
+            "status": "pending",
+            "priority": "H",
+        });
+        let done = serde_json::json!({ "description": "Ship it", "status": "completed" });
+        let deleted = serde_json::json!({ "description": "Gone", "status": "deleted" });
+
+        assert_eq!(
+            task_to_markdown(&pending, true).as_deref(),
+            Some("- [ ] Write docs (priority: H)")
+        );
+        assert_eq!(
+            task_to_markdown(&pending, false).as_deref(),
+            Some("- [ ] Write docs")
+        );
+        assert_eq!(
+            task_to_markdown(&done, true).as_deref(),
+            Some("- [x] Ship it")
+        );
+        assert_eq!(task_to_markdown(&deleted, true), None);
+    }
+
+    #[tokio::test]
+    async fn test_export_tasks_markdown_checklist() {
+        let (_dir, server) = test_server();
+        add_task(&server, "Open item", "md-test").await;
+        let done_id = add_task(&server, "Finished item", "md-test").await;
+        server
+            .complete_task(Parameters(TaskIdRequest { id: done_id }))
+            .await
+            .unwrap();
+
+        let result = server
+            .export_tasks_markdown(Parameters(ExportMarkdownRequest {
+                project: "md-test".to_string(),
+                filter: Some("( status:pending or status:completed )".to_string()),
+                include_metadata: Some(true),
+            }))
+            .await
+            .unwrap();
+
+        let out = text_of(&result);
+        for line in out.lines() {
+            assert!(
+                line.starts_with("- [ ] ") || line.starts_with("- [x] "),
+                "not a Markdown checklist item: {line:?}"
+            );
+        }
+        assert!(out.contains("- [ ] Open item"));
+        assert!(out.contains("- [x] Finished item"));
+    }
+
+    // ── import_tasks_from_markdown ────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_checklist_item() {
+        assert_eq!(
+            parse_checklist_item("- [ ] Plain item"),
+            Some(ChecklistItem {
+                description: "Plain item".to_string(),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            parse_checklist_item("  - [ ] Ship (due: 2025-06-15 14:30, priority: H)"),
+            Some(ChecklistItem {
+                description: "Ship".to_string(),
+                due: Some("2025-06-15T14:30".to_string()),
+                priority: Some("H".to_string()),
+                ..Default::default()
+            })
+        );
+        assert_eq!(
+            parse_checklist_item("- [ ] Call Bob (again)").map(|i| i.description),
+            Some("Call Bob (again)".to_string())
+        );
+        assert_eq!(parse_checklist_item("- [x] Already done"), None);
+        assert_eq!(parse_checklist_item("## Heading"), None);
+        assert_eq!(parse_checklist_item("- [ ]"), None);
+    }
+
+    #[tokio::test]
+    async fn test_import_tasks_from_markdown_five_items() {
+        let (_dir, server) = test_server();
+        let markdown = "\
+# Launch checklist
+- [ ] Draft announcement
+- [ ] Record demo (due:tomorrow)
+- [ ] Update changelog (priority: H)
+- [x] Pick a date
+this line is not a task
+- [ ] Tag release (due: tomorrow, priority: M)
+- [ ] Tweet about it
+";
+
+        let result = server
+            .import_tasks_from_markdown(Parameters(ImportMarkdownRequest {
+                markdown: markdown.to_string(),
+                project: "md-import".to_string(),
+                default_priority: Some("L".to_string()),
+            }))
+            .await
+            .unwrap();
+
+        let out = text_of(&result);
+        assert!(out.starts_with("Created 5 task(s)"), "unexpected: {out}");
+        assert!(out.contains("3 line(s) skipped"));
+
+        let csv = server
+            .export_tasks_csv(Parameters(ExportCsvRequest {
+                project: "md-import".to_string(),
+                filter: None,
+                columns: Some(vec!["description".to_string(), "priority".to_string()]),
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+        let csv = text_of(&csv);
+        assert!(csv.contains("Update changelog,H"));
+        assert!(csv.contains("Tag release,M"));
+        assert!(csv.contains("Tweet about it,L"), "default priority applies");
+    }
+
+    // ── export_tasks_ical ─────────────────────────────────────────────────────
+
+    #[test]
+    fn test_tasks_to_ical_escapes_and_folds() {
+        let tasks = vec![serde_json::json!({
+            "uuid": "0e1f6f4c-0000-0000-0000-000000000000",
+            "description": format!("Buy milk, eggs; {}", "x".repeat(80)),
+            "status": "completed",
+            "priority": "M",
+            "end": "20250601T120000Z",
+        })];
+
+        let ics = tasks_to_ical(&tasks);
+        assert!(ics.contains(r"SUMMARY:Buy milk\, eggs\; xxx"));
+        assert!(ics.contains("PRIORITY:5\r\n"));
+        assert!(ics.contains("STATUS:COMPLETED\r\n"));
+        assert!(ics.contains("COMPLETED:20250601T120000Z\r\n"));
+        for line in ics.split("\r\n") {
+            assert!(line.len() <= 75, "unfolded line: {line:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_export_tasks_ical_contains_vtodo() {
+        let (_dir, server) = test_server();
+        server
+            .add_task(Parameters(AddTaskRequest {
+                description: "Dentist appointment".to_string(),
+                project: "ical-test".to_string(),
+                due: Some("tomorrow".to_string()),
+                tags: None,
+                priority: Some("H".to_string()),
+                wait: None,
+                scheduled: None,
+            }))
+            .await
+            .unwrap();
+
+        let result = server
+            .export_tasks_ical(Parameters(ExportIcalRequest {
+                project: "ical-test".to_string(),
+                filter: None,
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+
+        let out = text_of(&result);
+        assert!(out.starts_with("BEGIN:VCALENDAR\r\n"));
+        assert!(out.contains("BEGIN:VTODO"));
+        assert!(out.contains("SUMMARY:Dentist appointment"));
+        assert!(out.contains("\r\nDUE:"));
+        assert!(out.contains("PRIORITY:1"));
+        assert!(out.contains("STATUS:NEEDS-ACTION"));
+        assert!(out.trim_end().ends_with("END:VCALENDAR"));
+    }
+
+    // ── import_tasks_from_json_array ──────────────────────────────────────────
+
+    #[test]
+    fn test_prepare_import_validates_input() {
+        let err = prepare_import(r#"{"description": "not an array"}"#, None).unwrap_err();
+        assert!(err.message.contains("JSON array"));
+        assert!(prepare_import("not json", None).is_err());
+
+        let (tasks, skipped) = prepare_import(
+            r#"[{"description": "ok", "project": "old"}, 42, {"project": "x"}]"#,
+            Some("new"),
+        )
+        .unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0]["project"], "new");
+        assert_eq!(skipped.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_import_tasks_from_json_array_three_elements() {
+        let (_dir, server) = test_server();
+        let json = r#"[
+            {"description": "Imported one", "project": "ignored"},
+            {"description": "Imported two", "priority": "H"},
+            {"description": "Imported three", "tags": ["bulk"]}
+        ]"#;
+
+        let result = server
+            .import_tasks_from_json_array(Parameters(ImportJsonArrayRequest {
+                json_array: json.to_string(),
+                project_override: Some("json-import".to_string()),
+            }))
+            .await
+            .unwrap();
+
+        assert!(
+            text_of(&result).starts_with("Imported 3 task(s), skipped 0."),
+            "unexpected: {}",
+            text_of(&result)
+        );
+
+        let list = server
+            .list_tasks(Parameters(ListTasksRequest {
+                project: "json-import".to_string(),
+                filter: None,
+                report: Some("list".to_string()),
+                all_projects: None,
+                ..Default::default()
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&list);
+        assert!(out.contains("Imported one"));
+        assert!(out.contains("Imported two"));
+        assert!(out.contains("Imported three"));
+    }
+
+    // ── get_completed_tasks ───────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_completed_tasks_in_date_range() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Finished today", "completed-test").await;
+        add_task(&server, "Still pending", "completed-test").await;
+        server
+            .complete_task(Parameters(TaskIdRequest { id }))
+            .await
+            .unwrap();
+
+        let in_range = server
+            .get_completed_tasks(Parameters(CompletedTasksRequest {
+                project: "completed-test".to_string(),
+                since: Some("today".to_string()),
+                until_date: Some("tomorrow".to_string()),
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&in_range);
+        assert!(out.contains("Finished today"));
+        assert!(!out.contains("Still pending"));
+
+        let out_of_range = server
+            .get_completed_tasks(Parameters(CompletedTasksRequest {
+                project: "completed-test".to_string(),
+                since: None,
+                until_date: Some("yesterday".to_string()),
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+        assert!(!text_of(&out_of_range).contains("Finished today"));
+    }
+
+    #[tokio::test]
+    async fn test_get_completed_tasks_validates_dates() {
+        let (_dir, server) = test_server();
+        let err = server
+            .get_completed_tasks(Parameters(CompletedTasksRequest {
+                project: "completed-test".to_string(),
+                since: Some("last week".to_string()),
+                until_date: None,
+                all_projects: None,
+            }))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("since"), "{}", err.message);
+    }
+
+    // ── get_recurring_tasks ───────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_recurring_tasks_lists_templates_only() {
+        let (_dir, server) = test_server();
+        server
+            .run(&[
+                "add",
+                "Weekly sync",
+                "project:recur-test",
+                "due:tomorrow",
+                "recur:weekly",
+            ])
+            .await
+            .unwrap();
+        add_task(&server, "One-off chore", "recur-test").await;
+
+        let result = server
+            .get_recurring_tasks(Parameters(RecurringTasksRequest {
+                project: "recur-test".to_string(),
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&result);
+        assert!(out.contains("Weekly sync"));
+        assert!(!out.contains("One-off chore"));
+
+        // Pending reports only ever see spawned instances (which carry a `parent`).
+        let pending = server
+            .export(&[
+                "project:recur-test".to_string(),
+                "status:pending".to_string(),
+            ])
+            .await
+            .unwrap();
+        for task in pending.iter().filter(|t| t["description"] == "Weekly sync") {
+            assert!(task.get("parent").is_some(), "template leaked: {task}");
+        }
+    }
+
+    // ── edit_annotation ───────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_edit_annotation_replaces_text() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Annotated task", "edit-annotation").await;
+        annotate(&server, &id, "Waiting on review from Sam").await;
+        annotate(&server, &id, "Unrelated note").await;
+
+        server
+            .edit_annotation(Parameters(EditAnnotationRequest {
+                id: id.clone(),
+                old_pattern: "review from Sam".to_string(),
+                new_text: "Review approved by Sam".to_string(),
+            }))
+            .await
+            .unwrap();
+
+        let tasks = server.export(&[id]).await.unwrap();
+        let notes = annotation_texts(&tasks[0]);
+        assert_eq!(notes.len(), 2);
+        assert!(notes.contains(&"Review approved by Sam".to_string()));
+        assert!(notes.contains(&"Unrelated note".to_string()));
+        assert!(!notes.iter().any(|n| n.contains("Waiting on review")));
+    }
+
+    #[tokio::test]
+    async fn test_edit_annotation_no_match_errors() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "No notes here", "edit-annotation").await;
+        annotate(&server, &id, "Something else").await;
+
+        let err = server
+            .edit_annotation(Parameters(EditAnnotationRequest {
+                id,
+                old_pattern: "missing".to_string(),
+                new_text: "whatever".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("No annotation"));
+    }
+
+    // ── suggest_due_date ──────────────────────────────────────────────────────
+
+    fn top_suggestion(description: &str, priority: Option<&str>) -> Option<String> {
+        suggest_due_dates(description, priority)
+            .into_iter()
+            .next()
+            .map(|(token, _, _)| token)
+    }
+
+    #[test]
+    fn test_suggest_due_dates_keywords() {
+        assert_eq!(
+            top_suggestion("finish report by friday", None).as_deref(),
+            Some("friday")
+        );
+        assert_eq!(
+            top_suggestion("wrap up the migration this week", None).as_deref(),
+            Some("eow")
+        );
+        assert_eq!(
+            top_suggestion("Invoices due end of month", None).as_deref(),
+            Some("eom")
+        );
+        assert_eq!(
+            top_suggestion("follow up in 3 days", None).as_deref(),
+            Some("today+3d")
+        );
+        assert_eq!(
+            top_suggestion("urgent: ship hotfix on 2030-02-01", None).as_deref(),
+            Some("2030-02-01")
+        );
+        // "today" must not match inside other words
+        assert_eq!(top_suggestion("update todays notes", None), None);
+    }
+
+    #[test]
+    fn test_suggest_due_dates_ranking_and_fallback() {
+        let ranked = suggest_due_dates("urgent, needs to be done tomorrow", None);
+        assert_eq!(ranked[0].0, "tomorrow");
+        assert!(ranked.windows(2).all(|w| w[0].1 >= w[1].1));
+
+        assert_eq!(
+            top_suggestion("refactor parser", Some("H")).as_deref(),
+            Some("tomorrow")
+        );
+        assert!(suggest_due_dates("refactor parser", None).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_suggest_due_date_tool_output() {
+        let (_dir, server) = test_server();
+        let result = server
+            .suggest_due_date(Parameters(SuggestDueDateRequest {
+                description: "finish report by friday".to_string(),
+                priority: None,
+            }))
+            .await
+            .unwrap();
+        assert!(text_of(&result).starts_with("friday (confidence"));
+    }
+
+    // ── get_next_actions ──────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_next_actions_validates_excluded_projects() {
+        let (_dir, server) = test_server();
+        let err = server
+            .get_next_actions(Parameters(NextActionsRequest {
+                limit: None,
+                exclude_projects: Some(vec!["Home".into(), "x or +y".into()]),
+            }))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("exclude_projects"), "{}", err.message);
+    }
+
+    #[tokio::test]
+    async fn test_get_next_actions_orders_by_urgency_across_projects() {
+        let (_dir, server) = test_server();
+        add_task(&server, "Low urgency chore", "home").await;
+        server
+            .add_task(Parameters(AddTaskRequest {
+                description: "Critical outage fix".to_string(),
+                project: "work".to_string(),
+                due: Some("today".to_string()),
+                tags: None,
+                priority: Some("H".to_string()),
+                wait: None,
+                scheduled: None,
+            }))
+            .await
+            .unwrap();
+
+        let result = server
+            .get_next_actions(Parameters(NextActionsRequest {
+                limit: Some(5),
+                exclude_projects: None,
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&result);
+        let critical = out.find("Critical outage fix").expect("work task missing");
+        let chore = out.find("Low urgency chore").expect("home task missing");
+        assert!(critical < chore, "higher urgency must come first:\n{out}");
+
+        let excluded = server
+            .get_next_actions(Parameters(NextActionsRequest {
+                limit: None,
+                exclude_projects: Some(vec!["work".to_string()]),
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&excluded);
+        assert!(out.contains("Low urgency chore"));
+        assert!(!out.contains("Critical outage fix"));
+    }
+
+    // ── get_focus_tasks ───────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_focus_tasks_excludes_blocked() {
+        let (_dir, server) = test_server();
+        let blocker = add_task(&server, "Design schema", "focus-test").await;
+        let blocked = add_task(&server, "Write migrations", "focus-test").await;
+        add_task(&server, "Review open PRs", "focus-test").await;
+        server
+            .modify_task(Parameters(ModifyTaskRequest {
+                id: blocked,
+                modifications: format!("depends:{blocker}"),
+            }))
+            .await
+            .unwrap();
+
+        let result = server
+            .get_focus_tasks(Parameters(GetFocusRequest {
+                project: "focus-test".to_string(),
+                limit: None,
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&result);
+        assert!(out.contains("Design schema"));
+        assert!(out.contains("Review open PRs"));
+        assert!(
+            !out.contains("Write migrations"),
+            "blocked task surfaced:\n{out}"
+        );
+    }
+
+    // ── set_task_wait / clear_wait ────────────────────────────────────────────
+
+    #[test]
+    fn test_validate_date() {
+        for ok in [
+            "tomorrow",
+            "eow",
+            "2025-06-15",
+            "2025-06-15T14:30",
+            "today+3d",
+            "eow-1d",
+        ] {
+            assert!(validate_date("due", ok).is_ok(), "{ok} should be valid");
+        }
+        for bad in ["", "next week", "+tag", "-1d", "due:friday;rm"] {
+            assert!(
+                validate_date("due", bad).is_err(),
+                "{bad} should be invalid"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_set_task_wait_hides_until_cleared() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Deferred task", "wait-test").await;
+        let uuid = uuid_of(&server, &id).await;
+
+        server
+            .set_task_wait(Parameters(SetWaitRequest {
+                id: uuid.clone(),
+                wait_until: "tomorrow".to_string(),
+            }))
+            .await
+            .unwrap();
+        assert!(!list_text(&server, "wait-test")
+            .await
+            .contains("Deferred task"));
+
+        server
+            .clear_wait(Parameters(TaskIdRequest { id: uuid }))
+            .await
+            .unwrap();
+        assert!(list_text(&server, "wait-test")
+            .await
+            .contains("Deferred task"));
+    }
+
+    #[tokio::test]
+    async fn test_set_task_wait_rejects_bad_date() {
+        let (_dir, server) = test_server();
+        let err = server
+            .set_task_wait(Parameters(SetWaitRequest {
+                id: "1".to_string(),
+                wait_until: "next week".to_string(),
+            }))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("wait_until"));
+    }
+
+    // ── get_task_count_by_project ─────────────────────────────────────────────
+
+    #[test]
+    fn test_count_by_and_table() {
+        let tasks = vec![
+            serde_json::json!({"project": "b"}),
+            serde_json::json!({"project": "a"}),
+            serde_json::json!({"project": "b"}),
+            serde_json::json!({"project": "c"}),
+        ];
+        let rows = count_by(&tasks, |t| vec![t["project"].as_str().unwrap().to_string()]);
+        assert_eq!(
+            rows,
+            vec![
+                ("b".to_string(), 2),
+                ("a".to_string(), 1),
+                ("c".to_string(), 1)
+            ]
+        );
+        assert_eq!(
+            count_table("Project", &rows),
+            "Project | Count\n--------|------\nb       | 2\na       | 1\nc       | 1"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_task_count_by_project() {
+        let (_dir, server) = test_server();
+        for _ in 0..3 {
+            add_task(&server, "alpha task", "alpha").await;
+        }
+        for _ in 0..2 {
+            add_task(&server, "beta task", "beta").await;
+        }
+        add_task(&server, "gamma task", "gamma").await;
+
+        let result = server.get_task_count_by_project().await.unwrap();
+        let out = text_of(&result);
+        let rows: Vec<&str> = out.lines().skip(2).take(3).collect();
+        assert_eq!(rows, vec!["alpha   | 3", "beta    | 2", "gamma   | 1"]);
+        assert!(out.ends_with("3 project(s), 6 task(s)"));
+    }
+
+    // ── get_task_count_by_tag ─────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_task_count_by_tag() {
+        let (_dir, server) = test_server();
+        for (desc, tags) in [
+            ("one", vec!["bug"]),
+            ("two", vec!["bug", "ui"]),
+            ("three", vec!["bug"]),
+            ("four", vec!["ui"]),
+        ] {
+            server
+                .add_task(Parameters(AddTaskRequest {
+                    description: desc.to_string(),
+                    project: "tag-count".to_string(),
+                    due: None,
+                    tags: Some(tags.into_iter().map(str::to_string).collect()),
+                    priority: None,
+                    wait: None,
+                    scheduled: None,
+                }))
+                .await
+                .unwrap();
+        }
+        add_task(&server, "elsewhere", "other").await;
+
+        let result = server
+            .get_task_count_by_tag(Parameters(CountByTagRequest {
+                project: Some("tag-count".to_string()),
+            }))
+            .await
+            .unwrap();
+        let rows: Vec<&str> = text_of(&result).lines().skip(2).collect();
+        assert_eq!(rows, vec!["bug | 3", "ui  | 2"]);
+    }
+
+    // ── get_task_count_by_priority ────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_task_count_by_priority() {
+        let (_dir, server) = test_server();
+        for priority in ["H", "H", "M", "L", "L", "L"] {
+            server
+                .add_task(Parameters(AddTaskRequest {
+                    description: format!("priority {priority}"),
+                    project: "prio-count".to_string(),
+                    due: None,
+                    tags: None,
+                    priority: Some(priority.to_string()),
+                    wait: None,
+                    scheduled: None,
+                }))
+                .await
+                .unwrap();
+        }
+        add_task(&server, "no priority", "prio-count").await;
+        add_task(&server, "other project", "elsewhere").await;
+
+        let result = server
+            .get_task_count_by_priority(Parameters(CountByPriorityRequest {
+                project: "prio-count".to_string(),
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+        assert_eq!(text_of(&result), "H: 2, M: 1, L: 3, None: 1");
+    }
+
+    // ── get_weekly_review ─────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_weekly_review_counts() {
+        let (_dir, server) = test_server();
+        let done = add_task(&server, "Done this week", "review-test").await;
+        add_task(&server, "Still open", "review-test").await;
+        server
+            .complete_task(Parameters(TaskIdRequest { id: done }))
+            .await
+            .unwrap();
+
+        let result = server
+            .get_weekly_review(Parameters(WeeklyReviewRequest {
+                project: "review-test".to_string(),
+            }))
+            .await
+            .unwrap();
+
+        let out = text_of(&result);
+        let counts: Vec<usize> = out
+            .lines()
+            .skip(1)
+            .map(|line| {
+                line.rsplit(' ')
+                    .next()
+                    .and_then(|n| n.parse().ok())
+                    .unwrap_or_else(|| panic!("not a count: {line:?}"))
+            })
+            .collect();
+        assert_eq!(counts.len(), 5, "{out}");
+        assert_eq!(counts, vec![1, 2, 0, 0, 1]);
+    }
+
+    // ── get_sprint_summary ────────────────────────────────────────────────────
+
+    #[test]
+    fn test_numeric_field_accepts_numbers_and_strings() {
+        let task = serde_json::json!({"a": 3, "b": "2.5", "c": "n/a", "d": [1]});
+        assert_eq!(numeric_field(&task, "a"), Some(3.0));
+        assert_eq!(numeric_field(&task, "b"), Some(2.5));
+        assert_eq!(numeric_field(&task, "c"), None);
+        assert_eq!(numeric_field(&task, "d"), None);
+        assert_eq!(numeric_field(&task, "missing"), None);
+    }
+
+    #[tokio::test]
+    async fn test_get_sprint_summary_groups_by_status() {
+        let (_dir, server) = test_server();
+        let mut uuids = Vec::new();
+        for (desc, points) in [
+            ("Login page", "3"),
+            ("Signup page", "5"),
+            ("Password reset", "2"),
+        ] {
+            let out = server
+                .run(&[
+                    "rc.uda.story_points.type=numeric",
+                    "add",
+                    desc,
+                    "project:sprint-test",
+                    "+sprint-1",
+                    &format!("story_points:{points}"),
+                ])
+                .await
+                .unwrap();
+            uuids.push(uuid_of(&server, &created_id(&out)).await);
+        }
+        add_task(&server, "Not in sprint", "sprint-test").await;
+        for uuid in &uuids[..2] {
+            server
+                .complete_task(Parameters(TaskIdRequest { id: uuid.clone() }))
+                .await
+                .unwrap();
+        }
+
+        let result = server
+            .get_sprint_summary(Parameters(SprintSummaryRequest {
+                project: "sprint-test".to_string(),
+                sprint_tag: "sprint-1".to_string(),
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&result);
+        assert!(
+            out.contains("Tasks: 3 total, 2 completed, 1 incomplete (67% done)"),
+            "{out}"
+        );
+        assert!(
+            out.contains("Story points: 10 total, 8 completed, 2 remaining"),
+            "{out}"
+        );
+        assert!(out.contains("Password reset"));
+        assert!(!out.contains("Not in sprint"));
+    }
+
+    // ── get_recently_completed ────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_recently_completed_last_day() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Shipped the fix", "recent").await;
+        add_task(&server, "Not done yet", "recent").await;
+        server
+            .complete_task(Parameters(TaskIdRequest { id }))
+            .await
+            .unwrap();
+
+        let result = server
+            .get_recently_completed(Parameters(RecentlyCompletedRequest { hours: None }))
+            .await
+            .unwrap();
+        let out = text_of(&result);
+        assert!(out.contains("Shipped the fix"));
+        assert!(!out.contains("Not done yet"));
+    }
+
+    // ── get_recently_added ────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_recently_added_excludes_older_tasks() {
+        let (_dir, server) = test_server();
+        server
+            .import_tasks_from_json_array(Parameters(ImportJsonArrayRequest {
+                json_array:
+                    r#"[{"description": "Captured years ago", "entry": "20200101T090000Z"}]"#
+                        .to_string(),
+                project_override: Some("capture".to_string()),
+            }))
+            .await
+            .unwrap();
+        add_task(&server, "Captured just now", "capture").await;
+
+        let result = server
+            .get_recently_added(Parameters(RecentlyAddedRequest { hours: Some(2) }))
+            .await
+            .unwrap();
+        let out = text_of(&result);
+        assert!(out.contains("Captured just now"));
+        assert!(!out.contains("Captured years ago"));
+    }
+
+    // ── get_task_changelog ────────────────────────────────────────────────────
+
+    #[test]
+    fn test_task_changelog_parses_undo_blocks() {
+        let undo = "time 1717243200\n\
+            new [description:\"Say &dquot;hi&dquot;\" entry:\"1717243200\" status:\"pending\" uuid:\"u-1\"]\n\
+            ---\n\
+            time 1717243260\n\
+            new [description:\"Other\" status:\"pending\" uuid:\"u-2\"]\n\
+            ---\n\
+            time 1717243320\n\
+            old [description:\"Say &dquot;hi&dquot;\" entry:\"1717243200\" status:\"pending\" uuid:\"u-1\"]\n\
+            new [description:\"Say &dquot;hi&dquot;\" entry:\"1717243200\" priority:\"H\" status:\"pending\" uuid:\"u-1\"]\n\
+            ---\n";
+        let lines = task_changelog(undo, "u-1");
+        assert_eq!(lines.len(), 2, "{lines:?}");
+        assert!(lines[0].ends_with("— created: Say \"hi\""));
+        assert!(lines[1].ends_with("— priority set to H"));
+    }
+
+    #[tokio::test]
+    async fn test_get_task_changelog_tracks_modifications() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Evolving task", "history").await;
+        for modifications in ["priority:M", "priority:H", "+urgent"] {
+            server
+                .modify_task(Parameters(ModifyTaskRequest {
+                    id: id.clone(),
+                    modifications: modifications.to_string(),
+                }))
+                .await
+                .unwrap();
+        }
+
+        let result = server
+            .get_task_changelog(Parameters(TaskChangelogRequest { id }))
+            .await
+            .unwrap();
+        let out = text_of(&result);
+        assert!(out.contains("created: Evolving task"), "{out}");
+        assert!(out.contains("priority set to M"), "{out}");
+        assert!(out.contains("priority changed from M to H"), "{out}");
+        assert!(out.contains("tags set to urgent"), "{out}");
+    }
+
+    // ── search_tasks_by_due_range ─────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_search_tasks_by_due_range() {
+        let (_dir, server) = test_server();
+        for (desc, due) in [
+            ("Due last week", "today-7d"),
+            ("Due in three days", "today+3d"),
+            ("Due in a month", "today+30d"),
+        ] {
+            server
+                .add_task(Parameters(AddTaskRequest {
+                    description: desc.to_string(),
+                    project: "window".to_string(),
+                    due: Some(due.to_string()),
+                    tags: None,
+                    priority: None,
+                    wait: None,
+                    scheduled: None,
+                }))
+                .await
+                .unwrap();
+        }
+
+        let result = server
+            .search_tasks_by_due_range(Parameters(DueRangeSearchRequest {
+                project: "window".to_string(),
+                due_after: "today".to_string(),
+                due_before: "today+14d".to_string(),
+                filter: None,
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&result);
+        assert!(out.contains("Due in three days"));
+        assert!(!out.contains("Due last week"));
+        assert!(!out.contains("Due in a month"));
+    }
+
+    // ── get_task_dependencies_recursive ───────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_task_dependencies_recursive_chain() {
+        let (_dir, server) = test_server();
+        let mut uuids = Vec::new();
+        for desc in ["Ship release", "Pass QA", "Write code"] {
+            let id = add_task(&server, desc, "deps").await;
+            uuids.push(uuid_of(&server, &id).await);
+        }
+        for pair in uuids.windows(2) {
+            server
+                .modify_task(Parameters(ModifyTaskRequest {
+                    id: pair[0].clone(),
+                    modifications: format!("depends:{}", pair[1]),
+                }))
+                .await
+                .unwrap();
+        }
+
+        let result = server
+            .get_task_dependencies_recursive(Parameters(RecursiveDepsRequest {
+                id: uuids[0].clone(),
+                max_depth: None,
+            }))
+            .await
+            .unwrap();
+        let lines: Vec<&str> = text_of(&result).lines().collect();
+        assert_eq!(lines.len(), 3, "{lines:?}");
+        assert!(lines[0].starts_with("- Ship release"));
+        assert!(lines[1].starts_with("  - Pass QA"));
+        assert!(lines[2].starts_with("    - Write code"));
+
+        let shallow = server
+            .get_task_dependencies_recursive(Parameters(RecursiveDepsRequest {
+                id: uuids[0].clone(),
+                max_depth: Some(1),
+            }))
+            .await
+            .unwrap();
+        assert!(!text_of(&shallow).contains("Write code"));
+    }
+
+    // ── cleanup_orphan_dependencies ───────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_cleanup_orphan_dependencies_after_delete() {
+        let (_dir, server) = test_server();
+        let blocker = add_task(&server, "Abandoned blocker", "orphans").await;
+        let blocker = uuid_of(&server, &blocker).await;
+        let blocked = add_task(&server, "Stuck task", "orphans").await;
+        let blocked = uuid_of(&server, &blocked).await;
+        server
+            .modify_task(Parameters(ModifyTaskRequest {
+                id: blocked.clone(),
+                modifications: format!("depends:{blocker}"),
+            }))
+            .await
+            .unwrap();
+        server
+            .delete_task(Parameters(TaskIdRequest { id: blocker }))
+            .await
+            .unwrap();
+
+        let result = server.cleanup_orphan_dependencies().await.unwrap();
+        assert!(
+            text_of(&result).starts_with("Cleaned 1 task(s), removed 1 orphaned dependency."),
+            "{}",
+            text_of(&result)
+        );
+        let task = server.export(&[blocked]).await.unwrap();
+        assert!(depends_of(&task[0]).is_empty());
+
+        let again = server.cleanup_orphan_dependencies().await.unwrap();
+        assert!(text_of(&again).starts_with("Cleaned 0 task(s)"));
+    }
+
+    // ── task_search_and_replace_description ───────────────────────────────────
+
+    #[tokio::test]
+    async fn test_task_search_and_replace_description() {
+        let (_dir, server) = test_server();
+        for desc in [
+            "Draft: spec",
+            "Draft: API docs",
+            "Draft: test plan",
+            "Review budget",
+        ] {
+            add_task(&server, desc, "rename").await;
+        }
+        let request = |dry_run| SearchReplaceRequest {
+            project: "rename".to_string(),
+            pattern: "Draft:".to_string(),
+            replacement: "Final:".to_string(),
+            all_projects: None,
+            dry_run: Some(dry_run),
+        };
+
+        let preview = server
+            .task_search_and_replace_description(Parameters(request(true)))
+            .await
+            .unwrap();
+        assert!(text_of(&preview).starts_with("Would update 3 task(s):"));
+        assert!(text_of(&preview).contains("- Draft: API docs → Final: API docs"));
+        assert!(!list_text(&server, "rename").await.contains("Final:"));
+
+        let result = server
+            .task_search_and_replace_description(Parameters(request(false)))
+            .await
+            .unwrap();
+        assert!(text_of(&result).starts_with("Updated 3 task(s):"));
+        let list = list_text(&server, "rename").await;
+        assert!(list.contains("Final: spec"));
+        assert!(list.contains("Final: API docs"));
+        assert!(list.contains("Final: test plan"));
+        assert!(list.contains("Review budget"));
+        assert!(!list.contains("Draft:"));
+    }
+
+    // ── rename_project ────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_validate_project_name() {
+        for ok in ["Work", "Work.Backend", "side-project_2", "Küche"] {
+            assert!(
+                validate_project_name("project", ok).is_ok(),
+                "{ok} should be valid"
+            );
+        }
+        for bad in [
+            "",
+            "two words",
+            "Work.",
+            ".Work",
+            "a..b",
+            "x:y",
+            "-Work",
+            "(Work)",
+        ] {
+            assert!(
+                validate_project_name("project", bad).is_err(),
+                "{bad} should be invalid"
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_rename_project_moves_all_tasks() {
+        let (_dir, server) = test_server();
+        for desc in ["First", "Second", "Third"] {
+            add_task(&server, desc, "OldName").await;
+        }
+        let waiting = add_task(&server, "Snoozed", "OldName").await;
+        server
+            .set_task_wait(Parameters(SetWaitRequest {
+                id: waiting,
+                wait_until: "tomorrow".to_string(),
+            }))
+            .await
+            .unwrap();
+
+        let result = server
+            .rename_project(Parameters(RenameProjectRequest {
+                old_project: "OldName".to_string(),
+                new_project: "NewName".to_string(),
+            }))
+            .await
+            .unwrap();
+        assert!(
+            text_of(&result).contains("4 task(s) moved (3 pending, 1 waiting, 0 recurring)"),
+            "{}",
+            text_of(&result)
+        );
+        let old = scoped_filter("OldName", None, None);
+        assert_eq!(server.count(&old).await.unwrap(), 0);
+        let new = scoped_filter(
+            "NewName",
+            Some("( status:pending or status:waiting )"),
+            None,
+        );
+        assert_eq!(server.count(&new).await.unwrap(), 4);
+    }
+
+    // ── archive_project / unarchive_project ───────────────────────────────────
+
+    #[tokio::test]
+    async fn test_archive_project_round_trip() {
+        let (_dir, server) = test_server();
+        for desc in ["Plan offsite", "Book venue"] {
+            add_task(&server, desc, "paused").await;
+        }
+
+        let result = server
+            .archive_project(Parameters(ArchiveProjectRequest {
+                project: "paused".to_string(),
+                wait_until: None,
+            }))
+            .await
+            .unwrap();
+        assert_eq!(
+            text_of(&result),
+            "Archived project paused: 2 task(s) hidden until 2099-12-31."
+        );
+        assert!(!list_text(&server, "paused").await.contains("Plan offsite"));
+        let waiting = scoped_filter("paused", Some("+WAITING"), None);
+        assert_eq!(server.count(&waiting).await.unwrap(), 2);
+
+        let result = server
+            .unarchive_project(Parameters(UnarchiveProjectRequest {
+                project: "paused".to_string(),
+            }))
+            .await
+            .unwrap();
+        assert_eq!(
+            text_of(&result),
+            "Unarchived project paused: 2 task(s) visible again."
+        );
+        let list = list_text(&server, "paused").await;
+        assert!(list.contains("Plan offsite"), "{list}");
+        assert!(list.contains("Book venue"), "{list}");
+        assert_eq!(server.count(&waiting).await.unwrap(), 0);
+    }
+
+    // ── get_tag_usage_report / get_tag_details ────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_tag_usage_report_counts_tags() {
+        let (_dir, server) = test_server();
+        for desc in ["Tagged one", "Tagged two", "Tagged three"] {
+            server
+                .add_task(Parameters(AddTaskRequest {
+                    description: desc.to_string(),
+                    project: "tags".to_string(),
+                    due: None,
+                    tags: Some(vec!["foo".to_string()]),
+                    priority: None,
+                    wait: None,
+                    scheduled: None,
+                }))
+                .await
+                .unwrap();
+        }
+        add_task(&server, "Untagged", "tags").await;
+
+        let report = server.get_tag_usage_report().await.unwrap();
+        let row = text_of(&report)
+            .lines()
+            .find(|line| line.split_whitespace().next() == Some("foo"))
+            .expect("foo missing from tag report");
+        assert_eq!(row.split_whitespace().last(), Some("3"));
+
+        let details = server
+            .get_tag_details(Parameters(TagDetailsRequest {
+                tag: "+foo".to_string(),
+            }))
+            .await
+            .unwrap();
+        let out = text_of(&details);
+        assert!(out.contains("Tagged two"));
+        assert!(!out.contains("Untagged"));
+    }
+
+    // ── normalize_tags ────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_normalize_tags_lowercases_mixed_case() {
+        let (_dir, server) = test_server();
+        server
+            .add_task(Parameters(AddTaskRequest {
+                description: "Shouty tags".to_string(),
+                project: "tag-case".to_string(),
+                due: None,
+                tags: Some(vec!["Urgent".to_string(), "review".to_string()]),
+                priority: None,
+                wait: None,
+                scheduled: None,
+            }))
+            .await
+            .unwrap();
+
+        let result = server
+            .normalize_tags(Parameters(NormalizeTagsRequest {
+                project: "tag-case".to_string(),
+            }))
+            .await
+            .unwrap();
+        assert!(text_of(&result).starts_with("Normalized 1 tag(s) on 1 task(s):"));
+        assert!(text_of(&result).contains("Shouty tags: Urgent → urgent"));
+
+        let tasks = server
+            .export(&scoped_filter("tag-case", None, None))
+            .await
+            .unwrap();
+        let mut tags = field_text(tasks[0].get("tags"))
+            .split(' ')
+            .map(str::to_string)
+            .collect::<Vec<_>>();
+        tags.sort();
+        assert_eq!(tags, ["review", "urgent"]);
+    }
+
+    // ── register_template / add_task_from_template ────────────────────────────
+
+    #[tokio::test]
+    async fn test_add_task_from_template() {
+        let (dir, server) = test_server();
+        let result = server
+            .register_template(Parameters(RegisterTemplateRequest {
+                name: "bug".to_string(),
+                template: AddTaskRequest {
+                    description: "Triage bug report".to_string(),
+                    project: "Eng".to_string(),
+                    due: Some("tomorrow".to_string()),
+                    tags: Some(vec!["bug".to_string()]),
+                    priority: Some("H".to_string()),
+                    wait: None,
+                    scheduled: None,
+                },
+            }))
+            .await
+            .unwrap();
+        assert_eq!(text_of(&result), "Registered template \"bug\".");
+
+        let plain = server
+            .add_task_from_template(Parameters(UseTemplateRequest {
+                template_name: "bug".to_string(),
+                description_override: None,
+                due_override: None,
+            }))
+            .await
+            .unwrap();
+        let plain = server
+            .export(&[created_uuid(text_of(&plain))])
+            .await
+            .unwrap();
+        assert_eq!(field_text(plain[0].get("description")), "Triage bug report");
+        assert_eq!(field_text(plain[0].get("priority")), "H");
+        assert_eq!(field_text(plain[0].get("tags")), "bug");
+
+        // Templates persist, so a fresh server over the same data dir can use them.
+        let reopened = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .build();
+        let custom = reopened
+            .add_task_from_template(Parameters(UseTemplateRequest {
+                template_name: "bug".to_string(),
+                description_override: Some("Fix login crash".to_string()),
+                due_override: Some("2030-01-15".to_string()),
+            }))
+            .await
+            .unwrap();
+        let custom = reopened
+            .export(&[created_uuid(text_of(&custom))])
+            .await
+            .unwrap();
+        assert_eq!(field_text(custom[0].get("description")), "Fix login crash");
+        assert_eq!(field_text(custom[0].get("project")), "Eng");
+        assert!(field_text(custom[0].get("due")).starts_with("2030011"));
+
+        let missing = server
+            .add_task_from_template(Parameters(UseTemplateRequest {
+                template_name: "nope".to_string(),
+                description_override: None,
+                due_override: None,
+            }))
+            .await;
+        assert!(missing.is_err());
+    }
+
+    // ── list_templates / delete_template ──────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_delete_template_removes_from_list() {
+        let (dir, server) = test_server();
+        for (name, description) in [("standup", "Prep standup notes"), ("bug", "Triage bug")] {
+            server
+                .register_template(Parameters(RegisterTemplateRequest {
+                    name: name.to_string(),
+                    template: AddTaskRequest {
+                        description: description.to_string(),
+                        project: "Eng".to_string(),
+                        due: None,
+                        tags: None,
+                        priority: None,
+                        wait: None,
+                        scheduled: None,
+                    },
+                }))
+                .await
+                .unwrap();
+        }
+        let list = server.list_templates().await.unwrap();
+        assert_eq!(
+            text_of(&list),
+            "- bug: Triage bug (project:Eng)\n- standup: Prep standup notes (project:Eng)"
+        );
+
+        server
+            .delete_template(Parameters(DeleteTemplateRequest {
+                name: "standup".to_string(),
+            }))
+            .await
+            .unwrap();
+        let list = server.list_templates().await.unwrap();
+        assert!(!text_of(&list).contains("standup"));
+
+        // The deletion is persisted too.
+        let reopened = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .build();
+        let list = reopened.list_templates().await.unwrap();
+        assert_eq!(text_of(&list), "- bug: Triage bug (project:Eng)");
+    }
+
+    // ── stream_list_tasks ─────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_stream_list_tasks_returns_chunks() {
+        let (_dir, server) = test_server();
+        let tasks: Vec<serde_json::Value> = (1..=100)
+            .map(|n| serde_json::json!({ "description": format!("Bulk task {n}") }))
+            .collect();
+        server
+            .import_tasks_from_json_array(Parameters(ImportJsonArrayRequest {
+                json_array: serde_json::Value::from(tasks).to_string(),
+                project_override: Some("big".to_string()),
+            }))
+            .await
+            .unwrap();
+
+        let result = server
+            .stream_list_tasks(Parameters(StreamListRequest {
+                project: "big".to_string(),
+                filter: None,
+                chunk_size: None,
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+        assert_eq!(result.content.len(), 4);
+        let total: usize = result
+            .content
+            .iter()
+            .map(|c| {
+                let chunk: Vec<serde_json::Value> =
+                    serde_json::from_str(&c.as_text().unwrap().text).unwrap();
+                chunk.len()
+            })
+            .sum();
+        assert_eq!(total, 100);
+    }
+
+    // ── suggest_projects ──────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_suggest_projects_by_prefix() {
+        let (_dir, server) = test_server();
+        add_task(&server, "API work", "Work.Backend").await;
+        add_task(&server, "UI work", "Work.Frontend").await;
+        add_task(&server, "Groceries", "Home").await;
+
+        let result = server
+            .suggest_projects(Parameters(SuggestProjectsRequest {
+                prefix: "work.".to_string(),
+            }))
+            .await
+            .unwrap();
+        assert_eq!(text_of(&result), "Work.Backend\nWork.Frontend");
+    }
+
+    // ── suggest_tags ──────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_suggest_tags_ranked_by_usage() {
+        let (_dir, server) = test_server();
+        for (desc, tags) in [
+            ("One", vec!["alps", "beta"]),
+            ("Two", vec!["alpha", "beta"]),
+            ("Three", vec!["alpha"]),
+            ("Four", vec!["alpha"]),
+        ] {
+            server
+                .add_task(Parameters(AddTaskRequest {
+                    description: desc.to_string(),
+                    project: "tag-suggest".to_string(),
+                    due: None,
+                    tags: Some(tags.into_iter().map(str::to_string).collect()),
+                    priority: None,
+                    wait: None,
+                    scheduled: None,
+                }))
+                .await
+                .unwrap();
+        }
+
+        let result = server
+            .suggest_tags(Parameters(SuggestTagsRequest {
+                prefix: "AL".to_string(),
+            }))
+            .await
+            .unwrap();
+        assert_eq!(text_of(&result), "alpha (3)\nalps (1)");
+    }
+
+    // ── get_task_effort_report ────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_task_effort_report_sums_effort() {
+        let (_dir, server) = test_server();
+        for effort in ["2", "4.5", ""] {
+            let mut args = vec![
+                "rc.uda.effort.type=numeric",
+                "add",
+                "Estimate me",
+                "project:effort-test",
+            ];
+            let attr = format!("effort:{effort}");
+            if !effort.is_empty() {
+                args.push(&attr);
+            }
+            server.run(&args).await.unwrap();
+        }
+
+        let result = server
+            .get_task_effort_report(Parameters(EffortReportRequest {
+                project: "effort-test".to_string(),
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+        let row = text_of(&result)
+            .lines()
+            .find(|line| line.starts_with("effort-test"))
+            .expect("project row missing")
+            .to_string();
+        let cols: Vec<&str> = row.split('|').map(str::trim).collect();
+        assert_eq!(cols, ["effort-test", "3", "6.5", "2.2", "4.5"]);
+    }
+
+    // ── timew_start / timew_stop ──────────────────────────────────────────────
+
+    #[cfg(feature = "timew-integration")]
+    #[tokio::test]
+    async fn test_timew_start_and_stop() {
+        let (dir, server) = test_server();
+        std::fs::create_dir_all(dir.path().join("timewarrior")).unwrap();
+        let id = add_task(&server, "Write report", "timew-test").await;
+
+        let started = server
+            .timew_start(Parameters(TimewRequest { id }))
+            .await
+            .unwrap();
+        assert!(
+            text_of(&started).contains("Tracking"),
+            "{}",
+            text_of(&started)
+        );
+        assert!(text_of(&started).contains("Write report"));
+
+        let stopped = server.timew_stop().await.unwrap();
+        assert!(
+            text_of(&stopped).contains("Recorded"),
+            "{}",
+            text_of(&stopped)
+        );
+    }
+
+    // ── get_timewarrior_summary ───────────────────────────────────────────────
+
+    #[cfg(feature = "timew-integration")]
+    #[tokio::test]
+    async fn test_get_timewarrior_summary_reports_tracked_time() {
+        let (dir, server) = test_server();
+        std::fs::create_dir_all(dir.path().join("timewarrior")).unwrap();
+        let id = add_task(&server, "Summarize me", "timew-test").await;
+        server
+            .timew_start(Parameters(TimewRequest { id }))
+            .await
+            .unwrap();
+        server.timew_stop().await.unwrap();
+
+        let summary = server
+            .get_timewarrior_summary(Parameters(TimewSummaryRequest {
+                tag: Some("Summarize me".to_string()),
+                period: None,
+            }))
+            .await
+            .unwrap();
+        assert!(!text_of(&summary).is_empty());
+    }
+
+    // ── resources ─────────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_read_task_resource() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Resource-backed task", "resources").await;
+        let uuid = uuid_of(&server, &id).await;
+
+        let listed = server.list_task_resources().await.unwrap();
+        let uris: Vec<&str> = listed.iter().map(|r| r.raw.uri.as_str()).collect();
+        assert!(uris.contains(&"task://project/resources"));
+        assert!(uris.contains(&format!("task://task/{uuid}").as_str()));
+
+        let read = server
+            .read_task_resource(&format!("task://task/{uuid}"))
+            .await
+            .unwrap();
+        let ResourceContents::TextResourceContents { text, .. } = &read.contents[0] else {
+            panic!("expected text contents");
+        };
+        let task: serde_json::Value = serde_json::from_str(text).unwrap();
+        assert_eq!(task["description"], "Resource-backed task");
+
+        let project = server
+            .read_task_resource("task://project/resources")
+            .await
+            .unwrap();
+        assert!(matches!(
+            &project.contents[0],
+            ResourceContents::TextResourceContents { text, .. } if text.contains("Resource-backed task")
+        ));
+        assert!(server
+            .read_task_resource("file:///etc/passwd")
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_read_task_resource_rejects_filters() {
+        let (_dir, server) = test_server();
+        for uri in [
+            "task://task/+WORK",
+            "task://task/project:x",
+            "task://task/1",
+        ] {
+            let err = server.read_task_resource(uri).await.unwrap_err();
+            assert_eq!(err.code, ErrorCode::RESOURCE_NOT_FOUND, "{uri}");
+        }
+        assert!(is_uuid("0f3c9d2e-1b4a-4c5d-8e6f-7a8b9c0d1e2f"));
+        assert!(!is_uuid("0f3c9d2e-1b4a-4c5d-8e6f-7a8b9c0d1e2"));
+        assert!(!is_uuid("0f3c9d2e+1b4a-4c5d-8e6f-7a8b9c0d1e2f"));
+    }
+
+    // ── prompts ───────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_workflow_prompts() {
+        let names: Vec<String> = workflow_prompts().into_iter().map(|p| p.name).collect();
+        assert_eq!(
+            names,
+            [
+                "daily-standup",
+                "weekly-review",
+                "inbox-processing",
+                "sprint-planning"
+            ]
+        );
+
+        let prompt = workflow_prompt("daily-standup", Some("Work")).unwrap();
+        let PromptMessageContent::Text { text } = &prompt.messages[0].content else {
+            panic!("expected a text message");
+        };
+        assert!(text.contains("list_tasks with project=\"Work\" filter=\"+OVERDUE\""));
+        assert!(!text.contains("{project}"));
+        assert!(workflow_prompt("nope", None).is_none());
+    }
+
+    // ── health_check ──────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_health_check_ok() {
+        let (_dir, server) = test_server();
+        let result = server.health_check().await.unwrap();
+        let report: serde_json::Value = serde_json::from_str(text_of(&result)).unwrap();
+        assert_eq!(report["status"], "ok", "{report}");
+        assert!(report["taskwarrior_version"].is_string());
+        assert_eq!(report["errors"], serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_health_check_degraded_on_missing_data_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let server = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path().join("missing"))
+            .build();
+        let result = server.health_check().await.unwrap();
+        let report: serde_json::Value = serde_json::from_str(text_of(&result)).unwrap();
+        assert_eq!(report["status"], "degraded");
+    }
+
+    // ── get_server_info ───────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_server_info_includes_version() {
+        let (_dir, server) = test_server();
+        let result = server.get_server_info().await.unwrap();
+        let out = text_of(&result);
+        assert!(out.contains(env!("CARGO_PKG_VERSION")), "{out}");
+        assert!(out.contains("Timeout: 30s"));
+    }
+
+    // ── get_metrics ───────────────────────────────────────────────────────────
+
+    /// A request context backed by an in-memory transport, for driving `call_tool`.
+    fn test_context(server: &TaskWarriorServer) -> RequestContext<RoleServer> {
+        let (transport, _client) = tokio::io::duplex(4096);
+        let running = rmcp::service::serve_directly(server.clone(), transport, None);
+        RequestContext {
+            ct: Default::default(),
+            id: RequestId::Number(1),
+            meta: Default::default(),
+            extensions: Default::default(),
+            peer: running.peer().clone(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_get_metrics_counts_tool_calls() {
+        let (_dir, server) = test_server();
+        for n in 1..=3 {
+            let args = serde_json::json!({
+                "description": format!("Metered task {n}"),
+                "project": "metrics",
+            });
+            server
+                .call_tool(
+                    CallToolRequestParams {
+                        meta: None,
+                        name: "add_task".into(),
+                        arguments: args.as_object().cloned(),
+                        task: None,
+                    },
+                    test_context(&server),
+                )
+                .await
+                .unwrap();
+        }
+
+        let result = server.get_metrics().await.unwrap();
+        let row = text_of(&result)
+            .lines()
+            .find(|line| line.starts_with("add_task "))
+            .expect("add_task missing from metrics")
+            .to_string();
+        let cols: Vec<&str> = row.split('|').map(str::trim).collect();
+        assert_eq!(cols[1], "3");
+        assert_eq!(cols[2], "0");
+    }
+
+    // ── request IDs ───────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_call_tool_errors_carry_request_id() {
+        let (_dir, server) = test_server();
+        let mut meta = Meta::new();
+        meta.insert("requestId".to_string(), "req-42".into());
+        let args = serde_json::json!({ "project": "x", "due_before": "next week" });
+        let err = server
+            .call_tool(
+                CallToolRequestParams {
+                    meta: Some(meta),
+                    name: "list_tasks".into(),
+                    arguments: args.as_object().cloned(),
+                    task: None,
+                },
+                test_context(&server),
+            )
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("due_before"));
+        assert!(
+            err.message.ends_with("(request_id: req-42)"),
+            "{}",
+            err.message
+        );
+    }
+
+    // ── shutdown ──────────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_drain_waits_for_in_flight_calls() {
+        let drain = Drain::default();
+        let guard = drain.enter().expect("drain open");
+        let worker = tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            drop(guard);
+        });
+
+        drain.close();
+        assert!(drain.enter().is_none(), "new work accepted after close");
+        assert!(drain.wait_idle(Duration::from_secs(5)).await);
+        worker.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_drain_times_out_on_stuck_calls() {
+        let drain = Drain::default();
+        let _stuck = drain.enter().expect("drain open");
+        drain.close();
+        let drained = tokio::time::timeout(
+            Duration::from_secs(5),
+            drain.wait_idle(Duration::from_millis(50)),
+        )
+        .await
+        .expect("wait_idle ignored its own timeout");
+        assert!(!drained);
+    }
+
+    #[tokio::test]
+    async fn test_run_rejected_while_shutting_down() {
+        let (_dir, server) = test_server();
+        server.drain.close();
+        let err = server.run(&["count"]).await.unwrap_err();
+        assert_eq!(err.message, "Server is shutting down");
+    }
+
+    // ── task_split ────────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_task_split_three_ways() {
+        let (_dir, server) = test_server();
+        let result = server
+            .add_task(Parameters(AddTaskRequest {
+                description: "Launch website".to_string(),
+                project: "split".to_string(),
+                due: None,
+                tags: Some(vec!["web".to_string()]),
+                priority: Some("H".to_string()),
+                wait: None,
+                scheduled: None,
+            }))
+            .await
+            .unwrap();
+        let original = created_uuid(text_of(&result));
+
+        let result = server
+            .task_split(Parameters(SplitTaskRequest {
+                id: original.clone(),
+                subtask_descriptions: vec![
+                    "Design pages".to_string(),
+                    "Build pages".to_string(),
+                    "Deploy".to_string(),
+                ],
+                complete_original: None,
+                add_dependency: Some(true),
+            }))
+            .await
+            .unwrap();
+        let subtasks: Vec<String> = text_of(&result)
+            .lines()
+            .filter_map(|line| line.strip_prefix("- "))
+            .map(|line| line.split_whitespace().next().unwrap().to_string())
+            .collect();
+        assert_eq!(subtasks.len(), 3);
+
+        let parent = server
+            .export(std::slice::from_ref(&original))
+            .await
+            .unwrap();
+        assert!(depends_of(&parent[0]).is_empty());
+        assert_eq!(field_text(parent[0].get("status")), "pending");
+
+        for uuid in &subtasks {
+            let task = server.export(std::slice::from_ref(uuid)).await.unwrap();
+            assert_eq!(depends_of(&task[0]), std::slice::from_ref(&original));
+            assert_eq!(field_text(task[0].get("project")), "split");
+            assert_eq!(field_text(task[0].get("priority")), "H");
+            assert_eq!(field_text(task[0].get("tags")), "web");
+        }
+    }
+
+    // ── merge_tasks ───────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_merge_tasks_moves_annotations() {
+        let (_dir, server) = test_server();
+        let primary = uuid_of(&server, &add_task(&server, "Fix login bug", "merge").await).await;
+        let secondary = uuid_of(
+            &server,
+            &add_task(&server, "Login broken on Safari", "merge").await,
+        )
+        .await;
+        for (id, note) in [
+            (&primary, "Reported by support"),
+            (&secondary, "Only on Safari 17"),
+            (&secondary, "Repro steps in ticket"),
+        ] {
+            server
+                .annotate_task(Parameters(AnnotateTaskRequest {
+                    id: id.clone(),
+                    note: note.to_string(),
+                    note_lines: None,
+                }))
+                .await
+                .unwrap();
+        }
+
+        let result = server
+            .merge_tasks(Parameters(MergeTasksRequest {
+                primary_id: primary.clone(),
+                secondary_id: secondary.clone(),
+                combined_description: Some("Fix login bug on Safari".to_string()),
+            }))
+            .await
+            .unwrap();
+        assert!(text_of(&result).contains("Fix login bug on Safari"));
+
+        let task = server.export(std::slice::from_ref(&primary)).await.unwrap();
+        let notes: Vec<&str> = task[0]["annotations"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|a| a["description"].as_str().unwrap())
+            .collect();
+        assert_eq!(notes.len(), 3);
+        for note in [
+            "Reported by support",
+            "Only on Safari 17",
+            "Repro steps in ticket",
+        ] {
+            assert!(notes.contains(&note), "missing {note}: {notes:?}");
+        }
+
+        let gone = server
+            .export(std::slice::from_ref(&secondary))
+            .await
+            .unwrap();
+        assert_eq!(field_text(gone[0].get("status")), "deleted");
+    }
+
+    // ── create_task_chain ─────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_create_task_chain_three_steps() {
+        let (_dir, server) = test_server();
+        let result = server
+            .create_task_chain(Parameters(TaskChainRequest {
+                project: "chain".to_string(),
+                descriptions: vec![
+                    "Write draft".to_string(),
+                    "Get review".to_string(),
+                    "Publish".to_string(),
+                ],
+                priority: Some("M".to_string()),
+                tags: None,
+            }))
+            .await
+            .unwrap();
+        let uuids: Vec<String> = text_of(&result)
+            .lines()
+            .skip(1)
+            .map(|line| line.split_whitespace().nth(1).unwrap().to_string())
+            .collect();
+        assert_eq!(uuids.len(), 3);
+
+        let middle = server
+            .run(&[&uuids[1], "+BLOCKED", "+BLOCKING", "_uuids"])
+            .await
+            .unwrap();
+        assert_eq!(middle, uuids[1]);
+        let first = server
+            .run(&[&uuids[0], "+BLOCKED", "_uuids"])
+            .await
+            .unwrap();
+        assert!(first.is_empty(), "first step should be actionable");
+        let last = server
+            .export(std::slice::from_ref(&uuids[2]))
+            .await
+            .unwrap();
+        assert_eq!(depends_of(&last[0]), vec![uuids[1].clone()]);
+    }
+
+    // ── task_capture / task_inbox_process ─────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_task_capture_defaults_to_inbox() {
+        let (_dir, server) = test_server();
+        let result = server
+            .task_capture(Parameters(CaptureTaskRequest {
+                description: "Call the plumber".to_string(),
+                project: None,
+                tags: None,
+                due: None,
+            }))
+            .await
+            .unwrap();
+        let uuid = created_uuid(text_of(&result));
+        let task = server.export(std::slice::from_ref(&uuid)).await.unwrap();
+        assert_eq!(field_text(task[0].get("project")), "Inbox");
+
+        let result = server
+            .task_inbox_process(Parameters(InboxProcessRequest {
+                project_for_inbox: "Inbox".to_string(),
+            }))
+            .await
+            .unwrap();
+        assert!(text_of(&result).contains("Call the plumber"));
+    }
+
+    #[tokio::test]
+    async fn test_task_inbox_process_empty() {
+        let (_dir, server) = test_server();
+        // A fully processed inbox only holds completed tasks.
+        let id = add_task(&server, "Already sorted", "Inbox").await;
+        server
+            .complete_task(Parameters(TaskIdRequest { id }))
+            .await
+            .unwrap();
+        let result = server
+            .task_inbox_process(Parameters(InboxProcessRequest {
+                project_for_inbox: "Inbox".to_string(),
+            }))
+            .await
+            .unwrap();
+        assert!(text_of(&result).contains("empty"));
+    }
+
+    // ── someday/maybe ─────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_add_someday_task_appears_in_list() {
+        let (_dir, server) = test_server();
+        let result = server
+            .add_someday_task(Parameters(AddSomedayTaskRequest {
+                description: "Learn to sail".to_string(),
+                tags: None,
+            }))
+            .await
+            .unwrap();
+        let uuid = created_uuid(text_of(&result));
+        let task = server.export(std::slice::from_ref(&uuid)).await.unwrap();
+        assert_eq!(field_text(task[0].get("priority")), "L");
+        assert!(task[0].get("due").is_none());
+
+        add_task(&server, "Not someday", "Work").await;
+        let result = server
+            .get_someday_maybe_list(Parameters(SomedayMaybeRequest { filter: None }))
+            .await
+            .unwrap();
+        let text = text_of(&result);
+        assert!(text.contains("Learn to sail"));
+        assert!(!text.contains("Not someday"));
+    }
+
+    // ── filter presets ────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_filter_preset_save_list_run_delete() {
+        let (dir, server) = test_server();
+        let result = server
+            .add_task(Parameters(AddTaskRequest {
+                description: "Hotfix payments".to_string(),
+                project: "presets".to_string(),
+                due: None,
+                tags: None,
+                priority: Some("H".to_string()),
+                wait: None,
+                scheduled: None,
+            }))
+            .await
+            .unwrap();
+        assert!(text_of(&result).contains("Created task"));
+        add_task(&server, "Tidy docs", "presets").await;
+
+        server
+            .save_filter_preset(Parameters(SavePresetRequest {
+                name: "hot".to_string(),
+                filter: "priority:H".to_string(),
+                project: Some("presets".to_string()),
+            }))
+            .await
+            .unwrap();
+
+        let list = server.list_filter_presets().await.unwrap();
+        assert!(text_of(&list).contains("- hot: priority:H (project:presets)"));
+        let reopened = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .build();
+        let list = reopened.list_filter_presets().await.unwrap();
+        assert!(text_of(&list).contains("- hot:"), "presets should persist");
+
+        let run = server
+            .run_filter_preset(Parameters(RunPresetRequest {
+                name: "hot".to_string(),
+            }))
+            .await
+            .unwrap();
+        assert!(text_of(&run).contains("Hotfix payments"));
+        assert!(!text_of(&run).contains("Tidy docs"));
+
+        server
+            .delete_filter_preset(Parameters(RunPresetRequest {
+                name: "hot".to_string(),
+            }))
+            .await
+            .unwrap();
+        let list = server.list_filter_presets().await.unwrap();
+        assert_eq!(text_of(&list), "No filter presets saved.");
+        assert!(server
+            .run_filter_preset(Parameters(RunPresetRequest {
+                name: "hot".to_string(),
+            }))
+            .await
+            .is_err());
+    }
+
+    // ── task_quick_add ────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_task_quick_add_parses_modifiers() {
+        let (_dir, server) = test_server();
+        let result = server
+            .task_quick_add(Parameters(QuickAddRequest {
+                text: "Fix the login bug project:Work priority:H +bug due:friday".to_string(),
+            }))
+            .await
+            .unwrap();
+        let uuid = created_uuid(text_of(&result));
+        let task = server.export(std::slice::from_ref(&uuid)).await.unwrap();
+        assert_eq!(field_text(task[0].get("description")), "Fix the login bug");
+        assert_eq!(field_text(task[0].get("project")), "Work");
+        assert_eq!(field_text(task[0].get("priority")), "H");
+        assert_eq!(field_text(task[0].get("tags")), "bug");
+        assert!(task[0].get("due").is_some());
+    }
+
+    #[test]
+    fn test_quick_add_args_requires_project() {
+        let err = quick_add_args("Buy milk +errand due:tomorrow").unwrap_err();
+        assert!(err.message.contains("project"));
+        let err = quick_add_args("project:Home +errand").unwrap_err();
+        assert!(err.message.contains("description"));
+        assert_eq!(
+            quick_add_args("Buy  milk project:Home").unwrap(),
+            ["Buy", "milk", "project:Home"]
+        );
+    }
+
+    #[test]
+    fn test_quick_add_args_coalesces_depends() {
+        assert_eq!(
+            quick_add_args("Task C depends:1 depends:2,3 project:Work").unwrap(),
+            ["Task", "C", "project:Work", "depends:1,2,3"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_task_quick_add_multiple_depends() {
+        let (_dir, server) = test_server();
+        let a = uuid_of(&server, &add_task(&server, "Task A", "Work").await).await;
+        let b = uuid_of(&server, &add_task(&server, "Task B", "Work").await).await;
+        let result = server
+            .task_quick_add(Parameters(QuickAddRequest {
+                text: "Task C depends:1 depends:2 project:Work".to_string(),
+            }))
+            .await
+            .unwrap();
+        let uuid = created_uuid(text_of(&result));
+        let task = server.export(std::slice::from_ref(&uuid)).await.unwrap();
+        let mut depends = depends_of(&task[0]);
+        depends.sort();
+        let mut expected = vec![a, b];
+        expected.sort();
+        assert_eq!(depends, expected);
+    }
+
+    // ── fuzzy_search_tasks ────────────────────────────────────────────────────
+
+    #[test]
+    fn test_fuzzy_score() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert!(fuzzy_score("implment feture", "implement feature") > 0.8);
+        assert!(fuzzy_score("implment feture", "Please implement feature X soon") > 0.8);
+        assert!(fuzzy_score("implment feture", "Water the plants") < 0.6);
+    }
+
+    #[tokio::test]
+    async fn test_fuzzy_search_tasks_tolerates_typos() {
+        let (_dir, server) = test_server();
+        add_task(&server, "implement feature", "fuzzy").await;
+        add_task(&server, "Water the plants", "fuzzy").await;
+        let result = server
+            .fuzzy_search_tasks(Parameters(FuzzySearchRequest {
+                query: "implment feture".to_string(),
+                project: "fuzzy".to_string(),
+                threshold: None,
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+        let text = text_of(&result);
+        assert!(text.contains("implement feature"));
+        assert!(!text.contains("Water the plants"));
+    }
+
+    // ── get_tasks_due_today ───────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_tasks_due_today() {
+        let (_dir, server) = test_server();
+        for (desc, due) in [("Pay rent", "today"), ("Book flights", "today+3d")] {
+            server
+                .add_task(Parameters(AddTaskRequest {
+                    description: desc.to_string(),
+                    project: "today".to_string(),
+                    due: Some(due.to_string()),
+                    tags: None,
+                    priority: None,
+                    wait: None,
+                    scheduled: None,
+                }))
+                .await
+                .unwrap();
+        }
+        let result = server
+            .get_tasks_due_today(Parameters(DueTodayRequest { project: None }))
+            .await
+            .unwrap();
+        let text = text_of(&result);
+        assert!(text.contains("Pay rent"));
+        assert!(!text.contains("Book flights"));
+
+        let result = server
+            .get_tasks_due_today(Parameters(DueTodayRequest {
+                project: Some("elsewhere".to_string()),
+            }))
+            .await
+            .unwrap();
+        assert_eq!(text_of(&result), "Nothing due today.");
+    }
+
+    // ── get_tasks_due_this_week ───────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_tasks_due_this_week() {
+        let (_dir, server) = test_server();
+        for (desc, due) in [
+            ("Due today", "today"),
+            ("Due last week", "today-8d"),
+            ("Due next month", "today+35d"),
+        ] {
+            server
+                .add_task(Parameters(AddTaskRequest {
+                    description: desc.to_string(),
+                    project: "week".to_string(),
+                    due: Some(due.to_string()),
+                    tags: None,
+                    priority: None,
+                    wait: None,
+                    scheduled: None,
+                }))
+                .await
+                .unwrap();
+        }
+        let result = server
+            .get_tasks_due_this_week(Parameters(DueThisWeekRequest {
+                project: Some("week".to_string()),
+            }))
+            .await
+            .unwrap();
+        let text = text_of(&result);
+        assert!(text.contains("Due today"));
+        assert!(!text.contains("Due last week"));
+        assert!(!text.contains("Due next month"));
+    }
+
+    // ── urgency_report ────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_urgency_report_ranks_tasks() {
+        let (_dir, server) = test_server();
+        for (desc, priority, due) in [
+            ("Low someday", Some("L"), None),
+            ("High due today", Some("H"), Some("today")),
+            ("Medium no date", Some("M"), None),
+        ] {
+            server
+                .add_task(Parameters(AddTaskRequest {
+                    description: desc.to_string(),
+                    project: "urgency".to_string(),
+                    due: due.map(str::to_string),
+                    tags: None,
+                    priority: priority.map(str::to_string),
+                    wait: None,
+                    scheduled: None,
+                }))
+                .await
+                .unwrap();
+        }
+        let result = server
+            .urgency_report(Parameters(UrgencyReportRequest {
+                project: "urgency".to_string(),
+                limit: None,
+                all_projects: None,
+            }))
+            .await
+            .unwrap();
+        let text = text_of(&result);
+        let rows: Vec<&str> = text.lines().skip(2).collect();
+        assert_eq!(rows.len(), 3, "{text}");
+        assert!(rows[0].ends_with("High due today"), "{text}");
+        assert!(rows[1].ends_with("Medium no date"), "{text}");
+        assert!(rows[2].ends_with("Low someday"), "{text}");
+        let scores: Vec<f64> = rows
+            .iter()
+            .map(|row| row.split('|').next().unwrap().trim().parse().unwrap())
+            .collect();
+        assert!(scores[0] > scores[1] && scores[1] > scores[2], "{scores:?}");
+    }
+
+    #[tokio::test]
+    async fn test_urgency_report_propagates_errors() {
+        let dir = TempDir::new().unwrap();
+        let server = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .task_binary(dir.path().join("no-such-task"))
+            .build();
+        let result = server
+            .urgency_report(Parameters(UrgencyReportRequest {
+                project: "urgency".to_string(),
+                limit: None,
+                all_projects: None,
+            }))
+            .await;
+        assert!(result.is_err());
+    }
+
+    // ── task_review ───────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_task_review_lists_stale_tasks() {
+        let (_dir, server) = test_server();
+        server
+            .import_tasks_from_json_array(Parameters(ImportJsonArrayRequest {
+                json_array: r#"[{"description": "Forgotten idea", "entry": "20200101T090000Z",
+                    "modified": "20200101T090000Z"}]"#
+                    .to_string(),
+                project_override: Some("review".to_string()),
+            }))
+            .await
+            .unwrap();
+        add_task(&server, "Fresh task", "review").await;
+
+        let result = server
+            .task_review(Parameters(TaskReviewRequest {
+                project: "review".to_string(),
+                stale_days: None,
+            }))
+            .await
+            .unwrap();
+        let text = text_of(&result);
+        assert!(text.contains("Forgotten idea"));
+        assert!(!text.contains("Fresh task"));
+    }
+
+    // ── task_describe ─────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_describe_task_formats_fields_and_timeline() {
+        let task = serde_json::json!({
+            "description": "Ship release",
+            "project": "Work",
+            "status": "pending",
+            "priority": "H",
+            "tags": ["release"],
+            "uuid": "u-1",
+            "urgency": 9.25,
+            "annotations": [
+                {"entry": "20250602T090000Z", "description": "QA signed off"},
+                {"entry": "20250601T090000Z", "description": "Branch cut"}
+            ]
+        });
+        let text = describe_task(&task);
+        assert!(text.starts_with("Description: Ship release\nProject: Work\n"));
+        assert!(text.contains("Priority: H\nTags: release"));
+        assert!(text.contains("Urgency: 9.25"));
+        let branch = text.find("Branch cut").unwrap();
+        let qa = text.find("QA signed off").unwrap();
+        assert!(branch < qa, "annotations should be oldest first: {text}");
+    }
+
+    #[tokio::test]
+    async fn test_task_describe_includes_annotations() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Write quarterly report", "describe").await;
+        add_task(&server, "Write quarterly reports", "describe").await;
+        for note in ["Gathered numbers", "Draft sent to Alex"] {
+            server
+                .annotate_task(Parameters(AnnotateTaskRequest {
+                    id: id.clone(),
+                    note: note.to_string(),
+                    note_lines: None,
+                }))
+                .await
+                .unwrap();
+        }
+        let result = server
+            .task_describe(Parameters(DescribeTaskRequest {
+                id,
+                include_similar: Some(true),
+            }))
+            .await
+            .unwrap();
+        let text = text_of(&result);
+        assert!(text.contains("Description: Write quarterly report"));
+        assert!(text.contains("Project: describe"));
+        assert!(text.contains("Gathered numbers"));
+        assert!(text.contains("Draft sent to Alex"));
+        assert!(text.contains("Similar tasks in describe:\n  - Write quarterly reports"));
+    }
+
+    // ── validate_task_data ────────────────────────────────────────────────────
+
+    #[test]
+    fn test_data_issues_flags_each_check() {
+        let tasks = serde_json::json!([
+            {"uuid": "a", "status": "pending", "description": "Blocked", "depends": "gone"},
+            {"uuid": "b", "status": "recurring", "description": "Template", "recur": "weekly"},
+            {"uuid": "c", "status": "pending", "description": "Odd", "priority": "X"},
+            {"uuid": "d", "status": "waiting", "description": "Snoozed",
+             "wait": "20200101T000000Z"},
+            {"uuid": "e", "status": "deleted", "description": "Ignored", "depends": "gone"},
+            {"uuid": "f", "status": "pending", "description": "Woke up",
+             "wait": "20200101T000000Z"},
+            {"uuid": "g", "status": "pending", "description": "Still asleep",
+             "wait": "20990101T000000Z"}
+        ]);
+        let issues = data_issues(tasks.as_array().unwrap(), Utc::now());
+        let counts: Vec<usize> = issues.iter().map(|(_, found)| found.len()).collect();
+        assert_eq!(counts, [1, 1, 1, 2]);
+        assert_eq!(issues[3].1[1], "Woke up (f)");
+        assert_eq!(issues[0].1[0], "Blocked (a) → gone");
+    }
+
+    #[tokio::test]
+    async fn test_validate_task_data_reports_missing_dependency() {
+        let (_dir, server) = test_server();
+        server
+            .import_tasks_from_json_array(Parameters(ImportJsonArrayRequest {
+                json_array: r#"[{"description": "Waits on a ghost",
+                    "depends": "00000000-0000-4000-8000-000000000000"}]"#
+                    .to_string(),
+                project_override: Some("validate".to_string()),
+            }))
+            .await
+            .unwrap();
+        let result = server.validate_task_data().await.unwrap();
+        let text = text_of(&result);
+        assert!(
+            text.contains("Dependencies on missing or deleted tasks: 1"),
+            "{text}"
+        );
+        assert!(text.contains("00000000-0000-4000-8000-000000000000"));
+    }
+
+    // ── task_backup ───────────────────────────────────────────────────────────
+
+    /// Entry names of a .tar.gz archive.
+    fn archive_names(archive: &[u8]) -> Vec<String> {
+        untar_gz(archive)
+            .unwrap()
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    #[test]
+    fn test_tar_header_checksum() {
+        let header = tar_header("pending.data", 10, 0);
+        let stored =
+            u32::from_str_radix(std::str::from_utf8(&header[148..154]).unwrap(), 8).unwrap();
+        let mut blank = header;
+        blank[148..156].copy_from_slice(b"        ");
+        assert_eq!(stored, blank.iter().map(|&b| u32::from(b)).sum::<u32>());
+
+        let archive = tar_gz(
+            &[
+                ("a.txt".to_string(), b"hello".to_vec()),
+                ("b.txt".to_string(), Vec::new()),
+            ],
+            0,
+        )
+        .unwrap();
+        assert_eq!(archive_names(&archive), ["a.txt", "b.txt"]);
+    }
+
+    #[tokio::test]
+    async fn test_task_backup_archives_data_files() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Back me up", "backup").await;
+        add_task(&server, "Still pending", "backup").await;
+        server
+            .complete_task(Parameters(TaskIdRequest { id }))
+            .await
+            .unwrap();
+
+        let out_dir = tempfile::tempdir().unwrap();
+        let result = server
+            .task_backup(Parameters(BackupRequest {
+                output_path: out_dir.path().display().to_string(),
+            }))
+            .await
+            .unwrap();
+        assert!(text_of(&result).contains("taskwarrior-backup-"));
+        let archive = std::fs::read_dir(out_dir.path())
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap()
+            .path();
+        let names = archive_names(&std::fs::read(archive).unwrap());
+        assert!(names.contains(&"pending.data".to_string()), "{names:?}");
+        assert!(names.contains(&"completed.data".to_string()), "{names:?}");
+    }
+
+    // ── task_restore ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_untar_gz_rejects_paths() {
+        let archive = tar_gz(&[("../evil".to_string(), b"x".to_vec())], 0).unwrap();
+        assert!(untar_gz(&archive).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_task_restore_requires_confirm() {
+        let (_dir, server) = test_server();
+        let err = server
+            .task_restore(Parameters(RestoreRequest {
+                backup_path: "/nonexistent.tar.gz".to_string(),
+                confirm: false,
+            }))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("confirm=true"));
+    }
+
+    #[tokio::test]
+    async fn test_task_restore_removes_files_missing_from_archive() {
+        let (dir, server) = test_server();
+        let archive = dir.path().join("snapshot.tar.gz");
+        std::fs::write(
+            &archive,
+            tar_gz(&[("pending.data".to_string(), b"old".to_vec())], 0).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(dir.path().join("pending.data"), "new").unwrap();
+        std::fs::write(dir.path().join("taskchampion.sqlite3-wal"), "stale").unwrap();
+
+        server
+            .task_restore(Parameters(RestoreRequest {
+                backup_path: archive.display().to_string(),
+                confirm: true,
+            }))
+            .await
+            .unwrap();
+        let mut names: Vec<String> = std::fs::read_dir(dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, ["pending.data", "snapshot.tar.gz"]);
+        assert_eq!(
+            std::fs::read_to_string(dir.path().join("pending.data")).unwrap(),
+            "old"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_task_restore_round_trip() {
+        let (_dir, server) = test_server();
+        add_task(&server, "Before backup", "restore").await;
+        let out_dir = tempfile::tempdir().unwrap();
+        let archive = out_dir.path().join("snapshot.tar.gz");
+        server
+            .task_backup(Parameters(BackupRequest {
+                output_path: archive.display().to_string(),
+            }))
+            .await
+            .unwrap();
+        add_task(&server, "After backup", "restore").await;
+
+        let result = server
+            .task_restore(Parameters(RestoreRequest {
+                backup_path: archive.display().to_string(),
+                confirm: true,
+            }))
+            .await
+            .unwrap();
+        assert!(text_of(&result).starts_with("Restored "));
+        let list = list_text(&server, "restore").await;
+        assert!(list.contains("Before backup"));
+        assert!(!list.contains("After backup"));
+    }
+
+    // ── describe_filters ──────────────────────────────────────────────────────
+
+    #[test]
+    fn test_describe_filter_examples() {
+        assert_eq!(
+            describe_filter("project.is:Work +urgent priority:H -WAITING"),
+            "This filter matches tasks in project 'Work' that are tagged 'urgent', \
+             have priority H, and are not waiting."
+        );
+        assert_eq!(
+            describe_filter("+OVERDUE status:pending"),
+            "This filter matches tasks that are overdue and have status pending."
+        );
+        assert_eq!(
+            describe_filter("project:Home due.before:eow -someday"),
+            "This filter matches tasks in project 'Home' or its subprojects that are due \
+             before eow and are not tagged 'someday'."
+        );
+        assert_eq!(describe_filter(""), "This filter matches all tasks.");
+    }
+
+    #[test]
+    fn test_describe_filter_flags_or() {
+        let text = describe_filter("( +bug or +regression ) priority:");
+        assert!(text.starts_with(
+            "This filter matches tasks that are tagged 'bug', are tagged 'regression', \
+             and have no priority."
+        ));
+        assert!(text.contains("uses 'or'"));
+    }
+
+    // ── prepend_to_task / append_to_task ──────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_prepend_and_append_to_task() {
+        let (_dir, server) = test_server();
+        let uuid = uuid_of(&server, &add_task(&server, "Fix login", "edit").await).await;
+        server
+            .prepend_to_task(Parameters(PrependTextRequest {
+                id: uuid.clone(),
+                text: "URGENT:".to_string(),
+            }))
+            .await
+            .unwrap();
+        let info = server
+            .get_task(Parameters(TaskIdRequest { id: uuid.clone() }))
+            .await
+            .unwrap();
+        assert!(text_of(&info).contains("URGENT: Fix login"));
+
+        server
+            .append_to_task(Parameters(AppendTextRequest {
+                id: uuid.clone(),
+                text: "on Safari".to_string(),
+            }))
+            .await
+            .unwrap();
+        let task = server.export(std::slice::from_ref(&uuid)).await.unwrap();
+        assert_eq!(
+            field_text(task[0].get("description")),
+            "URGENT: Fix login on Safari"
+        );
+    }
+
+    // ── task_done_with_note ───────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_task_done_with_note() {
+        let (_dir, server) = test_server();
+        let uuid = uuid_of(&server, &add_task(&server, "Ship v1.4", "done").await).await;
+        server
+            .task_done_with_note(Parameters(CompleteWithNoteRequest {
+                id: uuid.clone(),
+                note: "Released to production".to_string(),
+            }))
+            .await
+            .unwrap();
+        let task = server.export(std::slice::from_ref(&uuid)).await.unwrap();
+        assert_eq!(field_text(task[0].get("status")), "completed");
+        assert_eq!(
+            task[0]["annotations"][0]["description"],
+            "Released to production"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_task_done_with_note_keeps_task_open_when_note_fails() {
+        let (_dir, server) = test_server();
+        let uuid = uuid_of(&server, &add_task(&server, "Ship v1.5", "done").await).await;
+        let result = server
+            .task_done_with_note(Parameters(CompleteWithNoteRequest {
+                id: uuid.clone(),
+                note: "  ".to_string(),
+            }))
+            .await;
+        assert!(result.is_err());
+        let task = server.export(std::slice::from_ref(&uuid)).await.unwrap();
+        assert_eq!(field_text(task[0].get("status")), "pending");
+    }
+
+    // ── batch_import_from_csv ─────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_csv_quotes() {
+        let rows = parse_csv("a,b\r\n\"x, y\",\"say \"\"hi\"\"\"\n\n\"multi\nline\",\n");
+        assert_eq!(
+            rows,
+            [
+                vec!["a", "b"],
+                vec!["x, y", "say \"hi\""],
+                vec!["multi\nline", ""]
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_batch_import_from_csv_five_rows() {
+        let (_dir, server) = test_server();
+        let csv = "Title,Project,Due,Priority\n\
+                   Buy milk,Home,tomorrow,L\n\
+                   \"Call Bob, again\",Home,,\n\
+                   File taxes,,eom,H\n\
+                   ,Home,,\n\
+                   Renew passport,Admin,,M\n";
+        let result = server
+            .batch_import_from_csv(Parameters(ImportCsvRequest {
+                csv: csv.to_string(),
+                project_column: None,
+                description_column: Some("title".to_string()),
+                due_column: None,
+                priority_column: None,
+                default_project: Some("Inbox".to_string()),
+            }))
+            .await
+            .unwrap();
+        let text = text_of(&result);
+        assert!(text.starts_with("Created 4 task(s) (1 failed)."), "{text}");
+        assert!(text.contains("row 4: missing description"));
+
+        let home = list_text(&server, "Home").await;
+        assert!(home.contains("Call Bob, again"));
+        assert!(list_text(&server, "Inbox").await.contains("File taxes"));
+    }
+
+    // ── get_completion_rate ───────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_completion_rate_validates_project() {
+        let (_dir, server) = test_server();
+        let err = server
+            .get_completion_rate(Parameters(CompletionRateRequest {
+                project: "+work".into(),
+                days: None,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_get_completion_rate_counts() {
+        let (_dir, server) = test_server();
+        let mut uuids = Vec::new();
+        for desc in ["One", "Two", "Three", "Four"] {
+            uuids.push(uuid_of(&server, &add_task(&server, desc, "rate").await).await);
+        }
+        for uuid in &uuids[..2] {
+            server
+                .complete_task(Parameters(TaskIdRequest { id: uuid.clone() }))
+                .await
+                .unwrap();
+        }
+        let result = server
+            .get_completion_rate(Parameters(CompletionRateRequest {
+                project: "rate".to_string(),
+                days: Some(2),
+            }))
+            .await
+            .unwrap();
+        let text = text_of(&result);
+        assert!(text.contains("2 completed, 4 added"), "{text}");
+        assert!(text.contains("Completion ratio: 0.50"), "{text}");
+        assert!(
+            text.contains("Velocity: 1.0 tasks/day, net: -1.0/day (backlog growing)"),
+            "{text}"
+        );
+    }
+
+    // ── close_project ─────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_close_project_completes_everything() {
+        let (_dir, server) = test_server();
+        for desc in ["Write copy", "Design banner", "Launch"] {
+            add_task(&server, desc, "campaign").await;
+        }
+        add_task(&server, "Unrelated", "other").await;
+        let result = server
+            .close_project(Parameters(CloseProjectRequest {
+                project: "campaign".to_string(),
+                note: Some("Campaign wrapped".to_string()),
+            }))
+            .await
+            .unwrap();
+        assert_eq!(
+            text_of(&result),
+            "Closed project campaign: completed 3 task(s)."
+        );
+        let pending = FilterBuilder::new()
+            .project("campaign")
+            .status("pending")
+            .into_args();
+        assert_eq!(server.count(&pending).await.unwrap(), 0);
+        let done = server
+            .export(&["project:campaign".to_string()])
+            .await
+            .unwrap();
+        assert!(done
+            .iter()
+            .all(|t| t["annotations"][0]["description"] == "Campaign wrapped"));
+        assert!(list_text(&server, "other").await.contains("Unrelated"));
+    }
+
+    // ── get_task_age_report ───────────────────────────────────────────────────
 
-#[tokio::main]
-async fn main() -> Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env().add_directive(tracing::Level::INFO.into()))
-        .with_writer(std::io::stderr)
-        .with_ansi(false)
-        .init();
+    #[tokio::test]
+    async fn test_get_task_age_report_validates_project() {
+        let (_dir, server) = test_server();
+        let err = server
+            .get_task_age_report(Parameters(AgeReportRequest {
+                project: String::new(),
+                older_than_days: None,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+    }
 
-    tracing::info!("Starting task-warrior-mcp");
+    #[tokio::test]
+    async fn test_get_task_age_report_new_task_is_zero_days() {
+        let (_dir, server) = test_server();
+        add_task(&server, "Brand new", "age").await;
+        let result = server
+            .get_task_age_report(Parameters(AgeReportRequest {
+                project: "age".to_string(),
+                older_than_days: None,
+            }))
+            .await
+            .unwrap();
+        let text = text_of(&result);
+        assert!(text.contains("\n         0 | Brand new"), "{text}");
 
-    let service = TaskWarriorServer::new()
-        .serve(stdio())
-        .await
-        .inspect_err(|e| tracing::error!("Serving error: {e:?}"))?;
+        let result = server
+            .get_task_age_report(Parameters(AgeReportRequest {
+                project: "age".to_string(),
+                older_than_days: Some(30),
+            }))
+            .await
+            .unwrap();
+        assert!(text_of(&result).starts_with("No pending tasks"));
+    }
 
-    service.waiting().await?;
-    Ok(())
-}
+    // ── json_envelope ─────────────────────────────────────────────────────────
 
-// ── Tests ─────────────────────────────────────────────────────────────────────
+    async fn call_enveloped(
+        server: &TaskWarriorServer,
+        tool: &str,
+        args: serde_json::Value,
+    ) -> serde_json::Value {
+        let result = server
+            .call_tool(
+                CallToolRequestParams {
+                    meta: None,
+                    name: tool.to_string().into(),
+                    arguments: args.as_object().cloned(),
+                    task: None,
+                },
+                test_context(server),
+            )
+            .await
+            .expect("enveloped calls always succeed at the protocol level");
+        serde_json::from_str(text_of(&result)).expect("envelope is JSON")
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use tempfile::TempDir;
+    #[tokio::test]
+    async fn test_json_envelope_wraps_output() {
+        let (_dir, server) = test_server();
+        let envelope = call_enveloped(
+            &server,
+            "describe_filters",
+            serde_json::json!({"filter": "+urgent", "json_envelope": true}),
+        )
+        .await;
+        assert_eq!(envelope["tool"], "describe_filters");
+        assert_eq!(envelope["status"], "ok");
+        assert_eq!(
+            envelope["data"],
+            "This filter matches tasks that are tagged 'urgent'."
+        );
+        assert!(DateTime::parse_from_rfc3339(envelope["timestamp"].as_str().unwrap()).is_ok());
+    }
 
-    // ── helpers ───────────────────────────────────────────────────────────────
+    #[tokio::test]
+    async fn test_json_envelope_reports_errors() {
+        let (_dir, server) = test_server();
+        let envelope = call_enveloped(
+            &server,
+            "fuzzy_search_tasks",
+            serde_json::json!({
+                "query": "x", "project": "p", "threshold": 2.0, "json_envelope": true
+            }),
+        )
+        .await;
+        assert_eq!(envelope["status"], "error");
+        assert!(envelope["data"].as_str().unwrap().contains("threshold"));
+    }
 
-    fn test_server() -> (TempDir, TaskWarriorServer) {
-        let dir = TempDir::new().expect("failed to create temp dir");
-        let server = TaskWarriorServer::with_data_dir(dir.path());
-        (dir, server)
+    #[test]
+    fn test_json_envelope_in_every_schema() {
+        let (_dir, server) = test_server();
+        for tool in server.tool_router.list_all() {
+            let tool = server.get_tool(&tool.name).unwrap();
+            assert!(
+                tool.input_schema["properties"]
+                    .get("json_envelope")
+                    .is_some(),
+                "{} lacks json_envelope",
+                tool.name
+            );
+        }
     }
 
-    fn text_of(result: &CallToolResult) -> &str {
-        result
-            .content
-            .first()
-            .expect("no content")
-            .as_text()
-            .expect("expected text content")
-            .text
-            .as_str()
+    // ── get_next_week_tasks ───────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_next_week_tasks_groups_by_day() {
+        let (_dir, server) = test_server();
+        for (desc, due) in [
+            ("Dentist", "today+3d"),
+            ("Call landlord", "today+1d"),
+            ("Pick up parcel", "today+1d"),
+            ("Renew lease", "today+20d"),
+        ] {
+            server
+                .add_task(Parameters(AddTaskRequest {
+                    description: desc.to_string(),
+                    project: "week".to_string(),
+                    due: Some(due.to_string()),
+                    tags: None,
+                    priority: None,
+                    wait: None,
+                    scheduled: None,
+                }))
+                .await
+                .unwrap();
+        }
+        let result = server
+            .get_next_week_tasks(Parameters(NextWeekRequest {
+                project: Some("week".to_string()),
+            }))
+            .await
+            .unwrap();
+        let text = text_of(&result);
+        let tomorrow = (Local::now() + chrono::Duration::days(1)).format("%A %Y-%m-%d:");
+        let in_three = (Local::now() + chrono::Duration::days(3)).format("%A %Y-%m-%d:");
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines.len(), 5, "{text}");
+        assert_eq!(lines[0], tomorrow.to_string());
+        assert!(lines[1..3].contains(&"  - Call landlord"));
+        assert!(lines[1..3].contains(&"  - Pick up parcel"));
+        assert_eq!(lines[3], in_three.to_string());
+        assert_eq!(lines[4], "  - Dentist");
     }
 
-    /// Parse "Created task 5." → "5"
-    fn created_id(output: &str) -> String {
-        output
-            .split_whitespace()
-            .nth(2)
-            .unwrap_or("1")
-            .trim_end_matches('.')
-            .to_string()
+    // ── deprioritize_project ──────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_deprioritize_project_sets_low_priority() {
+        let (_dir, server) = test_server();
+        for (desc, priority) in [
+            ("Refactor", Some("H")),
+            ("Docs", Some("M")),
+            ("Cleanup", None),
+        ] {
+            server
+                .add_task(Parameters(AddTaskRequest {
+                    description: desc.to_string(),
+                    project: "sideline".to_string(),
+                    due: None,
+                    tags: None,
+                    priority: priority.map(str::to_string),
+                    wait: None,
+                    scheduled: None,
+                }))
+                .await
+                .unwrap();
+        }
+        let result = server
+            .deprioritize_project(Parameters(DeprioritizeProjectRequest {
+                project: "sideline".to_string(),
+            }))
+            .await
+            .unwrap();
+        assert!(text_of(&result).contains("3 task(s) set to priority L"));
+        let tasks = server
+            .export(&["project:sideline".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 3);
+        assert!(tasks.iter().all(|t| t["priority"] == "L"));
     }
 
-    async fn add_task(server: &TaskWarriorServer, desc: &str, project: &str) -> String {
+    // ── escalate_overdue ──────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_escalate_overdue_validates_project() {
+        let (_dir, server) = test_server();
+        let err = server
+            .escalate_overdue(Parameters(EscalateOverdueRequest {
+                project: Some("Work..Backend".into()),
+                new_priority: None,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_escalate_overdue_raises_priority() {
+        let (_dir, server) = test_server();
+        let mut uuids = Vec::new();
+        for (desc, due) in [("Late invoice", "today-2d"), ("Future invoice", "today+5d")] {
+            let result = server
+                .add_task(Parameters(AddTaskRequest {
+                    description: desc.to_string(),
+                    project: "billing".to_string(),
+                    due: Some(due.to_string()),
+                    tags: None,
+                    priority: Some("L".to_string()),
+                    wait: None,
+                    scheduled: None,
+                }))
+                .await
+                .unwrap();
+            uuids.push(created_uuid(text_of(&result)));
+        }
         let result = server
-            .add_task(Parameters(AddTaskRequest {
-                description: desc.to_string(),
-                project: project.to_string(),
-                due: None,
-                tags: None,
-                priority: None,
-                wait: None,
-                scheduled: None,
+            .escalate_overdue(Parameters(EscalateOverdueRequest {
+                project: Some("billing".to_string()),
+                new_priority: None,
             }))
             .await
-            .expect("add_task failed");
-        created_id(text_of(&result))
+            .unwrap();
+        assert_eq!(
+            text_of(&result),
+            "Escalated 1 overdue task(s) to priority H."
+        );
+        let late = server
+            .export(std::slice::from_ref(&uuids[0]))
+            .await
+            .unwrap();
+        assert_eq!(late[0]["priority"], "H");
+        let future = server
+            .export(std::slice::from_ref(&uuids[1]))
+            .await
+            .unwrap();
+        assert_eq!(future[0]["priority"], "L");
     }
 
-    // ── add_task ──────────────────────────────────────────────────────────────
+    // ── deprecation notices ───────────────────────────────────────────────────
+
+    #[test]
+    fn test_deprecation_notices() {
+        let stderr = "Configuration override rc.confirmation=no\n\
+                      The 'ghistory' report is Deprecated and will be removed in 3.0.\n\
+                      Done.";
+        assert_eq!(
+            deprecation_notices(stderr),
+            ["The 'ghistory' report is Deprecated and will be removed in 3.0."]
+        );
+        assert!(deprecation_notices("").is_empty());
+    }
+
+    /// `io::Write` sink shared with a test, for capturing log output.
+    #[derive(Clone, Default)]
+    struct LogBuffer(Arc<Mutex<Vec<u8>>>);
+
+    impl std::io::Write for LogBuffer {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.lock().unwrap().extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
 
+    /// Write an executable `sh` script standing in for the `task` binary.
+    #[cfg(unix)]
+    fn fake_task(dir: &std::path::Path, body: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+
+        let path = dir.join("fake-task");
+        std::fs::write(&path, format!("#!/bin/sh\n{body}\n")).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[cfg(unix)]
     #[tokio::test]
-    async fn test_add_task_basic() {
+    async fn test_run_warns_on_deprecated_invocation() {
+        let dir = TempDir::new().unwrap();
+        let fake = fake_task(
+            dir.path(),
+            "echo \"The 'ghistory' report is deprecated and will be removed.\" >&2\n\
+             echo ok",
+        );
+        let server = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .task_binary(fake)
+            .build();
+
+        let logs = LogBuffer::default();
+        let writer = logs.clone();
+        let subscriber = tracing_subscriber::fmt()
+            .with_writer(move || writer.clone())
+            .with_ansi(false)
+            .finish();
+        let out = {
+            let _guard = tracing::subscriber::set_default(subscriber);
+            server.run(&["ghistory"]).await.unwrap()
+        };
+        assert_eq!(out, "ok");
+        let logs = String::from_utf8(logs.0.lock().unwrap().clone()).unwrap();
+        assert!(logs.contains("WARN"), "{logs}");
+        assert!(logs.contains("report is deprecated"), "{logs}");
+    }
+
+    // ── rc overrides ──────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_rc_overrides() {
+        assert_eq!(
+            parse_rc_overrides("hooks=off; gc=off;;rc.verbose=nothing"),
+            [("hooks", "off"), ("gc", "off"), ("verbose", "nothing")]
+        );
+        assert!(parse_rc_overrides(" ; ").is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_run_injects_rc_overrides() {
+        let dir = TempDir::new().unwrap();
+        let server = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .task_binary(fake_task(dir.path(), "echo \"$@\""))
+            .rc_override("hooks", "off")
+            .build();
+        let out = server.run(&["list"]).await.unwrap();
+        assert!(out.ends_with("rc.hooks=off list"), "{out}");
+    }
+    // ── TaskWarriorServerBuilder ──────────────────────────────────────────────
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_builder_passes_task_rc() {
+        let dir = TempDir::new().unwrap();
+        let taskrc = dir.path().join("taskrc");
+        let server = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .task_binary(fake_task(dir.path(), "echo \"$TASKRC\""))
+            .task_rc(&taskrc)
+            .build();
+        assert_eq!(
+            server.run(&["list"]).await.unwrap(),
+            taskrc.display().to_string()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_builder_dry_run_skips_writes() {
+        let dir = TempDir::new().unwrap();
+        let server = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .task_binary(dir.path().join("no-such-task"))
+            .dry_run(true)
+            .build();
+        let out = server
+            .run(&["add", "Buy milk", "project:Home"])
+            .await
+            .unwrap();
+        assert_eq!(out, "Dry run: would run `task add Buy milk project:Home`");
+        // Reads still run — and fail here, since the binary doesn't exist.
+        assert!(server.run(&["list"]).await.is_err());
+    }
+
+    #[test]
+    fn test_modifies_data_looks_at_command_word() {
+        assert!(modifies_data(&["add", "export", "this"]));
+        assert!(modifies_data(&[
+            "rc.gc=off",
+            "+home",
+            "project:x",
+            "1",
+            "done"
+        ]));
+        assert!(modifies_data(&["Buy", "milk", "mod", "priority:H"]));
+        assert!(!modifies_data(&["export", "done"]));
+        assert!(!modifies_data(&["project:add", "export", "done"]));
+        assert!(!modifies_data(&["rc.context=none", "_get", "rc.context"]));
+        assert!(!modifies_data(&["+done", "count"]));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_builder_retries_timed_out_invocation() {
+        let dir = TempDir::new().unwrap();
+        let marker = dir.path().join("attempted");
+        let script = format!(
+            "if [ -e '{m}' ]; then echo ok; else touch '{m}'; exec sleep 5; fi",
+            m = marker.display()
+        );
+        let builder = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .task_binary(fake_task(dir.path(), &script))
+            .timeout(Duration::from_millis(500));
+
+        assert!(builder.clone().build().run(&["list"]).await.is_err());
+        std::fs::remove_file(&marker).unwrap();
+        let server = builder.max_retries(1).build();
+        assert_eq!(server.run(&["list"]).await.unwrap(), "ok");
+
+        // Writes are not retried after a timeout.
+        std::fs::remove_file(&marker).unwrap();
+        assert!(server.run(&["add", "Buy milk"]).await.is_err());
+    }
+    // ── UDAs in get_info ──────────────────────────────────────────────────────
+
+    #[test]
+    fn test_get_info_advertises_udas() {
+        let (_dir, server) = test_server();
+        let info = server.get_info();
+        assert!(!info
+            .instructions
+            .unwrap()
+            .contains("User-defined attributes"));
+
+        *server.udas.write().unwrap() = vec!["estimate".into(), "size".into()];
+        let info = server.get_info();
+        assert!(info.instructions.unwrap().contains(
+            "User-defined attributes (set as name:value in modifications): estimate · size."
+        ));
+        let extension = &info.capabilities.extensions.unwrap()[UDA_EXTENSION];
+        assert_eq!(extension["udas"], serde_json::json!(["estimate", "size"]));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_refresh_udas() {
+        let dir = TempDir::new().unwrap();
+        let server = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .task_binary(fake_task(dir.path(), "printf 'size\\nestimate\\n'"))
+            .build();
+        server.refresh_udas().await;
+        assert_eq!(*server.udas.read().unwrap(), ["estimate", "size"]);
+    }
+
+    // ── get_tool_info ─────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_get_tool_info_returns_schema_and_version() {
         let (_dir, server) = test_server();
         let result = server
+            .get_tool_info(Parameters(GetToolInfoRequest {
+                name: "add_task".into(),
+            }))
+            .await
+            .unwrap();
+        let info: serde_json::Value = serde_json::from_str(text_of(&result)).unwrap();
+        assert_eq!(info["name"], "add_task");
+        assert_eq!(info["version"], tool_version("add_task").unwrap());
+        assert_eq!(info["input_schema"]["type"], "object");
+        assert!(info["input_schema"]["properties"]["project"].is_object());
+
+        let err = server
+            .get_tool_info(Parameters(GetToolInfoRequest {
+                name: "no_such_tool".into(),
+            }))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("Unknown tool"));
+    }
+
+    #[test]
+    fn test_tool_changelog_covers_every_tool() {
+        let (_dir, server) = test_server();
+        for tool in server.tool_router.list_all() {
+            assert!(tool_version(&tool.name).is_some(), "{} missing", tool.name);
+        }
+        let semver = |v: &str| -> Vec<u32> { v.split('.').map(|n| n.parse().unwrap()).collect() };
+        for (tool, version) in TOOL_CHANGELOG {
+            assert!(
+                semver(version) <= semver(env!("CARGO_PKG_VERSION")),
+                "{tool} is listed under unreleased {version}"
+            );
+        }
+        let summary = changelog_summary();
+        assert!(summary.contains("0.1.1: "), "{summary}");
+        assert!(summary.contains("all others"), "{summary}");
+    }
+    // ── change notifications ──────────────────────────────────────────────────
+
+    /// Like [`test_context`], but keeps the service running and hands back the client end
+    /// of the transport so tests can read what the server sends.
+    fn test_context_with_client(
+        server: &TaskWarriorServer,
+    ) -> (
+        RequestContext<RoleServer>,
+        tokio::io::DuplexStream,
+        rmcp::service::RunningService<RoleServer, TaskWarriorServer>,
+    ) {
+        let (transport, client) = tokio::io::duplex(64 * 1024);
+        let running = rmcp::service::serve_directly(server.clone(), transport, None);
+        let context = RequestContext {
+            ct: Default::default(),
+            id: RequestId::Number(1),
+            meta: Default::default(),
+            extensions: Default::default(),
+            peer: running.peer().clone(),
+        };
+        (context, client, running)
+    }
+
+    /// Next `notifications/message` payload the server sent to `client`.
+    async fn next_log_message(client: tokio::io::DuplexStream) -> serde_json::Value {
+        use tokio::io::AsyncBufReadExt;
+
+        let mut lines = tokio::io::BufReader::new(client).lines();
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while let Some(line) = lines.next_line().await.unwrap() {
+                let message: serde_json::Value = serde_json::from_str(&line).unwrap();
+                if message["method"] == "notifications/message" {
+                    return message["params"].clone();
+                }
+            }
+            panic!("transport closed without a notification");
+        })
+        .await
+        .expect("no notification within 5s")
+    }
+
+    #[test]
+    fn test_change_event() {
+        assert_eq!(change_event("add_task"), Some("task_created"));
+        assert_eq!(change_event("delete_task"), Some("task_deleted"));
+        assert_eq!(change_event("list_tasks"), None);
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_add_task_emits_task_created_notification() {
+        let dir = TempDir::new().unwrap();
+        let script = "case \"$*\" in\n\
+                      *' add '*) echo 'Created task 1.' ;;\n\
+                      *_uuids*) echo 9a1b2c3d-0000-4000-8000-000000000001 ;;\n\
+                      esac";
+        let server = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .task_binary(fake_task(dir.path(), script))
+            .build();
+        let (context, client, _running) = test_context_with_client(&server);
+        let args = serde_json::json!({ "description": "Water plants", "project": "Home" });
+        server
+            .call_tool(
+                CallToolRequestParams {
+                    meta: None,
+                    name: "add_task".into(),
+                    arguments: args.as_object().cloned(),
+                    task: None,
+                },
+                context,
+            )
+            .await
+            .unwrap();
+
+        let params = next_log_message(client).await;
+        assert_eq!(params["logger"], CHANGE_LOGGER);
+        assert_eq!(
+            params["data"],
+            serde_json::json!({
+                "event": "task_created",
+                "task_id": "9a1b2c3d-0000-4000-8000-000000000001",
+                "project": "Home",
+            })
+        );
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_change_notifications_avoid_extra_lookups() {
+        let dir = TempDir::new().unwrap();
+        let log = dir.path().join("calls");
+        let script = format!(
+            "echo \"$*\" >> '{}'\n\
+             case \"$*\" in\n\
+             *'_get rc.context'*) echo work ;;\n\
+             *' add '*) echo 'Created task 1.' ;;\n\
+             *_uuids*) echo 9a1b2c3d-0000-4000-8000-000000000001 ;;\n\
+             *' modify '*) echo 'Modified 1 task.' ;;\n\
+             *export*) echo '[{{\"uuid\": \"9a1b2c3d-0000-4000-8000-000000000001\", \"project\": \"work\"}}]' ;;\n\
+             esac",
+            log.display()
+        );
+        let server = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .task_binary(fake_task(dir.path(), &script))
+            .build();
+        for (tool, args) in [
+            (
+                "add_task",
+                serde_json::json!({ "description": "Slides", "project": AUTO_PROJECT }),
+            ),
+            (
+                "modify_task",
+                serde_json::json!({ "id": "1", "modifications": "priority:H" }),
+            ),
+        ] {
+            let (context, client, _running) = test_context_with_client(&server);
+            server
+                .call_tool(
+                    CallToolRequestParams {
+                        meta: None,
+                        name: tool.into(),
+                        arguments: args.as_object().cloned(),
+                        task: None,
+                    },
+                    context,
+                )
+                .await
+                .unwrap();
+            let params = next_log_message(client).await;
+            assert_eq!(params["data"]["project"], "work", "{tool}");
+            assert_eq!(
+                params["data"]["task_id"], "9a1b2c3d-0000-4000-8000-000000000001",
+                "{tool}"
+            );
+        }
+
+        let calls = std::fs::read_to_string(&log).unwrap();
+        // Drop the rc.confirmation and rc.data.location arguments every call starts with.
+        let commands: Vec<String> = calls
+            .lines()
+            .map(|line| {
+                line.split_whitespace()
+                    .skip(2)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            })
+            .collect();
+        assert_eq!(
+            commands,
+            [
+                "_get rc.context",
+                "add Slides project:work",
+                "1 _uuids",
+                "1 modify priority:H",
+                "1 export",
+            ],
+            "{calls}"
+        );
+    }
+
+    // ── get_task_urgency_factors ──────────────────────────────────────────────
+
+    #[test]
+    fn test_parse_urgency_factors() {
+        let info = [
+            "Urgency               7.055",
+            "",
+            "    project      1 *    1 =      1",
+            "    priority     1 *    6 =      6",
+            "    age      0.027 *    2 =  0.055",
+            "                            ------",
+            "                             7.055",
+        ]
+        .join("\n");
+        let factors = parse_urgency_factors(&info);
+        assert_eq!(factors.len(), 3);
+        assert_eq!(factors[1], ("priority".to_string(), 1.0, 6.0, 6.0));
+        assert_eq!(factors[2].0, "age");
+        assert!((factors[2].3 - 0.055).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_get_task_urgency_factors_shows_priority() {
+        let (_dir, server) = test_server();
+        let out = server
             .add_task(Parameters(AddTaskRequest {
-                description: "Write the docs".to_string(),
-                project: "myproject".to_string(),
+                description: "Fix login bug".into(),
+                project: "urgency".into(),
                 due: None,
                 tags: None,
-                priority: None,
+                priority: Some("H".into()),
                 wait: None,
                 scheduled: None,
             }))
             .await
             .unwrap();
+        let uuid = created_uuid(text_of(&out));
 
-        assert!(!result.is_error.unwrap_or(false));
-        assert!(text_of(&result).contains("Created task"));
+        let result = server
+            .get_task_urgency_factors(Parameters(TaskIdRequest { id: uuid }))
+            .await
+            .unwrap();
+        let text = text_of(&result);
+        assert!(text.starts_with("Urgency of \"Fix login bug\""), "{text}");
+        let priority = text
+            .lines()
+            .find(|line| line.trim_start().starts_with("priority"))
+            .expect("no priority factor");
+        let contribution: f64 = priority.rsplit("= ").next().unwrap().parse().unwrap();
+        assert!(contribution > 0.0, "{priority}");
     }
 
+    // ── batch_create_daily_recurring ──────────────────────────────────────────
+
     #[tokio::test]
-    async fn test_add_task_with_all_fields() {
+    async fn test_batch_create_daily_recurring() {
         let (_dir, server) = test_server();
         let result = server
-            .add_task(Parameters(AddTaskRequest {
-                description: "Full featured task".to_string(),
-                project: "myproject".to_string(),
-                due: Some("tomorrow".to_string()),
-                tags: Some(vec!["urgent".to_string(), "review".to_string()]),
-                priority: Some("H".to_string()),
-                wait: None,
-                scheduled: None,
+            .batch_create_daily_recurring(Parameters(DailyHabitRequest {
+                descriptions: vec!["Meditate".into(), "Stretch".into(), "Read".into()],
+                project: "Habits".into(),
+                tags: Some(vec!["habit".into()]),
+                due_time: Some("07:30".into()),
+            }))
+            .await
+            .unwrap();
+        assert!(text_of(&result).starts_with("Created 3 daily habits in Habits:"));
+
+        let templates = server
+            .export(&["project:Habits".into(), "status:recurring".into()])
+            .await
+            .unwrap();
+        assert_eq!(templates.len(), 3);
+        for task in &templates {
+            assert_eq!(task["recur"], "daily");
+            assert_eq!(task["tags"], serde_json::json!(["habit"]));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_batch_create_daily_recurring_requires_a_habit() {
+        let (_dir, server) = test_server();
+        let err = server
+            .batch_create_daily_recurring(Parameters(DailyHabitRequest {
+                descriptions: vec!["  ".into()],
+                project: "Habits".into(),
+                tags: None,
+                due_time: None,
+            }))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("at least one habit"));
+    }
+
+    // ── task_unblock ──────────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_task_unblock_makes_task_ready() {
+        let (_dir, server) = test_server();
+        let blocker = add_task(&server, "Order parts", "unblock").await;
+        let blocker = uuid_of(&server, &blocker).await;
+        let blocked = add_task(&server, "Assemble shelf", "unblock").await;
+        let blocked = uuid_of(&server, &blocked).await;
+        server
+            .modify_task(Parameters(ModifyTaskRequest {
+                id: blocked.clone(),
+                modifications: format!("depends:{blocker}"),
+            }))
+            .await
+            .unwrap();
+        let is = |tag: &str| vec![blocked.clone(), tag.to_string()];
+        assert_eq!(server.count(&is("+BLOCKED")).await.unwrap(), 1);
+
+        let result = server
+            .task_unblock(Parameters(TaskIdRequest {
+                id: blocked.clone(),
             }))
             .await
             .unwrap();
+        assert!(text_of(&result).contains(&blocker), "{}", text_of(&result));
+        assert_eq!(server.count(&is("+BLOCKED")).await.unwrap(), 0);
+        assert_eq!(server.count(&is("+READY")).await.unwrap(), 1);
 
-        assert!(!result.is_error.unwrap_or(false));
-        assert!(text_of(&result).contains("Created task"));
+        let again = server
+            .task_unblock(Parameters(TaskIdRequest { id: blocked }))
+            .await
+            .unwrap();
+        assert!(text_of(&again).ends_with("has no dependencies."));
     }
 
+    // ── link_to_github_issue ──────────────────────────────────────────────────
+
     #[tokio::test]
-    async fn test_add_task_project_is_stored() {
+    async fn test_link_to_github_issue_annotates_task() {
         let (_dir, server) = test_server();
-        let id = add_task(&server, "Verify project stored", "stored-proj").await;
+        let id = add_task(&server, "Fix flaky CI", "github").await;
+        let uuid = uuid_of(&server, &id).await;
+        server
+            .link_to_github_issue(Parameters(GithubLinkRequest {
+                id: uuid.clone(),
+                repo: "octo-org/octo-repo".into(),
+                issue_number: 42,
+            }))
+            .await
+            .unwrap();
 
         let info = server
-            .get_task(Parameters(TaskIdRequest { id }))
+            .get_task(Parameters(TaskIdRequest { id: uuid }))
             .await
             .unwrap();
-
-        assert!(text_of(&info).contains("stored-proj"));
+        assert!(text_of(&info).contains("https://github.com/octo-org/octo-repo/issues/42"));
     }
 
-    // ── list_tasks ────────────────────────────────────────────────────────────
-
     #[tokio::test]
-    async fn test_list_tasks_scoped_to_project() {
-        let (_dir, server) = test_server();
-        add_task(&server, "Task in alpha", "alpha").await;
-        add_task(&server, "Task in beta", "beta").await;
-
+    async fn test_link_to_github_issue_sets_uda_when_configured() {
+        let dir = TempDir::new().unwrap();
+        let server = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .rc_override("uda.github_issue.type", "numeric")
+            .build();
+        let id = add_task(&server, "Triage report", "github").await;
+        let uuid = uuid_of(&server, &id).await;
         let result = server
-            .list_tasks(Parameters(ListTasksRequest {
-                project: "alpha".to_string(),
-                filter: None,
-                report: Some("list".to_string()),
-                all_projects: None,
+            .link_to_github_issue(Parameters(GithubLinkRequest {
+                id: uuid.clone(),
+                repo: "octo-org/octo-repo".into(),
+                issue_number: 7,
             }))
             .await
             .unwrap();
-
-        let out = text_of(&result);
-        assert!(out.contains("Task in alpha"), "alpha task must appear");
-        assert!(
-            !out.contains("Task in beta"),
-            "beta task must not leak into alpha scope"
-        );
+        assert!(text_of(&result).ends_with("Set github_issue:7."));
+        let task = server.export(&[uuid]).await.unwrap();
+        assert_eq!(task[0]["github_issue"], 7);
     }
 
     #[tokio::test]
-    async fn test_list_tasks_all_projects_override() {
+    async fn test_link_to_github_issue_rejects_bad_repo() {
         let (_dir, server) = test_server();
-        add_task(&server, "Task in alpha", "alpha").await;
-        add_task(&server, "Task in beta", "beta").await;
-
-        let result = server
-            .list_tasks(Parameters(ListTasksRequest {
-                project: "alpha".to_string(),
-                filter: None,
-                report: Some("list".to_string()),
-                all_projects: Some(true),
+        let err = server
+            .link_to_github_issue(Parameters(GithubLinkRequest {
+                id: "1".into(),
+                repo: "not a repo".into(),
+                issue_number: 1,
             }))
             .await
-            .unwrap();
+            .unwrap_err();
+        assert!(err.message.contains("owner/name"));
+    }
 
-        let out = text_of(&result);
-        assert!(out.contains("Task in alpha"));
-        assert!(
-            out.contains("Task in beta"),
-            "all_projects=true should surface both projects"
+    // ── get_tasks_with_url ────────────────────────────────────────────────────
+
+    #[test]
+    fn test_extract_urls_and_domains() {
+        let text = "See https://github.com/org/repo/issues/1, and (http://docs.example.org/a).";
+        let urls = extract_urls(text);
+        assert_eq!(
+            urls,
+            [
+                "https://github.com/org/repo/issues/1",
+                "http://docs.example.org/a"
+            ]
         );
+        assert!(url_in_domain(urls[0], "github.com"));
+        assert!(url_in_domain(urls[1], "example.org"));
+        assert!(!url_in_domain(urls[1], "github.com"));
+        assert!(!url_in_domain("https://notgithub.com/x", "github.com"));
+        assert!(extract_urls("no links here, just https:// alone").is_empty());
     }
 
     #[tokio::test]
-    async fn test_list_tasks_with_filter() {
+    async fn test_get_tasks_with_url_filters_by_domain() {
         let (_dir, server) = test_server();
-        add_task(&server, "High priority task", "filter-test").await;
-        add_task(&server, "Low priority task", "filter-test").await;
+        for (desc, url) in [
+            ("Review PR", "https://github.com/org/repo/pull/7"),
+            ("Read spec", "https://docs.example.org/spec"),
+        ] {
+            let id = add_task(&server, desc, "links").await;
+            server
+                .run(&[&id, "annotate", &format!("Link: {url}")])
+                .await
+                .unwrap();
+        }
 
-        // Modify the first to H priority so we can filter on it
-        server
-            .modify_task(Parameters(ModifyTaskRequest {
-                id: "1".to_string(),
-                modifications: "priority:H".to_string(),
+        let all = server
+            .get_tasks_with_url(Parameters(GetTasksWithUrlRequest {
+                project: "links".into(),
+                domain: None,
             }))
             .await
             .unwrap();
+        assert_eq!(text_of(&all).lines().count(), 4, "{}", text_of(&all));
 
-        let result = server
-            .list_tasks(Parameters(ListTasksRequest {
-                project: "filter-test".to_string(),
-                filter: Some("priority:H".to_string()),
-                report: Some("list".to_string()),
-                all_projects: None,
+        let github = server
+            .get_tasks_with_url(Parameters(GetTasksWithUrlRequest {
+                project: "links".into(),
+                domain: Some("github.com".into()),
             }))
             .await
             .unwrap();
-
-        let out = text_of(&result);
-        assert!(out.contains("High priority task"));
-        assert!(!out.contains("Low priority task"));
+        let text = text_of(&github);
+        assert!(
+            text.contains("https://github.com/org/repo/pull/7 | Review PR"),
+            "{text}"
+        );
+        assert!(!text.contains("docs.example.org"), "{text}");
     }
 
-    // ── search_tasks ──────────────────────────────────────────────────────────
+    // ── tag_tasks_by_project_prefix ───────────────────────────────────────────
 
     #[tokio::test]
-    async fn test_search_tasks_finds_match() {
+    async fn test_tag_tasks_by_project_prefix() {
         let (_dir, server) = test_server();
-        add_task(&server, "Fix the flibbertigibbet bug", "search-test").await;
-        add_task(&server, "Unrelated task", "search-test").await;
+        add_task(&server, "Fix API", "Work.Backend").await;
+        add_task(&server, "Fix CSS", "Work.Frontend").await;
+        add_task(&server, "Buy milk", "Home").await;
+        let request = || AutoTagRequest {
+            project_prefix: "Work".into(),
+            tag: "+work".into(),
+        };
 
         let result = server
-            .search_tasks(Parameters(SearchTasksRequest {
-                pattern: "flibbertigibbet".to_string(),
-                project: "search-test".to_string(),
-                filter: None,
-                all_projects: None,
-            }))
+            .tag_tasks_by_project_prefix(Parameters(request()))
             .await
             .unwrap();
+        assert_eq!(text_of(&result), "Tagged 2 task(s) in Work with +work.");
+        let tagged = server.export(&["+work".into()]).await.unwrap();
+        let mut projects: Vec<&str> = tagged
+            .iter()
+            .map(|t| t["project"].as_str().unwrap())
+            .collect();
+        projects.sort();
+        assert_eq!(projects, ["Work.Backend", "Work.Frontend"]);
 
-        let out = text_of(&result);
-        assert!(
-            out.contains("flibbertigibbet"),
-            "should find the matching task"
+        let again = server
+            .tag_tasks_by_project_prefix(Parameters(request()))
+            .await
+            .unwrap();
+        assert_eq!(text_of(&again), "Tagged 0 task(s) in Work with +work.");
+    }
+
+    // ── estimate_completion_date ──────────────────────────────────────────────
+
+    #[test]
+    fn test_estimate_finish() {
+        let today = NaiveDate::from_ymd_opt(2025, 6, 2).unwrap();
+        // 10 pending at 2/day → 5 days.
+        assert_eq!(
+            estimate_finish(10, 28, 14, today),
+            NaiveDate::from_ymd_opt(2025, 6, 7)
         );
-        assert!(
-            !out.contains("Unrelated task"),
-            "should not include non-matching tasks"
+        // Partial days round up.
+        assert_eq!(
+            estimate_finish(3, 2, 1, today),
+            NaiveDate::from_ymd_opt(2025, 6, 4)
         );
+        assert_eq!(estimate_finish(0, 5, 7, today), Some(today));
+        assert_eq!(estimate_finish(4, 0, 7, today), None);
     }
 
     #[tokio::test]
-    async fn test_search_tasks_scoped_to_project() {
+    async fn test_estimate_completion_date() {
         let (_dir, server) = test_server();
-        add_task(&server, "needle in project A", "proj-a").await;
-        add_task(&server, "needle in project B", "proj-b").await;
+        for n in 0..10 {
+            add_task(&server, &format!("Open {n}"), "forecast").await;
+        }
+        for n in 0..14 {
+            let desc = format!("Finished {n}");
+            server
+                .run(&["log", &desc, "project:forecast"])
+                .await
+                .unwrap();
+        }
 
         let result = server
-            .search_tasks(Parameters(SearchTasksRequest {
-                pattern: "needle".to_string(),
-                project: "proj-a".to_string(),
-                filter: None,
-                all_projects: None,
+            .estimate_completion_date(Parameters(EstimateCompletionRequest {
+                project: "forecast".into(),
+                velocity_window_days: Some(7),
             }))
             .await
             .unwrap();
-
-        let out = text_of(&result);
+        let expected = Local::now().date_naive() + chrono::Days::new(5);
+        let text = text_of(&result);
         assert!(
-            out.contains("needle in project A"),
-            "should find proj-a task"
+            text.starts_with(&format!(
+                "Estimated completion of forecast: {expected} (in 5 day(s))."
+            )),
+            "{text}"
         );
         assert!(
-            !out.contains("needle in project B"),
-            "should not leak proj-b results"
+            text.contains("10 pending task(s); velocity 2.0 tasks/day"),
+            "{text}"
         );
     }
 
-    // ── get_task ──────────────────────────────────────────────────────────────
+    #[tokio::test]
+    async fn test_estimate_completion_date_validates_project() {
+        let (_dir, server) = test_server();
+        let err = server
+            .estimate_completion_date(Parameters(EstimateCompletionRequest {
+                project: String::new(),
+                velocity_window_days: None,
+            }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, ErrorCode::INVALID_PARAMS);
+    }
+
+    // ── recurring_task_status ─────────────────────────────────────────────────
+
+    #[test]
+    fn test_advance_recurrence() {
+        let start = parse_tw_date("20250131T090000Z").unwrap();
+        let at = |periods, recur| advance_recurrence(start, recur, periods).map(|d| d.to_rfc3339());
+        assert_eq!(at(2, "daily").as_deref(), Some("2025-02-02T09:00:00+00:00"));
+        assert_eq!(at(1, "2w").as_deref(), Some("2025-02-14T09:00:00+00:00"));
+        // Months clamp to the end of shorter months, as Taskwarrior does.
+        assert_eq!(
+            at(1, "monthly").as_deref(),
+            Some("2025-02-28T09:00:00+00:00")
+        );
+        assert_eq!(
+            at(1, "yearly").as_deref(),
+            Some("2026-01-31T09:00:00+00:00")
+        );
+        assert_eq!(
+            at(0, "weekly").as_deref(),
+            Some("2025-01-31T09:00:00+00:00")
+        );
+        assert_eq!(at(1, "weekdays"), None);
+    }
 
     #[tokio::test]
-    async fn test_get_task_returns_details() {
+    async fn test_recurring_task_status_reports_next_due() {
         let (_dir, server) = test_server();
-        let id = add_task(&server, "Fetch me by ID", "get-test").await;
+        let out = server
+            .run_add(&[
+                "add".into(),
+                "Water plants".into(),
+                "project:Home".into(),
+                "recur:daily".into(),
+                "due:today".into(),
+            ])
+            .await
+            .unwrap();
+        let uuid = created_uuid(&out);
 
         let result = server
-            .get_task(Parameters(TaskIdRequest { id }))
+            .recurring_task_status(Parameters(RecurringStatusRequest { id: uuid }))
             .await
             .unwrap();
+        let text = text_of(&result);
+        assert!(
+            text.starts_with("Recurring task \"Water plants\""),
+            "{text}"
+        );
+        assert!(text.contains("recur: daily"), "{text}");
+        let today = Local::now().date_naive().to_string();
+        assert!(text.contains(&format!("Next due: {today}")), "{text}");
+    }
 
-        assert!(!result.is_error.unwrap_or(false));
-        assert!(text_of(&result).contains("Fetch me by ID"));
+    #[tokio::test]
+    async fn test_recurring_task_status_rejects_plain_task() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "One-off", "Home").await;
+        let err = server
+            .recurring_task_status(Parameters(RecurringStatusRequest { id }))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("not a recurring task"));
     }
 
-    // ── modify_task ───────────────────────────────────────────────────────────
+    // ── mark_all_waiting_as_pending ───────────────────────────────────────────
 
     #[tokio::test]
-    async fn test_modify_task_changes_priority() {
+    async fn test_mark_all_waiting_as_pending() {
         let (_dir, server) = test_server();
-        let id = add_task(&server, "Task to modify", "modify-test").await;
+        for desc in ["Renew passport", "Book dentist"] {
+            let id = add_task(&server, desc, "deferred").await;
+            server.run(&[&id, "modify", "wait:eom+1d"]).await.unwrap();
+        }
+        let listing = || {
+            server.list_tasks(Parameters(ListTasksRequest {
+                project: "deferred".into(),
+                ..Default::default()
+            }))
+        };
+        assert!(!text_of(&listing().await.unwrap()).contains("Renew passport"));
 
         let result = server
-            .modify_task(Parameters(ModifyTaskRequest {
-                id: id.clone(),
-                modifications: "priority:H".to_string(),
+            .mark_all_waiting_as_pending(Parameters(UnwaitAllRequest {
+                project: "deferred".into(),
             }))
             .await
             .unwrap();
+        assert_eq!(text_of(&result), "Released 2 waiting task(s) in deferred.");
+        let listed = listing().await.unwrap();
+        assert!(text_of(&listed).contains("Renew passport"));
+        assert!(text_of(&listed).contains("Book dentist"));
+        let waiting = scoped_filter("deferred", Some("+WAITING"), None);
+        assert_eq!(server.count(&waiting).await.unwrap(), 0);
+    }
+    // ── add_task with project "auto" ──────────────────────────────────────────
 
-        assert!(!result.is_error.unwrap_or(false));
-
-        let info = server
-            .get_task(Parameters(TaskIdRequest { id }))
-            .await
-            .unwrap();
-
-        // taskwarrior shows "H" or "High" in task info
-        let out = text_of(&info);
-        assert!(out.contains('H'), "priority should be H after modify");
+    #[tokio::test]
+    async fn test_add_task_auto_project_uses_active_context() {
+        let dir = TempDir::new().unwrap();
+        let server = TaskWarriorServerBuilder::default()
+            .data_dir(dir.path())
+            .rc_override("context.work", "project:work")
+            .rc_override("context", "work")
+            .build();
+        let id = add_task(&server, "Prepare slides", AUTO_PROJECT).await;
+        let uuid = uuid_of(&server, &id).await;
+        let task = server.export(&[uuid]).await.unwrap();
+        assert_eq!(task[0]["project"], "work");
     }
 
     #[tokio::test]
-    async fn test_modify_task_adds_tag() {
+    async fn test_add_task_auto_project_requires_context() {
         let (_dir, server) = test_server();
-        let id = add_task(&server, "Tag me", "tag-test").await;
-
-        server
-            .modify_task(Parameters(ModifyTaskRequest {
-                id: id.clone(),
-                modifications: "+newtag".to_string(),
+        let err = server
+            .add_task(Parameters(AddTaskRequest {
+                description: "Prepare slides".into(),
+                project: AUTO_PROJECT.into(),
+                due: None,
+                tags: None,
+                priority: None,
+                wait: None,
+                scheduled: None,
             }))
             .await
-            .unwrap();
+            .unwrap_err();
+        assert!(
+            err.message.contains("needs an active Taskwarrior context"),
+            "{}",
+            err.message
+        );
+    }
 
-        let info = server
-            .get_task(Parameters(TaskIdRequest { id }))
-            .await
-            .unwrap();
+    // ── dependency_visualization ──────────────────────────────────────────────
 
-        assert!(text_of(&info).contains("newtag"));
+    #[test]
+    fn test_render_dependency_graph() {
+        let tasks: Vec<serde_json::Value> = serde_json::from_str(
+            r#"[
+                {"uuid": "aaaaaaaa-1", "description": "Ship release", "status": "pending",
+                 "depends": ["bbbbbbbb-2", "cccccccc-3"]},
+                {"uuid": "bbbbbbbb-2", "description": "Write docs", "status": "pending",
+                 "depends": ["dddddddd-4"]},
+                {"uuid": "cccccccc-3", "description": "Fix tests", "status": "pending",
+                 "depends": ["dddddddd-4"]},
+                {"uuid": "dddddddd-4", "description": "Freeze API", "status": "completed"}
+            ]"#,
+        )
+        .unwrap();
+        let expected = [
+            "Ship release [aaaaaaaa]",
+            "├─▶ Write docs [bbbbbbbb]",
+            "│   └─▶ Freeze API [dddddddd] ✓",
+            "└─▶ Fix tests [cccccccc]",
+            "    └─▶ Freeze API [dddddddd] ✓ (see above)",
+        ];
+        assert_eq!(render_dependency_graph(&tasks, None), expected.join("\n"));
+        assert_eq!(
+            render_dependency_graph(&tasks, Some("cccccccc-3")),
+            "Fix tests [cccccccc]\n└─▶ Freeze API [dddddddd] ✓"
+        );
     }
 
-    // ── complete_task ─────────────────────────────────────────────────────────
+    #[test]
+    fn test_render_dependency_graph_caps_depth() {
+        // A chain of eight tasks, each depending on the next.
+        let tasks: Vec<serde_json::Value> = (0..8)
+            .map(|n| {
+                serde_json::json!({
+                    "uuid": format!("{n:08}"),
+                    "description": format!("Step {n}"),
+                    "status": "pending",
+                    "depends": if n < 7 { vec![format!("{:08}", n + 1)] } else { vec![] },
+                })
+            })
+            .collect();
+        let graph = render_dependency_graph(&tasks, None);
+        assert!(graph.contains("Step 5"), "{graph}");
+        assert!(!graph.contains("Step 6"), "{graph}");
+        assert!(graph.ends_with("└─▶ …"), "{graph}");
+    }
 
     #[tokio::test]
-    async fn test_complete_task_removes_from_pending() {
+    async fn test_dependency_visualization() {
         let (_dir, server) = test_server();
-        let id = add_task(&server, "Task to complete", "done-test").await;
+        let mut uuids = Vec::new();
+        for desc in ["Ship release", "Write docs", "Fix tests", "Freeze API"] {
+            let id = add_task(&server, desc, "graph").await;
+            uuids.push(uuid_of(&server, &id).await);
+        }
+        for (task, blocker) in [(0, 1), (0, 2), (1, 3), (2, 3)] {
+            server
+                .run(&[
+                    &uuids[task],
+                    "modify",
+                    &format!("depends:{}", uuids[blocker]),
+                ])
+                .await
+                .unwrap();
+        }
 
         let result = server
-            .complete_task(Parameters(TaskIdRequest { id }))
+            .dependency_visualization(Parameters(DependencyGraphRequest {
+                project: "graph".into(),
+                root_id: None,
+            }))
             .await
             .unwrap();
+        let text = text_of(&result);
+        assert!(text.contains("\nShip release ["), "{text}");
+        assert!(text.contains("─▶ Write docs ["), "{text}");
+        assert!(text.contains("─▶ Fix tests ["), "{text}");
+        assert!(text.contains("─▶ Freeze API ["), "{text}");
+    }
 
-        assert!(!result.is_error.unwrap_or(false));
+    // ── task_notes_to_document ────────────────────────────────────────────────
 
-        // Should no longer appear in the pending list
-        let list = server
-            .list_tasks(Parameters(ListTasksRequest {
-                project: "done-test".to_string(),
-                filter: None,
-                report: Some("list".to_string()),
-                all_projects: None,
-            }))
+    #[test]
+    fn test_notes_document() {
+        let task = serde_json::json!({
+            "description": "Migrate database",
+            "annotations": [
+                {"entry": "20250602T100000Z", "description": "Cut over done"},
+                {"entry": "20250601T090000Z", "description": "Backup taken"}
+            ]
+        });
+        let local = |utc: &str| {
+            parse_tw_date(utc)
+                .unwrap()
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        };
+        let (first, second) = (local("20250601T090000Z"), local("20250602T100000Z"));
+        assert_eq!(
+            notes_document(&task, true),
+            format!(
+                "# Task: Migrate database\n\n## Annotations\n\n### {first}\n\nBackup taken\
+                 \n\n### {second}\n\nCut over done"
+            )
+        );
+        assert_eq!(
+            notes_document(&task, false),
+            format!(
+                "Task: Migrate database\n\nAnnotations\n\n{first}\nBackup taken\
+                 \n\n{second}\nCut over done"
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn test_task_notes_to_document_markdown() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Migrate database", "journal").await;
+        server
+            .run(&[&id, "annotate", "Backup taken"])
             .await
             .unwrap();
 
-        assert!(!text_of(&list).contains("Task to complete"));
+        let result = server
+            .task_notes_to_document(Parameters(NotesToDocumentRequest { id, format: None }))
+            .await
+            .unwrap();
+        let text = text_of(&result);
+        assert!(
+            text.starts_with("# Task: Migrate database\n\n## Annotations"),
+            "{text}"
+        );
+        let today = Local::now().format("%Y-%m-%d").to_string();
+        assert!(text.contains(&format!("\n### {today} ")), "{text}");
+        assert!(text.ends_with("\n\nBackup taken"), "{text}");
     }
 
-    // ── delete_task ───────────────────────────────────────────────────────────
+    // ── get_task_siblings ─────────────────────────────────────────────────────
 
     #[tokio::test]
-    async fn test_delete_task_removes_from_list() {
+    async fn test_get_task_siblings_same_priority() {
         let (_dir, server) = test_server();
-        let id = add_task(&server, "Task to delete", "delete-test").await;
+        let mut ids = Vec::new();
+        for (desc, priority) in [
+            ("Fix outage", "H"),
+            ("Patch CVE", "H"),
+            ("Tidy README", "L"),
+            ("Rename vars", "L"),
+        ] {
+            let id = add_task(&server, desc, "siblings").await;
+            server
+                .run(&[&id, "modify", &format!("priority:{priority}")])
+                .await
+                .unwrap();
+            ids.push(uuid_of(&server, &id).await);
+        }
 
         let result = server
-            .delete_task(Parameters(TaskIdRequest { id }))
+            .get_task_siblings(Parameters(TaskIdRequest { id: ids[0].clone() }))
             .await
             .unwrap();
+        let text = text_of(&result);
+        assert!(text.starts_with("1 sibling(s) of task"), "{text}");
+        assert!(text.contains("at priority H:"), "{text}");
+        assert!(text.contains("Patch CVE"), "{text}");
+        assert!(
+            !text.contains("Tidy README") && !text.contains("Fix outage"),
+            "{text}"
+        );
 
-        assert!(!result.is_error.unwrap_or(false));
-
-        let list = server
-            .list_tasks(Parameters(ListTasksRequest {
-                project: "delete-test".to_string(),
-                filter: None,
-                report: Some("list".to_string()),
-                all_projects: None,
-            }))
+        let low = server
+            .get_task_siblings(Parameters(TaskIdRequest { id: ids[3].clone() }))
             .await
             .unwrap();
-
-        assert!(!text_of(&list).contains("Task to delete"));
+        assert!(text_of(&low).contains("Tidy README"));
     }
 
-    // ── annotate_task ─────────────────────────────────────────────────────────
+    // ── reorder_task_priority ─────────────────────────────────────────────────
 
     #[tokio::test]
-    async fn test_annotate_task_appears_in_info() {
+    async fn test_reorder_task_priority_same_priority() {
         let (_dir, server) = test_server();
-        let id = add_task(&server, "Task to annotate", "annotate-test").await;
+        let first = add_task(&server, "Write report", "reorder").await;
+        let first = uuid_of(&server, &first).await;
+        let second = add_task(&server, "Call plumber", "reorder").await;
+        let second = uuid_of(&server, &second).await;
+        // Give the task we want demoted the edge first.
+        server.run(&[&first, "modify", "+next"]).await.unwrap();
 
-        let result = server
-            .annotate_task(Parameters(AnnotateTaskRequest {
-                id: id.clone(),
-                note: "Important context note xyzzy".to_string(),
+        server
+            .reorder_task_priority(Parameters(ReorderRequest {
+                higher_id: second.clone(),
+                lower_id: first.clone(),
             }))
             .await
             .unwrap();
+        let urgency = |tasks: Vec<serde_json::Value>| tasks[0]["urgency"].as_f64().unwrap();
+        let higher = urgency(server.export(&[second]).await.unwrap());
+        let lower = urgency(server.export(&[first]).await.unwrap());
+        assert!(higher > lower, "{higher} <= {lower}");
+    }
 
-        assert!(!result.is_error.unwrap_or(false));
+    #[tokio::test]
+    async fn test_reorder_task_priority_swaps_priorities() {
+        let (_dir, server) = test_server();
+        let low = add_task(&server, "Low task", "reorder").await;
+        let low = uuid_of(&server, &low).await;
+        let high = add_task(&server, "High task", "reorder").await;
+        let high = uuid_of(&server, &high).await;
+        server.run(&[&low, "modify", "priority:L"]).await.unwrap();
+        server.run(&[&high, "modify", "priority:H"]).await.unwrap();
 
-        let info = server
-            .get_task(Parameters(TaskIdRequest { id }))
+        let result = server
+            .reorder_task_priority(Parameters(ReorderRequest {
+                higher_id: low.clone(),
+                lower_id: high.clone(),
+            }))
             .await
             .unwrap();
+        assert!(text_of(&result).starts_with("Reordered: swapped priorities (H ↔ L)."));
+        assert_eq!(server.export(&[low]).await.unwrap()[0]["priority"], "H");
+        assert_eq!(server.export(&[high]).await.unwrap()[0]["priority"], "L");
+    }
 
-        assert!(text_of(&info).contains("Important context note xyzzy"));
+    // ── check_task_exists ─────────────────────────────────────────────────────
+
+    #[tokio::test]
+    async fn test_check_task_exists() {
+        let (_dir, server) = test_server();
+        let id = add_task(&server, "Existing task", "exists").await;
+        let uuid = uuid_of(&server, &id).await;
+        let check =
+            |id: &str| server.check_task_exists(Parameters(TaskIdRequest { id: id.to_string() }));
+
+        let expected = serde_json::json!({ "exists": true, "uuid": uuid }).to_string();
+        assert_eq!(text_of(&check(&id).await.unwrap()), expected);
+        assert_eq!(text_of(&check(&uuid).await.unwrap()), expected);
+        assert_eq!(text_of(&check("999").await.unwrap()), r#"{"exists":false}"#);
+    }
+
+    #[tokio::test]
+    async fn test_check_task_exists_rejects_non_ids() {
+        let (_dir, server) = test_server();
+        for id in ["", "Existing", "1 or 2", "+READY"] {
+            let result = server
+                .check_task_exists(Parameters(TaskIdRequest { id: id.into() }))
+                .await
+                .unwrap();
+            assert_eq!(text_of(&result), r#"{"exists":false}"#, "{id:?}");
+        }
     }
 }